@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_providers::core::block::block::Block;
+
+fuzz_target!(|data: &[u8]| {
+	let mut reader = Decoder::new(data);
+	if let Ok(block) = Block::decode(&mut reader) {
+		let _ = block.verify_merkle_root();
+		let _ = block.hash();
+	}
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_providers::core::transaction::transaction::Transaction;
+
+fuzz_target!(|data: &[u8]| {
+	let mut reader = Decoder::new(data);
+	let _ = Transaction::decode(&mut reader);
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_providers::core::transaction::witness::Witness;
+
+fuzz_target!(|data: &[u8]| {
+	let mut reader = Decoder::new(data);
+	if let Ok(witness) = Witness::decode(&mut reader) {
+		let _ = witness.verification.get_public_keys();
+		let _ = witness.verification.get_signatures();
+	}
+});
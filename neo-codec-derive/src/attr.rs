@@ -0,0 +1,138 @@
+use syn::{
+	meta::ParseNestedMeta, parse::Parse, Attribute, Error, Expr, Field, LitStr, Path, Result, Type,
+};
+
+/// Attributes on the struct or enum.
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+	pub error: Option<Type>,
+	pub magic: Option<Expr>,
+	pub validate: Option<Path>,
+}
+
+/// How a field is laid out on the wire.
+pub(crate) enum Kind {
+	Serializable,
+	VarBytes,
+	VarString,
+	Fixed(Expr),
+	FixedString(Expr),
+	List,
+	With(Path),
+	Skip,
+}
+
+pub(crate) struct FieldAttrs {
+	pub kind: Kind,
+	pub max: Option<Expr>,
+	pub when: Option<Expr>,
+	pub default: Option<Expr>,
+	pub reserved: Option<Expr>,
+}
+
+#[derive(Default)]
+pub(crate) struct VariantAttrs {
+	pub tag: Option<Expr>,
+}
+
+impl ContainerAttrs {
+	pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|attr| attr.path().is_ident("neo")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("error") {
+					result.error = Some(parse_str(&meta)?);
+				} else if meta.path.is_ident("magic") {
+					result.magic = Some(meta.value()?.parse()?);
+				} else if meta.path.is_ident("validate") {
+					result.validate = Some(parse_str(&meta)?);
+				} else {
+					return Err(meta.error("unknown neo container attribute"))
+				}
+				Ok(())
+			})?;
+		}
+		Ok(result)
+	}
+}
+
+impl FieldAttrs {
+	pub fn parse(field: &Field) -> Result<Self> {
+		let mut kind = None;
+		let mut result =
+			Self { kind: Kind::Serializable, max: None, when: None, default: None, reserved: None };
+
+		for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("neo")) {
+			attr.parse_nested_meta(|meta| {
+				let new_kind = if meta.path.is_ident("var_bytes") {
+					Kind::VarBytes
+				} else if meta.path.is_ident("var_string") {
+					Kind::VarString
+				} else if meta.path.is_ident("fixed") {
+					Kind::Fixed(meta.value()?.parse()?)
+				} else if meta.path.is_ident("fixed_string") {
+					Kind::FixedString(meta.value()?.parse()?)
+				} else if meta.path.is_ident("list") {
+					Kind::List
+				} else if meta.path.is_ident("with") {
+					Kind::With(parse_str(&meta)?)
+				} else if meta.path.is_ident("skip") {
+					Kind::Skip
+				} else {
+					if meta.path.is_ident("max") {
+						result.max = Some(meta.value()?.parse()?);
+					} else if meta.path.is_ident("when") {
+						result.when = Some(parse_str(&meta)?);
+					} else if meta.path.is_ident("default") {
+						result.default = Some(parse_str(&meta)?);
+					} else if meta.path.is_ident("reserved") {
+						result.reserved = Some(meta.value()?.parse()?);
+					} else {
+						return Err(meta.error("unknown neo field attribute"))
+					}
+					return Ok(())
+				};
+				if kind.replace(new_kind).is_some() {
+					return Err(meta.error("a field can only have one encoding"))
+				}
+				Ok(())
+			})?;
+		}
+
+		if let Some(kind) = kind {
+			result.kind = kind;
+		}
+		if result.max.is_some()
+			&& !matches!(result.kind, Kind::VarBytes | Kind::VarString | Kind::List)
+		{
+			return Err(Error::new_spanned(field, "`max` needs `var_bytes`, `var_string` or `list`"))
+		}
+		if result.default.is_some() && result.when.is_none() && !matches!(result.kind, Kind::Skip) {
+			return Err(Error::new_spanned(field, "`default` needs `skip` or `when`"))
+		}
+		Ok(result)
+	}
+}
+
+impl VariantAttrs {
+	pub fn parse(attrs: &[Attribute]) -> Result<Self> {
+		let mut result = Self::default();
+		for attr in attrs.iter().filter(|attr| attr.path().is_ident("neo")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("tag") {
+					result.tag = Some(meta.value()?.parse()?);
+					Ok(())
+				} else {
+					Err(meta.error("unknown neo variant attribute"))
+				}
+			})?;
+		}
+		Ok(result)
+	}
+}
+
+/// Parses a value given as a string literal, e.g. `with = "path::to::module"`.
+fn parse_str<T: Parse>(meta: &ParseNestedMeta) -> Result<T> {
+	let lit: LitStr = meta.value()?.parse()?;
+	lit.parse()
+}
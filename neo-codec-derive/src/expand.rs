@@ -0,0 +1,316 @@
+use crate::attr::{ContainerAttrs, FieldAttrs, Kind, VariantAttrs};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Result, Type};
+
+/// A field of a struct or enum variant, bound to a local variable in the generated code.
+struct Field {
+	binding: Ident,
+	ty: Type,
+	attrs: FieldAttrs,
+}
+
+pub(crate) fn derive(input: &DeriveInput) -> Result<TokenStream> {
+	let name = &input.ident;
+	let container = ContainerAttrs::parse(&input.attrs)?;
+	let error = match &container.error {
+		Some(error) => quote!(#error),
+		None => quote!(::neo_codec::CodecError),
+	};
+
+	let (size, encode, decode) = match &input.data {
+		Data::Struct(data) => derive_struct(&container, &data.fields)?,
+		Data::Enum(data) => {
+			if container.magic.is_some() || container.validate.is_some() {
+				return Err(Error::new_spanned(
+					input,
+					"`magic` and `validate` are only supported on structs",
+				))
+			}
+			derive_enum(name, data)?
+		},
+		Data::Union(_) => return Err(Error::new_spanned(input, "unions are not supported")),
+	};
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::neo_codec::encode::NeoSerializable for #name #ty_generics #where_clause {
+			type Error = #error;
+
+			#[allow(unused_variables)]
+			fn size(&self) -> usize {
+				#size
+			}
+
+			#[allow(unused_variables)]
+			fn encode(&self, __writer: &mut ::neo_codec::Encoder) {
+				#encode
+			}
+
+			#[allow(unused_variables)]
+			fn decode(
+				__reader: &mut ::neo_codec::Decoder,
+			) -> ::core::result::Result<Self, <Self as ::neo_codec::encode::NeoSerializable>::Error> {
+				#decode
+			}
+
+			fn to_array(&self) -> ::std::vec::Vec<u8> {
+				let mut writer = ::neo_codec::Encoder::new();
+				::neo_codec::encode::NeoSerializable::encode(self, &mut writer);
+				writer.to_bytes()
+			}
+		}
+	})
+}
+
+fn derive_struct(
+	container: &ContainerAttrs,
+	fields: &Fields,
+) -> Result<(TokenStream, TokenStream, TokenStream)> {
+	let parsed = parse_fields(fields)?;
+	let pattern = pattern(quote!(Self), fields, &parsed);
+
+	let magic_size = container.magic.as_ref().map(|_| quote!(+ 4));
+	let magic_encode = container.magic.as_ref().map(|magic| quote!(__writer.write_u32(#magic);));
+	let magic_decode = container.magic.as_ref().map(|magic| {
+		quote! {
+			if __reader.read_u32()? != #magic {
+				return Err(::neo_codec::CodecError::InvalidFormat.into())
+			}
+		}
+	});
+	let validate = container.validate.as_ref().map(|validate| quote!(#validate(&__value)?;));
+
+	let sizes = parsed.iter().map(field_size);
+	let encodes = parsed.iter().map(field_encode);
+	let decodes = field_decodes(&parsed);
+
+	let size = quote! {
+		let #pattern = self;
+		0 #magic_size #(+ #sizes)*
+	};
+	let encode = quote! {
+		let #pattern = self;
+		#magic_encode
+		#(#encodes)*
+	};
+	let decode = quote! {
+		#magic_decode
+		#(#decodes)*
+		let __value = #pattern;
+		#validate
+		Ok(__value)
+	};
+	Ok((size, encode, decode))
+}
+
+fn derive_enum(
+	name: &Ident,
+	data: &syn::DataEnum,
+) -> Result<(TokenStream, TokenStream, TokenStream)> {
+	let fieldless = data.variants.iter().all(|variant| variant.fields.is_empty());
+
+	let mut sizes = vec![];
+	let mut encodes = vec![];
+	let mut decodes = vec![];
+	for variant in &data.variants {
+		let ident = &variant.ident;
+		let attrs = VariantAttrs::parse(&variant.attrs)?;
+		let tag = match (attrs.tag, &variant.discriminant) {
+			(Some(tag), _) => quote!(#tag),
+			(None, Some((_, discriminant))) => quote!(#discriminant),
+			(None, None) if fieldless => quote!(#name::#ident as u8),
+			(None, None) =>
+				return Err(Error::new_spanned(variant, "variant needs a `#[neo(tag = ...)]`")),
+		};
+
+		let parsed = parse_fields(&variant.fields)?;
+		let pattern = pattern(quote!(#name::#ident), &variant.fields, &parsed);
+		let field_sizes = parsed.iter().map(field_size);
+		let field_encodes = parsed.iter().map(field_encode);
+		let field_decodes = field_decodes(&parsed);
+
+		sizes.push(quote!(#pattern => 1 #(+ #field_sizes)*,));
+		encodes.push(quote! {
+			#pattern => {
+				__writer.write_u8(#tag);
+				#(#field_encodes)*
+			},
+		});
+		decodes.push(quote! {
+			if __tag == #tag {
+				#(#field_decodes)*
+				return Ok(#pattern)
+			}
+		});
+	}
+
+	let size = quote! {
+		match self {
+			#(#sizes)*
+		}
+	};
+	let encode = quote! {
+		match self {
+			#(#encodes)*
+		}
+	};
+	let decode = quote! {
+		let __tag = __reader.read_u8()?;
+		#(#decodes)*
+		Err(::neo_codec::CodecError::InvalidFormat.into())
+	};
+	Ok((size, encode, decode))
+}
+
+fn parse_fields(fields: &Fields) -> Result<Vec<Field>> {
+	fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| {
+			let binding = match &field.ident {
+				Some(ident) => ident.clone(),
+				None => format_ident!("__field{}", index),
+			};
+			Ok(Field { binding, ty: field.ty.clone(), attrs: FieldAttrs::parse(field)? })
+		})
+		.collect()
+}
+
+/// Builds `Path { a, b }`, `Path(__field0, __field1)` or `Path`, used both to destructure and
+/// to construct the value.
+fn pattern(path: TokenStream, fields: &Fields, parsed: &[Field]) -> TokenStream {
+	let bindings = parsed.iter().map(|field| &field.binding);
+	match fields {
+		Fields::Named(_) => quote!(#path { #(#bindings),* }),
+		Fields::Unnamed(_) => quote!(#path ( #(#bindings),* )),
+		Fields::Unit => path,
+	}
+}
+
+fn field_size(field: &Field) -> TokenStream {
+	let binding = &field.binding;
+	let size = match &field.attrs.kind {
+		Kind::Serializable => quote!(::neo_codec::encode::NeoSerializable::size(#binding)),
+		Kind::VarBytes | Kind::VarString =>
+			quote!(::neo_codec::encode::var_bytes_size(#binding.len())),
+		Kind::Fixed(length) | Kind::FixedString(length) => quote!((#length)),
+		Kind::List => quote! {
+			(::neo_codec::encode::var_int_size(#binding.len())
+				+ #binding.iter().map(::neo_codec::encode::NeoSerializable::size).sum::<usize>())
+		},
+		Kind::With(path) => quote!(#path::size(#binding)),
+		Kind::Skip => quote!(0),
+	};
+	let size = match &field.attrs.when {
+		Some(when) => quote!((if #when { #size } else { 0 })),
+		None => size,
+	};
+	match &field.attrs.reserved {
+		Some(reserved) => quote!((#reserved) + #size),
+		None => size,
+	}
+}
+
+fn field_encode(field: &Field) -> TokenStream {
+	let binding = &field.binding;
+	let encode = match &field.attrs.kind {
+		Kind::Serializable =>
+			quote!(::neo_codec::encode::NeoSerializable::encode(#binding, __writer);),
+		Kind::VarBytes => quote!(__writer.write_var_bytes(#binding);),
+		Kind::VarString => quote!(__writer.write_var_string(#binding);),
+		Kind::Fixed(length) => quote! {
+			__writer.write_fixed_bytes(::core::convert::AsRef::<[u8]>::as_ref(#binding), #length);
+		},
+		Kind::FixedString(length) => quote! {
+			__writer.write_fixed_bytes(
+				::neo_codec::encode::FixedString::fixed_str(#binding).as_bytes(),
+				#length,
+			);
+		},
+		Kind::List => quote!(__writer.write_serializable_variable_list(#binding);),
+		Kind::With(path) => quote!(#path::encode(#binding, __writer);),
+		Kind::Skip => quote!(),
+	};
+	let encode = match &field.attrs.when {
+		Some(when) => quote!(if #when { #encode }),
+		None => encode,
+	};
+	let reserved = field
+		.attrs
+		.reserved
+		.as_ref()
+		.map(|reserved| quote!(__writer.write_bytes(&[0u8; #reserved]);));
+	quote! {
+		#reserved
+		#encode
+	}
+}
+
+fn field_decodes(fields: &[Field]) -> Vec<TokenStream> {
+	fields
+		.iter()
+		.enumerate()
+		.map(|(index, field)| field_decode(field, &fields[..index]))
+		.collect()
+}
+
+/// Decodes `field`. Earlier fields are in scope by reference, as they are when encoding.
+fn field_decode(field: &Field, previous: &[Field]) -> TokenStream {
+	let Field { binding, ty, attrs } = field;
+	let previous = previous.iter().map(|field| &field.binding);
+	let default = match &attrs.default {
+		Some(default) => quote!(#default),
+		None => quote!(::core::default::Default::default()),
+	};
+	let decode = match &attrs.kind {
+		Kind::Serializable => quote!(__reader.read_serializable::<#ty>()?),
+		Kind::VarBytes => {
+			let max = attrs
+				.max
+				.as_ref()
+				.map_or(quote!(::neo_codec::MAX_VAR_BYTES_LENGTH), |max| quote!(#max));
+			quote!(__reader.read_var_bytes_max(#max)?)
+		},
+		Kind::VarString => {
+			let max = attrs
+				.max
+				.as_ref()
+				.map_or(quote!(::neo_codec::MAX_VAR_BYTES_LENGTH), |max| quote!(#max));
+			quote!(__reader.read_var_string_max(#max)?)
+		},
+		Kind::Fixed(length) => quote! {
+			::core::convert::TryFrom::try_from(__reader.read_bytes(#length)?)
+				.map_err(|_| ::neo_codec::CodecError::InvalidFormat)?
+		},
+		Kind::FixedString(length) => quote! {
+			::neo_codec::encode::FixedString::from_fixed_bytes(__reader.read_bytes(#length)?)?
+		},
+		Kind::List => {
+			let max = attrs
+				.max
+				.as_ref()
+				.map_or(quote!(::neo_codec::MAX_LIST_LENGTH), |max| quote!(#max));
+			quote!(__reader.read_serializable_list_max(#max)?)
+		},
+		Kind::With(path) => quote!(#path::decode(__reader)?),
+		Kind::Skip => default.clone(),
+	};
+	let decode = match &attrs.when {
+		Some(when) => quote! {
+			if { #(let #previous = &#previous;)* #when } { #decode } else { #default }
+		},
+		None => decode,
+	};
+	let reserved = attrs.reserved.as_ref().map(|reserved| {
+		quote! {
+			if __reader.read_bytes(#reserved)?.iter().any(|byte| *byte != 0) {
+				return Err(::neo_codec::CodecError::InvalidFormat.into())
+			}
+		}
+	});
+	quote! {
+		#reserved
+		let #binding: #ty = #decode;
+	}
+}
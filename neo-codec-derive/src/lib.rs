@@ -0,0 +1,54 @@
+//! Derive macro for `neo_codec::encode::NeoSerializable`.
+//!
+//! Fields are written in declaration order and `size()` is computed from the same layout, so the
+//! three methods can't drift apart. Without attributes a field is encoded with its own
+//! `NeoSerializable` implementation. The `#[neo(...)]` field attributes change that:
+//!
+//! - `var_bytes`: a `Vec<u8>` prefixed with its var-int length.
+//! - `var_string`: a `String` prefixed with its var-int length.
+//! - `fixed = N`: exactly `N` bytes, for `[u8; N]` or `Vec<u8>`.
+//! - `fixed_string = N`: a zero padded string of `N` bytes, for `String` or `Option<String>`.
+//! - `list`: a `Vec<T>` of serializable items prefixed with its var-int count.
+//! - `max = N`: the maximum length of a `var_bytes` or `var_string`, or count of a `list`.
+//! - `with = "path"`: encoded with `path::size`, `path::encode` and `path::decode`.
+//! - `when = "expr"`: only present on the wire if `expr` holds. `expr` can use earlier fields.
+//! - `skip`: not present on the wire.
+//! - `default = "expr"`: value of a `skip` or absent `when` field, `Default::default()` otherwise.
+//! - `reserved = N`: `N` zero bytes precede the field.
+//!
+//! On the container:
+//!
+//! - `error = "Type"`: the `NeoSerializable::Error`, which needs `From<CodecError>`. Defaults to
+//!   `CodecError`.
+//! - `magic = N`: a little-endian `u32` that precedes the fields.
+//! - `validate = "path"`: called with the decoded value, returning `Result<(), Self::Error>`.
+//!
+//! Enum variants are prefixed with a one byte tag, given by `#[neo(tag = N)]` or the variant's
+//! discriminant.
+//!
+//! ```ignore
+//! #[derive(NeoSerializable)]
+//! #[neo(error = "TransactionError")]
+//! pub struct Signer {
+//!     account: H160,
+//!     #[neo(with = "witness_scope_flags")]
+//!     scopes: Vec<WitnessScope>,
+//!     #[neo(list, max = 16, when = "scopes.contains(&WitnessScope::CustomContracts)")]
+//!     allowed_contracts: Vec<H160>,
+//! }
+//! ```
+
+mod attr;
+mod expand;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+#[proc_macro_derive(NeoSerializable, attributes(neo))]
+pub fn derive_neo_serializable(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match expand::derive(&input) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
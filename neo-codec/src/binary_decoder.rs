@@ -1,29 +1,36 @@
 use crate::encode::NeoSerializable;
 /// This module provides a binary decoder that can read various types of data from a byte slice.
 ///
+/// All integers are read as little-endian, like the Neo node writes them. Reads never panic:
+/// running out of data, non-canonical var-ints and lengths above the configured maximum are
+/// reported as a [`CodecError`], so payloads received from peers can be decoded safely.
+///
 /// # Examples
 ///
 /// ```
 ///
 /// use neo_codec::Decoder;
-/// let data = [0x01, 0x02, 0x03, 0x04];
+/// let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x03, 0x61, 0x62, 0x63];
 /// let mut decoder = Decoder::new(&data);
 ///
-/// assert_eq!(decoder.read_bool(), true);
-/// assert_eq!(decoder.read_u8(), 2);
-/// assert_eq!(decoder.read_u16(), 0x0403);
-/// assert_eq!(decoder.read_i16(), 0x0403);
-/// assert_eq!(decoder.read_u32(), 0x04030201);
-/// assert_eq!(decoder.read_i32(), 0x04030201);
-/// assert_eq!(decoder.read_u64(), 0x0807060504030201);
-/// assert_eq!(decoder.read_i64(), 0x0807060504030201);
+/// assert_eq!(decoder.read_bool().unwrap(), true);
+/// assert_eq!(decoder.read_u16().unwrap(), 0x0302);
+/// assert_eq!(decoder.read_u32().unwrap(), 0x07060504);
+/// assert_eq!(decoder.read_var_string().unwrap(), "abc");
+/// assert!(decoder.read_u8().is_err());
 /// ```
 use crate::CodecError;
 use getset::{Getters, Setters};
-use num_bigint::{BigInt, Sign};
+use num_bigint::BigInt;
 use serde::Deserialize;
 use serde_derive::Serialize;
 
+/// Default maximum length of var-bytes and var-strings, the same as the Neo node uses.
+pub const MAX_VAR_BYTES_LENGTH: usize = 0x1000000;
+
+/// Default maximum number of items in a var-int prefixed list, the same as the Neo node uses.
+pub const MAX_LIST_LENGTH: usize = 0x1000000;
+
 /// A binary decoder that can read various types of data from a byte slice.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, Getters, Setters)]
 pub struct Decoder<'a> {
@@ -54,120 +61,157 @@ impl<'a> Decoder<'a> {
 		Self { data, pointer: 0, marker: 0 }
 	}
 
-	/// Reads a boolean value from the byte slice.
-	pub fn read_bool(&mut self) -> bool {
-		let val = self.data[self.pointer] == 1;
-		self.pointer += 1;
-		val
+	/// Reads a boolean value from the byte slice, anything else than `0` or `1` is an error.
+	pub fn read_bool(&mut self) -> Result<bool, CodecError> {
+		match self.read_u8()? {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(CodecError::InvalidFormat),
+		}
 	}
 
 	/// Reads an unsigned 8-bit integer from the byte slice.
-	pub fn read_u8(&mut self) -> u8 {
-		let val = self.data[self.pointer];
+	pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+		let val = *self.data.get(self.pointer).ok_or_else(Self::eof)?;
 		self.pointer += 1;
-		val
+		Ok(val)
 	}
 
-	/// Reads an unsigned 16-bit integer from the byte slice.
-	pub fn read_u16(&mut self) -> u16 {
-		let bytes = self.read_bytes(2).unwrap();
-		u16::from_ne_bytes(bytes.try_into().unwrap())
+	/// Reads a little-endian unsigned 16-bit integer from the byte slice.
+	pub fn read_u16(&mut self) -> Result<u16, CodecError> {
+		Ok(u16::from_le_bytes(self.read_array()?))
 	}
 
-	/// Reads a signed 16-bit integer from the byte slice.
-	pub fn read_i16(&mut self) -> i16 {
-		let bytes = self.read_bytes(2).unwrap();
-		i16::from_ne_bytes(bytes.try_into().unwrap())
+	/// Reads a little-endian signed 16-bit integer from the byte slice.
+	pub fn read_i16(&mut self) -> Result<i16, CodecError> {
+		Ok(i16::from_le_bytes(self.read_array()?))
 	}
 
-	/// Reads an unsigned 32-bit integer from the byte slice.
-	pub fn read_u32(&mut self) -> u32 {
-		let bytes = self.read_bytes(4).unwrap();
-		u32::from_ne_bytes(bytes.try_into().unwrap())
+	/// Reads a little-endian unsigned 32-bit integer from the byte slice.
+	pub fn read_u32(&mut self) -> Result<u32, CodecError> {
+		Ok(u32::from_le_bytes(self.read_array()?))
 	}
 
-	/// Reads a signed 32-bit integer from the byte slice.
-	pub fn read_i32(&mut self) -> i32 {
-		let bytes = self.read_bytes(4).unwrap();
-		i32::from_ne_bytes(bytes.try_into().unwrap())
+	/// Reads a little-endian signed 32-bit integer from the byte slice.
+	pub fn read_i32(&mut self) -> Result<i32, CodecError> {
+		Ok(i32::from_le_bytes(self.read_array()?))
 	}
 
-	/// Reads an unsigned 64-bit integer from the byte slice.
-	pub fn read_u64(&mut self) -> u64 {
-		let bytes = self.read_bytes(8).unwrap();
-		u64::from_ne_bytes(bytes.try_into().unwrap())
+	/// Reads a little-endian unsigned 64-bit integer from the byte slice.
+	pub fn read_u64(&mut self) -> Result<u64, CodecError> {
+		Ok(u64::from_le_bytes(self.read_array()?))
 	}
-	pub fn read_i64(&mut self) -> i64 {
-		let bytes = self.read_bytes(8).unwrap();
-		i64::from_ne_bytes(bytes.try_into().unwrap())
+
+	/// Reads a little-endian signed 64-bit integer from the byte slice.
+	pub fn read_i64(&mut self) -> Result<i64, CodecError> {
+		Ok(i64::from_le_bytes(self.read_array()?))
 	}
 
+	/// Reads a big integer prefixed with its length, stored as little-endian two's complement.
 	pub fn read_bigint(&mut self) -> Result<BigInt, CodecError> {
-		let byte = self.read_u8();
-
-		let negative = byte & 0x80 != 0;
+		let byte = self.read_u8()?;
 		let len = match byte {
-			0..=0x4b => 1,
-			0x4c => self.read_u8() as usize,
-			0x4d => self.read_u16() as usize,
-			0x4e => self.read_u32() as usize,
+			0..=0x4b => byte as usize,
+			0x4c => self.read_u8()? as usize,
+			0x4d => self.read_u16()? as usize,
+			0x4e => self.read_u32()? as usize,
 			_ => return Err(CodecError::InvalidFormat),
 		};
 
-		let bytes = self.read_bytes(len).unwrap();
-		if negative {
-			// Flip sign bit
-			if let Some(byte) = bytes.to_owned().get_mut(len - 1) {
-				*byte ^= 0x80;
-			} else {
-				return Err(CodecError::InvalidFormat)
-			}
-			// bytes.get_mut()[len - 1] ^= 0x80;
+		let bytes = self.read_bytes(len)?;
+		if bytes.is_empty() {
+			return Ok(BigInt::from(0))
 		}
-		//TODO:: need to check be or le and sign
-		Ok(BigInt::from_bytes_be(Sign::Minus, &bytes))
+		Ok(BigInt::from_signed_bytes_le(&bytes))
 	}
 
-	/// Reads an encoded EC point from the byte slice.
-	pub fn read_encoded_ec_point(&mut self) -> Result<Vec<u8>, &'static str> {
-		let byte = self.read_u8();
+	/// Reads a compressed encoded EC point (prefix `0x02` or `0x03` and the 32 byte X coordinate)
+	/// from the byte slice.
+	pub fn read_encoded_ec_point(&mut self) -> Result<Vec<u8>, CodecError> {
+		let byte = self.read_u8()?;
 		match byte {
-			0x02 | 0x03 => Ok(self.read_bytes(32).unwrap()),
-			_ => Err("Invalid encoded EC point"),
+			0x02 | 0x03 => {
+				let mut point = vec![byte];
+				point.extend(self.read_bytes(32)?);
+				Ok(point)
+			},
+			_ => Err(CodecError::InvalidEncoding("Invalid encoded EC point".to_string())),
 		}
 	}
 
 	/// Reads a byte slice of the given length from the byte slice.
 	pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, CodecError> {
-		if self.pointer + length > self.data.len() {
-			return Err(CodecError::IndexOutOfBounds("Read beyond end of buffer".to_string()))
+		match self.pointer.checked_add(length) {
+			Some(end) if end <= self.data.len() => {
+				let result = self.data[self.pointer..end].to_vec();
+				self.pointer = end;
+				Ok(result)
+			},
+			_ => Err(Self::eof()),
 		}
-		let result = self.data[self.pointer..self.pointer + length].to_vec();
-		self.pointer += length;
-		Ok(result)
 	}
 
-	/// Reads a variable-length byte slice from the byte slice.
+	/// Reads a variable-length byte slice of at most [`MAX_VAR_BYTES_LENGTH`] bytes.
 	pub fn read_var_bytes(&mut self) -> Result<Vec<u8>, CodecError> {
-		let len = self.read_var_int().unwrap() as usize;
+		self.read_var_bytes_max(MAX_VAR_BYTES_LENGTH)
+	}
+
+	/// Reads a variable-length byte slice of at most `max` bytes.
+	pub fn read_var_bytes_max(&mut self, max: usize) -> Result<Vec<u8>, CodecError> {
+		let len = self.read_var_int_max(max as u64)? as usize;
 		self.read_bytes(len)
 	}
 
 	/// Reads a variable-length integer from the byte slice.
-	pub fn read_var_int(&mut self) -> Result<i64, CodecError> {
-		let first = self.read_u8();
-		match first {
-			0xfd => Ok(self.read_u16() as i64),
-			0xfe => Ok(self.read_u32() as i64),
-			0xff => Ok(self.read_u64() as i64),
-			_ => Ok(first as i64),
+	pub fn read_var_int(&mut self) -> Result<u64, CodecError> {
+		self.read_var_int_max(u64::MAX)
+	}
+
+	/// Reads a variable-length integer that must not exceed `max`.
+	///
+	/// Like the node, values that would fit a shorter encoding are rejected.
+	pub fn read_var_int_max(&mut self, max: u64) -> Result<u64, CodecError> {
+		let first = self.read_u8()?;
+		let value = match first {
+			0xfd => {
+				let value = self.read_u16()? as u64;
+				if value < 0xfd {
+					return Err(CodecError::InvalidFormat)
+				}
+				value
+			},
+			0xfe => {
+				let value = self.read_u32()? as u64;
+				if value <= 0xffff {
+					return Err(CodecError::InvalidFormat)
+				}
+				value
+			},
+			0xff => {
+				let value = self.read_u64()?;
+				if value <= 0xffffffff {
+					return Err(CodecError::InvalidFormat)
+				}
+				value
+			},
+			_ => first as u64,
+		};
+		if value > max {
+			return Err(CodecError::ExceedsMaxLength(value, max))
 		}
+		Ok(value)
 	}
 
+	/// Reads a variable-length UTF-8 string of at most [`MAX_VAR_BYTES_LENGTH`] bytes.
 	pub fn read_var_string(&mut self) -> Result<String, CodecError> {
-		let bytes = self.read_var_bytes().unwrap();
+		self.read_var_string_max(MAX_VAR_BYTES_LENGTH)
+	}
+
+	/// Reads a variable-length UTF-8 string of at most `max` bytes.
+	pub fn read_var_string_max(&mut self, max: usize) -> Result<String, CodecError> {
+		let bytes = self.read_var_bytes_max(max)?;
 
-		let string = match String::from_utf8(bytes.to_vec()) {
+		let string = match String::from_utf8(bytes) {
 			Ok(s) => s,
 			Err(e) => {
 				// Handle invalid UTF-8
@@ -183,12 +227,12 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a push byte slice from the byte slice.
 	pub fn read_push_bytes(&mut self) -> Result<Vec<u8>, CodecError> {
-		let opcode = self.read_u8();
+		let opcode = self.read_u8()?;
 		let len = match opcode {
-			0x01..=0x4B => opcode as usize,
-			0x4C => self.read_u8() as usize,
-			0x4D => self.read_u16() as usize,
-			0x4E => self.read_u32() as usize,
+			// PUSHDATA1, PUSHDATA2, PUSHDATA4
+			0x0c => self.read_u8()? as usize,
+			0x0d => self.read_u16()? as usize,
+			0x0e => self.read_u32()? as usize,
 			_ => return Err(CodecError::InvalidOpCode),
 		};
 
@@ -196,63 +240,63 @@ impl<'a> Decoder<'a> {
 	}
 
 	/// Reads a push integer from the byte slice.
-	pub fn read_push_int(&mut self) -> Result<i64, CodecError> {
-		let opcode = self.read_u8();
-		match opcode {
-			0x00..=0x16 => Ok(opcode as i64 - 1),
-			0x01..=0x04 => {
-				let n = match opcode {
-					0x51 => 1,
-					0x52 => 2,
-					0x53 => 4,
-					0x54 => 8,
-					_ => {
-						panic!("Invalid opcode")
-					},
-				};
-				let bytes = self.read_bytes(n).unwrap();
-				Ok(i64::from_be_bytes(bytes.try_into().unwrap()))
-			},
-			_ => Err(CodecError::InvalidOpCode),
-		}
+	pub fn read_push_int(&mut self) -> Result<BigInt, CodecError> {
+		let opcode = self.read_u8()?;
+		let len = match opcode {
+			// PUSHINT8 to PUSHINT256
+			0x00..=0x05 => 1 << opcode,
+			// PUSHM1 to PUSH16
+			0x0f..=0x20 => return Ok(BigInt::from(opcode as i32 - 0x10)),
+			_ => return Err(CodecError::InvalidOpCode),
+		};
+		let bytes = self.read_bytes(len)?;
+		Ok(BigInt::from_signed_bytes_le(&bytes))
 	}
 
 	/// Reads a push string from the byte slice.
 	pub fn read_push_string(&mut self) -> Result<String, CodecError> {
-		let bytes = self.read_push_bytes().unwrap();
-		String::from_utf8(Vec::from(bytes))
+		let bytes = self.read_push_bytes()?;
+		String::from_utf8(bytes)
 			.map_err(|_| CodecError::InvalidEncoding("Invalid UTF-8".to_string()))
 	}
 
 	/// Reads a deserializable value from the byte slice.
 	pub fn read_serializable<T: NeoSerializable>(&mut self) -> Result<T, CodecError> {
-		T::decode(self).map_err(|_e| CodecError::InvalidFormat)
+		T::decode(self).map_err(|e| CodecError::InvalidData(format!("{:?}", e)))
 	}
 
-	/// Reads a list of deserializable values from the byte slice.
+	/// Reads a list of at most [`MAX_LIST_LENGTH`] deserializable values from the byte slice.
 	pub fn read_serializable_list<T: NeoSerializable>(&mut self) -> Result<Vec<T>, CodecError> {
-		let len = self.read_var_int().unwrap();
-		let mut list = Vec::with_capacity(len as usize);
+		self.read_serializable_list_max(MAX_LIST_LENGTH)
+	}
+
+	/// Reads a list of at most `max` deserializable values from the byte slice.
+	pub fn read_serializable_list_max<T: NeoSerializable>(
+		&mut self,
+		max: usize,
+	) -> Result<Vec<T>, CodecError> {
+		let len = self.read_var_int_max(max as u64)? as usize;
+		// every item takes at least one byte, don't trust the length to allocate
+		let mut list = Vec::with_capacity(len.min(self.available()));
 		for _ in 0..len {
-			T::decode(self)
-				.and_then(|item| Ok(list.push(item)))
-				.expect("TODO: panic message");
+			list.push(self.read_serializable()?);
 		}
 		Ok(list)
 	}
 
+	/// Reads a list of deserializable values prefixed with their total size in bytes.
 	pub fn read_serializable_list_var_bytes<T: NeoSerializable>(
 		&mut self,
 	) -> Result<Vec<T>, CodecError> {
-		let len = self.read_var_int().unwrap();
-		let mut bytes_read = 0;
-		let mut offset = self.pointer;
-		let mut list = Vec::with_capacity(len as usize);
-		while bytes_read < len {
-			T::decode(self)
-				.and_then(|item| Ok(list.push(item)))
-				.expect("TODO: panic message");
-			bytes_read = (self.pointer - offset) as i64;
+		let len = self.read_var_int_max(MAX_VAR_BYTES_LENGTH as u64)? as usize;
+		let end = self.pointer.checked_add(len).filter(|end| *end <= self.data.len());
+		let end = end.ok_or_else(Self::eof)?;
+		let mut list = vec![];
+		while self.pointer < end {
+			list.push(self.read_serializable()?);
+		}
+		if self.pointer != end {
+			return Err(CodecError::InvalidFormat)
 		}
 		Ok(list)
 	}
@@ -282,6 +326,132 @@ impl<'a> Decoder<'a> {
 	// }
 
 	pub fn available(&self) -> usize {
-		self.data.len() - self.pointer
+		self.data.len().saturating_sub(self.pointer)
+	}
+
+	fn read_array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+		let end = self.pointer.checked_add(N).filter(|end| *end <= self.data.len());
+		let end = end.ok_or_else(Self::eof)?;
+		let mut array = [0u8; N];
+		array.copy_from_slice(&self.data[self.pointer..end]);
+		self.pointer = end;
+		Ok(array)
+	}
+
+	fn eof() -> CodecError {
+		CodecError::IndexOutOfBounds("Read beyond end of buffer".to_string())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Encoder;
+
+	#[test]
+	fn test_read_little_endian() {
+		let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+		assert_eq!(Decoder::new(&data).read_u16().unwrap(), 0x0201);
+		assert_eq!(Decoder::new(&data).read_u32().unwrap(), 0x04030201);
+		assert_eq!(Decoder::new(&data).read_u64().unwrap(), 0x0807060504030201);
+		assert_eq!(Decoder::new(&[0xff, 0xff]).read_i16().unwrap(), -1);
+		assert_eq!(Decoder::new(&[0xfe, 0xff, 0xff, 0xff]).read_i32().unwrap(), -2);
+	}
+
+	#[test]
+	fn test_short_input() {
+		assert!(Decoder::new(&[]).read_u8().is_err());
+		assert!(Decoder::new(&[]).read_bool().is_err());
+		assert!(Decoder::new(&[0x01]).read_u16().is_err());
+		assert!(Decoder::new(&[0x01, 0x02, 0x03]).read_u32().is_err());
+		assert!(Decoder::new(&[0x01; 7]).read_i64().is_err());
+		assert!(Decoder::new(&[0x05, 0x01]).read_var_bytes().is_err());
+		assert!(Decoder::new(&[0xfd, 0x01]).read_var_int().is_err());
+		assert!(Decoder::new(&[0x02]).read_encoded_ec_point().is_err());
+		assert!(Decoder::new(&[0x4d, 0x01]).read_bigint().is_err());
+
+		let mut decoder = Decoder::new(&[0x01, 0x02]);
+		assert!(decoder.read_bytes(usize::MAX).is_err());
+		assert_eq!(decoder.read_u16().unwrap(), 0x0201);
+	}
+
+	#[test]
+	fn test_read_var_int() {
+		assert_eq!(Decoder::new(&[0xfc]).read_var_int().unwrap(), 0xfc);
+		assert_eq!(Decoder::new(&[0xfd, 0xfd, 0x00]).read_var_int().unwrap(), 0xfd);
+		assert_eq!(Decoder::new(&[0xfe, 0x00, 0x00, 0x01, 0x00]).read_var_int().unwrap(), 0x10000);
+		// non-canonical encodings
+		assert!(Decoder::new(&[0xfd, 0x01, 0x00]).read_var_int().is_err());
+		assert!(Decoder::new(&[0xfe, 0xff, 0xff, 0x00, 0x00]).read_var_int().is_err());
+
+		assert_eq!(
+			Decoder::new(&[0x10]).read_var_int_max(0x0f),
+			Err(CodecError::ExceedsMaxLength(0x10, 0x0f))
+		);
+	}
+
+	#[test]
+	fn test_var_bytes_max_length() {
+		let mut data = vec![0xfe, 0x01, 0x00, 0x00, 0x01];
+		data.extend(vec![0u8; 16]);
+		assert!(matches!(
+			Decoder::new(&data).read_var_bytes(),
+			Err(CodecError::ExceedsMaxLength(0x1000001, _))
+		));
+		assert!(Decoder::new(&[0x03, 0x61, 0x62, 0x63]).read_var_bytes_max(2).is_err());
+		assert_eq!(Decoder::new(&[0x03, 0x61, 0x62, 0x63]).read_var_string_max(3).unwrap(), "abc");
+	}
+
+	#[test]
+	fn test_serializable_list_length() {
+		// claims u32::MAX items but has none
+		let data = [0xfe, 0xff, 0xff, 0xff, 0xff];
+		assert!(Decoder::new(&data).read_serializable_list::<u8>().is_err());
+		assert!(Decoder::new(&[0x02, 0x01]).read_serializable_list::<u8>().is_err());
+		assert_eq!(
+			Decoder::new(&[0x02, 0x01, 0x02]).read_serializable_list::<u8>().unwrap(),
+			vec![1, 2]
+		);
+		assert!(Decoder::new(&[0x02, 0x01, 0x02]).read_serializable_list_max::<u8>(1).is_err());
+	}
+
+	#[test]
+	fn test_read_bool() {
+		assert!(!Decoder::new(&[0x00]).read_bool().unwrap());
+		assert!(Decoder::new(&[0x01]).read_bool().unwrap());
+		assert!(Decoder::new(&[0x02]).read_bool().is_err());
+	}
+
+	#[test]
+	fn test_read_push_int() {
+		assert_eq!(Decoder::new(&[0x0f]).read_push_int().unwrap(), BigInt::from(-1));
+		assert_eq!(Decoder::new(&[0x20]).read_push_int().unwrap(), BigInt::from(16));
+		assert_eq!(Decoder::new(&[0x00, 0xff]).read_push_int().unwrap(), BigInt::from(-1));
+		assert_eq!(Decoder::new(&[0x01, 0x00, 0x01]).read_push_int().unwrap(), BigInt::from(256));
+		assert!(Decoder::new(&[0x02, 0x00]).read_push_int().is_err());
+		assert!(Decoder::new(&[0x40]).read_push_int().is_err());
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let mut encoder = Encoder::new();
+		encoder.write_u16(0x1234);
+		encoder.write_i32(-123456);
+		encoder.write_i64(-2);
+		encoder.write_u64(u64::MAX - 1);
+		encoder.write_var_int(0xfffe);
+		encoder.write_var_int(0x10000);
+		encoder.write_var_bytes(&[1, 2, 3]);
+		let bytes = encoder.to_bytes();
+
+		let mut decoder = Decoder::new(&bytes);
+		assert_eq!(decoder.read_u16().unwrap(), 0x1234);
+		assert_eq!(decoder.read_i32().unwrap(), -123456);
+		assert_eq!(decoder.read_i64().unwrap(), -2);
+		assert_eq!(decoder.read_u64().unwrap(), u64::MAX - 1);
+		assert_eq!(decoder.read_var_int().unwrap(), 0xfffe);
+		assert_eq!(decoder.read_var_int().unwrap(), 0x10000);
+		assert_eq!(decoder.read_var_bytes().unwrap(), vec![1, 2, 3]);
+		assert_eq!(decoder.available(), 0);
 	}
 }
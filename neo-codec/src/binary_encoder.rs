@@ -9,9 +9,9 @@ use crate::{encode::NeoSerializable, CodecError};
 /// let mut encoder = Encoder::new();
 /// encoder.write_u8(0x12);
 /// encoder.write_i32(-123456);
-/// encoder.write_string("hello");
+/// encoder.write_var_string("hello");
 /// let bytes = encoder.to_bytes();
-/// assert_eq!(bytes, vec![0x12, 0x30, 0x71, 0xfe, 0xff, 0xff, 0xff, 0x05, 0x68, 0x65, 0x6c, 0x6c, 0x6f]);
+/// assert_eq!(bytes, vec![0x12, 0xc0, 0x1d, 0xfe, 0xff, 0x05, 0x68, 0x65, 0x6c, 0x6c, 0x6f]);
 /// ```
 use serde::Serialize;
 use serde_derive::Deserialize;
@@ -59,6 +59,10 @@ impl Encoder {
 		self.data.extend_from_slice(&v.to_le_bytes());
 	}
 
+	pub fn write_u64(&mut self, v: u64) {
+		self.data.extend_from_slice(&v.to_le_bytes());
+	}
+
 	pub fn write_bytes(&mut self, bytes: &[u8]) {
 		self.data.extend_from_slice(bytes);
 	}
@@ -66,11 +70,15 @@ impl Encoder {
 	// Other primitive write methods
 	pub fn write_var_int(&mut self, value: i64) {
 		match value {
-			0..=0xfd => self.write_u8(value as u8),
-			0x10000..=0xffffffff => {
+			0..=0xfc => self.write_u8(value as u8),
+			0xfd..=0xffff => {
 				self.write_u8(0xfd);
 				self.write_u16(value as u16);
 			},
+			0x10000..=0xffffffff => {
+				self.write_u8(0xfe);
+				self.write_u32(value as u32);
+			},
 			_ => {
 				self.write_u8(0xff);
 				self.write_u64(value as u64);
@@ -96,6 +104,13 @@ impl Encoder {
 		Ok(self.write_bytes(&padded))
 	}
 
+	/// Writes exactly `length` bytes, truncating `bytes` or padding them with zeros.
+	pub fn write_fixed_bytes(&mut self, bytes: &[u8], length: usize) {
+		let len = bytes.len().min(length);
+		self.write_bytes(&bytes[..len]);
+		self.write_bytes(&vec![0; length - len]);
+	}
+
 	pub fn write_var_bytes(&mut self, bytes: &[u8]) {
 		self.write_var_int(bytes.len() as i64);
 		self.write_bytes(bytes);
@@ -1,4 +1,5 @@
 use crate::{CodecError, Decoder, Encoder};
+pub use neo_codec_derive::NeoSerializable;
 use primitive_types::{H160, H256};
 use std::fmt::Debug;
 
@@ -13,24 +14,30 @@ pub trait NeoSerializable {
 	fn to_array(&self) -> Vec<u8>;
 }
 
+/// Hashes are held in the order of their hex strings in RPC JSON and go on the wire reversed,
+/// little-endian like the node's `UInt160` and `UInt256`.
 impl NeoSerializable for H160 {
 	type Error = CodecError;
 	fn size(&self) -> usize {
 		H160::len_bytes()
 	}
 	fn encode(&self, writer: &mut Encoder) {
-		writer.write_var_bytes(&self.as_bytes());
+		writer.write_bytes(&self.to_array());
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
 	where
 		Self: Sized,
 	{
-		reader.read_var_bytes().map(|bytes| H160::from_slice(&bytes))
+		let mut bytes = reader.read_bytes(H160::len_bytes())?;
+		bytes.reverse();
+		Ok(H160::from_slice(&bytes))
 	}
 
 	fn to_array(&self) -> Vec<u8> {
-		self.as_bytes().to_vec()
+		let mut bytes = self.as_bytes().to_vec();
+		bytes.reverse();
+		bytes
 	}
 }
 
@@ -41,18 +48,22 @@ impl NeoSerializable for H256 {
 		H256::len_bytes()
 	}
 	fn encode(&self, writer: &mut Encoder) {
-		writer.write_var_bytes(&self.as_bytes());
+		writer.write_bytes(&self.to_array());
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, CodecError>
 	where
 		Self: Sized,
 	{
-		reader.read_var_bytes().map(|bytes| H256::from_slice(&bytes))
+		let mut bytes = reader.read_bytes(H256::len_bytes())?;
+		bytes.reverse();
+		Ok(H256::from_slice(&bytes))
 	}
 
 	fn to_array(&self) -> Vec<u8> {
-		self.as_bytes().to_vec()
+		let mut bytes = self.as_bytes().to_vec();
+		bytes.reverse();
+		bytes
 	}
 }
 
@@ -70,7 +81,7 @@ impl NeoSerializable for u8 {
 	where
 		Self: Sized,
 	{
-		Ok(reader.read_u8())
+		reader.read_u8()
 	}
 
 	fn to_array(&self) -> Vec<u8> {
@@ -78,14 +89,111 @@ impl NeoSerializable for u8 {
 	}
 }
 
+macro_rules! impl_neo_serializable_int {
+	($($ty:ty => $write:ident, $read:ident;)*) => {
+		$(
+			impl NeoSerializable for $ty {
+				type Error = CodecError;
+
+				fn size(&self) -> usize {
+					std::mem::size_of::<$ty>()
+				}
+
+				fn encode(&self, writer: &mut Encoder) {
+					writer.$write(*self);
+				}
+
+				fn decode(reader: &mut Decoder) -> Result<Self, CodecError> {
+					reader.$read()
+				}
+
+				fn to_array(&self) -> Vec<u8> {
+					self.to_le_bytes().to_vec()
+				}
+			}
+		)*
+	};
+}
+
+impl_neo_serializable_int! {
+	u16 => write_u16, read_u16;
+	i16 => write_i16, read_i16;
+	u32 => write_u32, read_u32;
+	i32 => write_i32, read_i32;
+	u64 => write_u64, read_u64;
+	i64 => write_i64, read_i64;
+}
+
+impl NeoSerializable for bool {
+	type Error = CodecError;
+
+	fn size(&self) -> usize {
+		1
+	}
+
+	fn encode(&self, writer: &mut Encoder) {
+		writer.write_bool(*self);
+	}
+
+	fn decode(reader: &mut Decoder) -> Result<Self, CodecError> {
+		reader.read_bool()
+	}
+
+	fn to_array(&self) -> Vec<u8> {
+		vec![*self as u8]
+	}
+}
+
+/// Strings stored in a fixed number of zero padded bytes, see `#[neo(fixed_string = N)]`.
+pub trait FixedString: Sized {
+	fn fixed_str(&self) -> &str;
+	fn from_fixed_bytes(bytes: Vec<u8>) -> Result<Self, CodecError>;
+}
+
+impl FixedString for String {
+	fn fixed_str(&self) -> &str {
+		self
+	}
+
+	fn from_fixed_bytes(bytes: Vec<u8>) -> Result<Self, CodecError> {
+		let string =
+			String::from_utf8(bytes).map_err(|e| CodecError::InvalidEncoding(e.to_string()))?;
+		Ok(string.trim_end_matches(char::from(0)).to_string())
+	}
+}
+
+impl FixedString for Option<String> {
+	fn fixed_str(&self) -> &str {
+		self.as_deref().unwrap_or_default()
+	}
+
+	fn from_fixed_bytes(bytes: Vec<u8>) -> Result<Self, CodecError> {
+		String::from_fixed_bytes(bytes).map(Some)
+	}
+}
+
+/// Returns the number of bytes needed to encode `value` as a var-int.
+pub fn var_int_size(value: usize) -> usize {
+	match value {
+		0..=0xfc => 1,
+		0xfd..=0xffff => 3,
+		0x10000..=0xffffffff => 5,
+		_ => 9,
+	}
+}
+
+/// Returns the size of `len` bytes prefixed with their var-int length.
+pub fn var_bytes_size(len: usize) -> usize {
+	var_int_size(len) + len
+}
+
 pub trait VarSizeTrait {
 	fn var_size(&self) -> usize;
 }
 
 impl<T: NeoSerializable> VarSizeTrait for Vec<T> {
 	fn var_size(&self) -> usize {
-		let count_var_size = self.len();
-		count_var_size + self.iter().map(|item| item.size()).sum::<usize>()
+		var_int_size(self.len()) + self.iter().map(|item| item.size()).sum::<usize>()
 	}
 }
 
@@ -100,3 +208,164 @@ impl<T: NeoSerializable> VarSizeTrait for Vec<T> {
 // 	let count_var_size = elements.len();
 // 	count_var_size + elements.iter().map(|item| item.size()).sum::<usize>()
 // }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(NeoSerializable, Debug, PartialEq)]
+	#[neo(magic = 0x01020304, validate = "Self::validate")]
+	struct Example {
+		version: u8,
+		hash: H160,
+		#[neo(var_bytes, max = 4)]
+		script: Vec<u8>,
+		#[neo(fixed_string = 8)]
+		name: Option<String>,
+		#[neo(list, reserved = 1)]
+		items: Vec<u16>,
+		#[neo(var_string, when = "*version > 0")]
+		comment: String,
+		#[neo(fixed = 2)]
+		checksum: [u8; 2],
+		#[neo(skip, default = "checksum[0] as u32")]
+		cached: u32,
+	}
+
+	impl Example {
+		fn validate(&self) -> Result<(), CodecError> {
+			match self.checksum == [0xff, 0xff] {
+				true => Err(CodecError::InvalidFormat),
+				false => Ok(()),
+			}
+		}
+	}
+
+	#[derive(NeoSerializable, Debug, PartialEq)]
+	enum Tagged {
+		#[neo(tag = 0x01)]
+		Unit,
+		#[neo(tag = 0x11)]
+		Tuple(u32, #[neo(var_bytes)] Vec<u8>),
+		#[neo(tag = 0x20)]
+		Named { flag: bool },
+	}
+
+	#[derive(NeoSerializable, Debug, PartialEq, Clone, Copy)]
+	#[repr(u8)]
+	enum Code {
+		Success = 0x00,
+		Error = 0xff,
+	}
+
+	fn example() -> Example {
+		Example {
+			version: 1,
+			hash: H160::repeat_byte(0xab),
+			script: vec![1, 2, 3],
+			name: Some("neo".to_string()),
+			items: vec![0x0102, 0x0304],
+			comment: "hi".to_string(),
+			checksum: [7, 8],
+			cached: 7,
+		}
+	}
+
+	#[test]
+	fn test_derive_struct_round_trip() {
+		let value = example();
+		let bytes = value.to_array();
+
+		let mut expected = vec![0x04, 0x03, 0x02, 0x01, 0x01];
+		expected.extend([0xab; 20]);
+		expected.extend([0x03, 1, 2, 3]);
+		expected.extend(b"neo\0\0\0\0\0");
+		expected.extend([0x00, 0x02, 0x02, 0x01, 0x04, 0x03]);
+		expected.extend([0x02, b'h', b'i']);
+		expected.extend([7, 8]);
+		assert_eq!(bytes, expected);
+		assert_eq!(value.size(), bytes.len());
+
+		let decoded = Example::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded, value);
+	}
+
+	#[test]
+	fn test_derive_struct_rejects_invalid_input() {
+		let bytes = example().to_array();
+
+		let mut bad_magic = bytes.clone();
+		bad_magic[0] = 0;
+		assert!(Example::decode(&mut Decoder::new(&bad_magic)).is_err());
+
+		let mut bad_reserved = bytes.clone();
+		bad_reserved[37] = 1;
+		assert!(Example::decode(&mut Decoder::new(&bad_reserved)).is_err());
+
+		let mut too_long = example();
+		too_long.script = vec![0; 5];
+		assert!(Example::decode(&mut Decoder::new(&too_long.to_array())).is_err());
+
+		let mut invalid = example();
+		invalid.checksum = [0xff, 0xff];
+		assert!(Example::decode(&mut Decoder::new(&invalid.to_array())).is_err());
+
+		for len in 0..bytes.len() {
+			assert!(Example::decode(&mut Decoder::new(&bytes[..len])).is_err());
+		}
+	}
+
+	#[test]
+	fn test_derive_struct_when() {
+		let mut value = example();
+		value.version = 0;
+		value.comment = "ignored".to_string();
+		let bytes = value.to_array();
+		assert_eq!(value.size(), bytes.len());
+
+		let decoded = Example::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded.comment, "");
+	}
+
+	#[test]
+	fn test_derive_enum() {
+		for (value, expected) in [
+			(Tagged::Unit, vec![0x01]),
+			(Tagged::Tuple(1, vec![9]), vec![0x11, 0x01, 0x00, 0x00, 0x00, 0x01, 0x09]),
+			(Tagged::Named { flag: true }, vec![0x20, 0x01]),
+		] {
+			assert_eq!(value.to_array(), expected);
+			assert_eq!(value.size(), expected.len());
+			assert_eq!(Tagged::decode(&mut Decoder::new(&expected)).unwrap(), value);
+		}
+		assert!(Tagged::decode(&mut Decoder::new(&[0x02])).is_err());
+
+		assert_eq!(Code::Error.to_array(), vec![0xff]);
+		assert_eq!(Code::decode(&mut Decoder::new(&[0x00])).unwrap(), Code::Success);
+		assert!(Code::decode(&mut Decoder::new(&[0x01])).is_err());
+	}
+
+	#[test]
+	fn test_hash_byte_order() {
+		let bytes: Vec<u8> = (0..20).collect();
+		let hash = H160::from_slice(&bytes);
+		let encoded = hash.to_array();
+		assert_eq!(encoded, bytes.iter().rev().copied().collect::<Vec<u8>>());
+		assert_eq!(H160::decode(&mut Decoder::new(&encoded)).unwrap(), hash);
+
+		let bytes: Vec<u8> = (0..32).collect();
+		let hash = H256::from_slice(&bytes);
+		let encoded = hash.to_array();
+		assert_eq!(encoded, bytes.iter().rev().copied().collect::<Vec<u8>>());
+		assert_eq!(H256::decode(&mut Decoder::new(&encoded)).unwrap(), hash);
+	}
+
+	#[test]
+	fn test_var_int_size() {
+		assert_eq!(var_int_size(0xfc), 1);
+		assert_eq!(var_int_size(0xfd), 3);
+		assert_eq!(var_int_size(0x10000), 5);
+		assert_eq!(var_int_size(0x100000000), 9);
+		assert_eq!(vec![1u16, 2].var_size(), 5);
+	}
+}
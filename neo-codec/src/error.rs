@@ -12,4 +12,8 @@ pub enum CodecError {
 	InvalidEncoding(String),
 	#[error("Invalid op code")]
 	InvalidOpCode,
+	#[error("Length {0} exceeds the maximum of {1}")]
+	ExceedsMaxLength(u64, u64),
+	#[error("Invalid data: {0}")]
+	InvalidData(String),
 }
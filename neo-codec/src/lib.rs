@@ -1,3 +1,6 @@
+// lets the derive macro's `::neo_codec` paths resolve inside this crate
+extern crate self as neo_codec;
+
 mod binary_decoder;
 pub mod binary_encoder;
 pub mod encode;
@@ -9,7 +9,7 @@ use neo_types::{
 	contract_parameter::ContractParameter,
 	contract_state::{ContractIdentifiers, ContractState},
 	nef_file::NefFile,
-	script_hash::ScriptHash,
+	script_hash::{ScriptHash, ScriptHashExtension},
 	*,
 };
 use primitive_types::H160;
@@ -128,10 +128,17 @@ impl<'a, P: JsonRpcClient> ContractManagement<'a, P> {
 		manifest: &[u8],
 		data: Option<ContractParameter>,
 	) -> Result<TransactionBuilder<P>, ContractError> {
-		let params = vec![nef.into(), manifest.into(), data.unwrap()];
+		let mut params = vec![nef.into(), manifest.into()];
+		params.extend(data);
 		let tx = self.invoke_function("deploy", params).await;
 		tx
 	}
+
+	/// Computes the hash the contract will get when `sender` deploys `nef` with a manifest named
+	/// `name`, before sending the deployment.
+	pub fn contract_hash(sender: &H160, nef: &NefFile, name: &str) -> H160 {
+		H160::from_contract(sender, nef.checksum_as_integer(), name)
+	}
 }
 
 // Other types and helpers
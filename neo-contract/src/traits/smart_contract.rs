@@ -14,12 +14,10 @@ use neo_types::{
 	contract_manifest::ContractManifest,
 	contract_parameter::ContractParameter,
 	invocation_result::InvocationResult,
-	op_code::OpCode,
 	script_hash::{ScriptHash, ScriptHashExtension},
 	stack_item::StackItem,
 	Bytes,
 };
-use num_bigint::BigInt;
 use primitive_types::H160;
 use rustc_serialize::hex::ToHex;
 use serde::Serialize;
@@ -225,13 +223,7 @@ pub trait SmartContractTrait<'a>: Send + Sync {
 		nef_checksum: u32,
 		contract_name: &str,
 	) -> Result<H160, ContractError> {
-		let mut script = ScriptBuilder::new();
-		script.op_code(&[OpCode::Abort]);
-		script.push_data(sender.to_vec()).unwrap();
-		script.push_integer(BigInt::from(nef_checksum)).unwrap();
-		script.push_data(contract_name.as_bytes().to_vec()).unwrap();
-
-		Ok(H160::from_slice(&script.to_bytes()))
+		Ok(H160::from_contract(&sender, nef_checksum, contract_name))
 	}
 
 	async fn get_manifest(&self) -> ContractManifest {
@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use neo_crypto::{
+	batch_verifier::{verify_batch, BatchVerifier, EncodedVerificationItem, VerificationItem},
+	keys::{Secp256r1PrivateKey, Secp256r1PublicKey, Secp256r1Signature},
+};
+use rand_core::OsRng;
+
+/// Signatures by a committee of 21 keys, the way witnesses on mainnet repeat the same signers.
+struct Witnesses {
+	public_keys: Vec<Secp256r1PublicKey>,
+	encoded_keys: Vec<Vec<u8>>,
+	messages: Vec<Vec<u8>>,
+	signatures: Vec<Secp256r1Signature>,
+}
+
+impl Witnesses {
+	const COMMITTEE_SIZE: usize = 21;
+
+	fn new(count: usize) -> Self {
+		let committee: Vec<_> = (0..Self::COMMITTEE_SIZE)
+			.map(|_| Secp256r1PrivateKey::random(&mut OsRng))
+			.collect();
+		let signers: Vec<_> = (0..count).map(|i| &committee[i % committee.len()]).collect();
+		let messages: Vec<Vec<u8>> =
+			(0..count).map(|i| (i as u64).to_le_bytes().to_vec()).collect();
+		let signatures = signers
+			.iter()
+			.zip(&messages)
+			.map(|(key, msg)| key.sign_tx(msg).unwrap())
+			.collect();
+		let public_keys: Vec<_> = signers.iter().map(|key| key.to_public_key()).collect();
+		let encoded_keys = public_keys.iter().map(|key| key.get_encoded(true)).collect();
+		Self { public_keys, encoded_keys, messages, signatures }
+	}
+
+	fn items(&self) -> Vec<VerificationItem<'_>> {
+		(0..self.messages.len())
+			.map(|i| (&self.public_keys[i], &self.messages[i][..], &self.signatures[i]))
+			.collect()
+	}
+
+	fn encoded_items(&self) -> Vec<EncodedVerificationItem<'_>> {
+		(0..self.messages.len())
+			.map(|i| (&self.encoded_keys[i][..], &self.messages[i][..], &self.signatures[i]))
+			.collect()
+	}
+}
+
+fn bench_verify(c: &mut Criterion) {
+	let mut group = c.benchmark_group("verify");
+	for count in [1, 64, 1024] {
+		let witnesses = Witnesses::new(count);
+		let items = witnesses.items();
+		let encoded_items = witnesses.encoded_items();
+		let precomputed = BatchVerifier::with_precomputed_keys(&witnesses.public_keys);
+		group.throughput(Throughput::Elements(count as u64));
+
+		group.bench_with_input(BenchmarkId::new("single", count), &items, |b, items| {
+			b.iter(|| {
+				items
+					.iter()
+					.map(|(key, msg, sig)| key.verify(msg, sig).is_ok())
+					.collect::<Vec<_>>()
+			})
+		});
+		group.bench_with_input(BenchmarkId::new("batch", count), &items, |b, items| {
+			b.iter(|| verify_batch(black_box(items)))
+		});
+		group.bench_with_input(BenchmarkId::new("encoded", count), &encoded_items, |b, items| {
+			b.iter(|| BatchVerifier::new().verify_encoded(black_box(items)))
+		});
+		group.bench_with_input(
+			BenchmarkId::new("encoded_precomputed_keys", count),
+			&encoded_items,
+			|b, items| b.iter(|| precomputed.verify_encoded(black_box(items))),
+		);
+	}
+	group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
@@ -0,0 +1,284 @@
+//! # Batch Verification
+//!
+//! Verifies many secp256r1 signatures at once, as an indexer does when checking the witnesses of
+//! every transaction in a block.
+//!
+//! Each signature is verified exactly like `Secp256r1PublicKey::verify` does, so a batch gives
+//! the same result per item as verifying the items one by one. With the `rayon` feature the items
+//! are verified in parallel.
+//!
+//! Most witnesses are signed by a small set of keys, the committee and the consensus nodes.
+//! `BatchVerifier` precomputes a table of multiples for each of those keys and for the generator,
+//! so verifying against them takes additions only instead of the doublings of a full scalar
+//! multiplication, about four times faster. A table takes about 13 ms to build and 69 KB of
+//! memory, so it's worth it for keys that sign many witnesses only. Other keys are decoded and
+//! verified as usual.
+//!
+//! ```
+//! use neo_crypto::{batch_verifier::BatchVerifier, keys::Secp256r1PrivateKey};
+//! use rand_core::OsRng;
+//!
+//! let private_key = Secp256r1PrivateKey::random(&mut OsRng);
+//! let public_key = private_key.to_public_key();
+//! let signature = private_key.sign_tx(b"message").unwrap();
+//!
+//! let verifier = BatchVerifier::with_precomputed_keys([&public_key]);
+//! let encoded = public_key.get_encoded(true);
+//! let results = verifier.verify_encoded(&[(&encoded[..], &b"message"[..], &signature)]);
+//! assert_eq!(results, vec![true]);
+//! ```
+
+use crate::keys::{Secp256r1PublicKey, Secp256r1Signature};
+use p256::{
+	ecdsa::Signature,
+	elliptic_curve::{
+		ops::{Invert, Reduce},
+		point::AffineCoordinates,
+	},
+	AffinePoint, ProjectivePoint, Scalar, U256,
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt, sync::OnceLock};
+
+/// A signature to verify, with the key and the message it should be valid for.
+pub type VerificationItem<'a> = (&'a Secp256r1PublicKey, &'a [u8], &'a Secp256r1Signature);
+
+/// A signature to verify against an encoded public key, as found in a verification script.
+pub type EncodedVerificationItem<'a> = (&'a [u8], &'a [u8], &'a Secp256r1Signature);
+
+/// Verifies every item and returns whether each signature is valid, in the order of `items`.
+pub fn verify_batch(items: &[VerificationItem]) -> Vec<bool> {
+	map_items(items, |(public_key, message, signature)| {
+		public_key.verify(message, signature).is_ok()
+	})
+}
+
+/// Verifies signatures against encoded public keys, with precomputed tables for frequently seen
+/// keys.
+#[derive(Debug, Clone, Default)]
+pub struct BatchVerifier {
+	precomputed_keys: HashMap<Vec<u8>, PrecomputedTable>,
+}
+
+impl BatchVerifier {
+	/// Creates a verifier without precomputed keys, which decodes the key of every item.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a verifier with tables for `public_keys`, e.g. the committee members.
+	pub fn with_precomputed_keys<'a>(
+		public_keys: impl IntoIterator<Item = &'a Secp256r1PublicKey>,
+	) -> Self {
+		let mut verifier = Self::new();
+		public_keys
+			.into_iter()
+			.for_each(|public_key| verifier.add_precomputed_key(public_key));
+		verifier
+	}
+
+	/// Precomputes the table of `public_key`. It is looked up by its compressed encoding.
+	pub fn add_precomputed_key(&mut self, public_key: &Secp256r1PublicKey) {
+		let point = public_key.as_public_key().to_projective();
+		self.precomputed_keys
+			.insert(public_key.get_encoded(true), PrecomputedTable::new(point));
+	}
+
+	/// Returns the number of keys with a precomputed table.
+	pub fn precomputed_keys_count(&self) -> usize {
+		self.precomputed_keys.len()
+	}
+
+	/// Verifies every item and returns whether each signature is valid, in the order of `items`.
+	///
+	/// Keys without a table are decoded for each item. An item whose key can't be decoded is
+	/// invalid.
+	pub fn verify_encoded(&self, items: &[EncodedVerificationItem]) -> Vec<bool> {
+		map_items(items, |(public_key, message, signature)| {
+			match self.precomputed_keys.get(*public_key) {
+				Some(table) => table.verify(message, signature.as_signature()),
+				None => Secp256r1PublicKey::from_bytes(public_key)
+					.is_ok_and(|decoded| decoded.verify(message, signature).is_ok()),
+			}
+		})
+	}
+}
+
+/// The multiples of a point for fixed windows of 4 bits, `d * 16^i * point` at `[i][d - 1]`.
+#[derive(Clone)]
+struct PrecomputedTable(Vec<[AffinePoint; 15]>);
+
+impl PrecomputedTable {
+	fn new(point: ProjectivePoint) -> Self {
+		let mut base = point;
+		let windows = (0..64)
+			.map(|_| {
+				let mut window = [AffinePoint::IDENTITY; 15];
+				let mut multiple = base;
+				for entry in window.iter_mut() {
+					*entry = multiple.to_affine();
+					multiple += base;
+				}
+				base = multiple;
+				window
+			})
+			.collect();
+		Self(windows)
+	}
+
+	fn generator() -> &'static Self {
+		static GENERATOR: OnceLock<PrecomputedTable> = OnceLock::new();
+		GENERATOR.get_or_init(|| Self::new(ProjectivePoint::GENERATOR))
+	}
+
+	/// Multiplies the point by `scalar`, one addition per non-zero window. The scalars of a
+	/// verification are public, so this doesn't need to run in constant time.
+	fn mul(&self, scalar: &Scalar) -> ProjectivePoint {
+		let mut product = ProjectivePoint::IDENTITY;
+		for (i, byte) in scalar.to_bytes().iter().rev().enumerate() {
+			for (j, digit) in [byte & 0x0f, byte >> 4].into_iter().enumerate() {
+				if digit != 0 {
+					product += self.0[2 * i + j][digit as usize - 1];
+				}
+			}
+		}
+		product
+	}
+
+	/// Verifies `signature` the way `ecdsa::hazmat::verify_prehashed` does, with the tables of
+	/// the generator and of the key.
+	fn verify(&self, message: &[u8], signature: &Signature) -> bool {
+		let z = <Scalar as Reduce<U256>>::reduce_bytes(&Sha256::digest(message));
+		let (r, s) = signature.split_scalars();
+		let s_inv = *s.invert_vartime();
+		let point = Self::generator().mul(&(z * s_inv)) + self.mul(&(*r * s_inv));
+		*r == <Scalar as Reduce<U256>>::reduce_bytes(&point.to_affine().x())
+	}
+}
+
+impl fmt::Debug for PrecomputedTable {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PrecomputedTable").finish_non_exhaustive()
+	}
+}
+
+#[cfg(feature = "rayon")]
+fn map_items<T: Sync>(items: &[T], verify: impl Fn(&T) -> bool + Sync + Send) -> Vec<bool> {
+	items.par_iter().map(verify).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn map_items<T>(items: &[T], verify: impl Fn(&T) -> bool) -> Vec<bool> {
+	items.iter().map(verify).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::keys::Secp256r1PrivateKey;
+	use p256::elliptic_curve::Field;
+	use rand_core::OsRng;
+
+	struct Fixture {
+		public_keys: Vec<Secp256r1PublicKey>,
+		messages: Vec<Vec<u8>>,
+		signatures: Vec<Secp256r1Signature>,
+	}
+
+	/// Signs a message per key, then breaks every third signature by swapping in the next one.
+	fn fixture() -> Fixture {
+		let private_keys: Vec<_> =
+			(0..6).map(|_| Secp256r1PrivateKey::random(&mut OsRng)).collect();
+		let messages: Vec<Vec<u8>> = (0..6u8).map(|i| vec![i; 32]).collect();
+		let mut signatures: Vec<_> = private_keys
+			.iter()
+			.zip(&messages)
+			.map(|(key, message)| key.sign_tx(message).unwrap())
+			.collect();
+		for i in (0..signatures.len()).step_by(3) {
+			signatures[i] = signatures[i + 1];
+		}
+		Fixture {
+			public_keys: private_keys.iter().map(Secp256r1PrivateKey::to_public_key).collect(),
+			messages,
+			signatures,
+		}
+	}
+
+	#[test]
+	fn test_verify_batch_matches_single_verify() {
+		let fixture = fixture();
+		let items: Vec<VerificationItem> = (0..fixture.public_keys.len())
+			.map(|i| (&fixture.public_keys[i], &fixture.messages[i][..], &fixture.signatures[i]))
+			.collect();
+
+		let expected: Vec<bool> = items
+			.iter()
+			.map(|(public_key, message, signature)| public_key.verify(message, signature).is_ok())
+			.collect();
+		assert_eq!(verify_batch(&items), expected);
+		assert_eq!(expected, vec![false, true, true, false, true, true]);
+		assert!(verify_batch(&[]).is_empty());
+	}
+
+	#[test]
+	fn test_verify_encoded() {
+		let fixture = fixture();
+		let encoded: Vec<Vec<u8>> = fixture
+			.public_keys
+			.iter()
+			.map(|public_key| public_key.get_encoded(true))
+			.collect();
+		let mut items: Vec<EncodedVerificationItem> = (0..encoded.len())
+			.map(|i| (&encoded[i][..], &fixture.messages[i][..], &fixture.signatures[i]))
+			.collect();
+		let invalid_key = [0x05; 33];
+		items.push((&invalid_key[..], &fixture.messages[1][..], &fixture.signatures[1]));
+		let expected = vec![false, true, true, false, true, true, false];
+
+		// Precomputed and other keys give the same results.
+		assert_eq!(BatchVerifier::new().verify_encoded(&items), expected);
+		let verifier = BatchVerifier::with_precomputed_keys(&fixture.public_keys[..3]);
+		assert_eq!(verifier.precomputed_keys_count(), 3);
+		assert_eq!(verifier.verify_encoded(&items), expected);
+
+		let uncompressed = fixture.public_keys[1].get_encoded(false);
+		let item = (&uncompressed[..], &fixture.messages[1][..], &fixture.signatures[1]);
+		assert_eq!(verifier.verify_encoded(&[item]), vec![true]);
+	}
+
+	#[test]
+	fn test_precomputed_table_mul() {
+		let point = ProjectivePoint::GENERATOR * Scalar::random(&mut OsRng);
+		let table = PrecomputedTable::new(point);
+		for scalar in [Scalar::ZERO, Scalar::ONE, -Scalar::ONE, Scalar::random(&mut OsRng)] {
+			assert_eq!(table.mul(&scalar), point * scalar);
+			assert_eq!(
+				PrecomputedTable::generator().mul(&scalar),
+				ProjectivePoint::GENERATOR * scalar
+			);
+		}
+	}
+
+	#[test]
+	fn test_precomputed_keys_match_single_verify() {
+		let fixture = fixture();
+		let verifier = BatchVerifier::with_precomputed_keys(&fixture.public_keys);
+		for i in 0..fixture.public_keys.len() {
+			let encoded = fixture.public_keys[i].get_encoded(true);
+			// The same signature with the other `s`, which `verify` accepts as well.
+			let (r, s) = fixture.signatures[i].as_signature().split_scalars();
+			let high_s =
+				Secp256r1Signature::from_scalars(r.to_bytes().into(), (-*s).to_bytes().into())
+					.unwrap();
+			for signature in [&fixture.signatures[i], &high_s] {
+				let item = (&encoded[..], &fixture.messages[i][..], signature);
+				let expected =
+					fixture.public_keys[i].verify(&fixture.messages[i], signature).is_ok();
+				assert_eq!(verifier.verify_encoded(&[item]), vec![expected]);
+			}
+		}
+	}
+}
@@ -13,9 +13,13 @@ use crate::{
 	wif::wif_from_private_key,
 };
 use rand::rngs::OsRng;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{ZeroizeOnDrop, Zeroizing};
 
 /// Represents an Elliptic Curve Key Pair containing both a private and a public key.
-
+///
+/// The private key is wiped from memory when the key pair is dropped and is redacted from the
+/// `Debug` output.
 #[derive(Debug, Clone)]
 pub struct KeyPair {
 	/// The private key component of the key pair.
@@ -55,8 +59,8 @@ impl KeyPair {
 		Self::new(private_key.clone(), public_key)
 	}
 
-	/// Returns the 32-byte representation of the private key.
-	pub fn private_key_bytes(&self) -> [u8; 32] {
+	/// Returns the 32-byte representation of the private key, wiped when dropped.
+	pub fn private_key_bytes(&self) -> Zeroizing<[u8; 32]> {
 		self.private_key.to_raw_bytes()
 	}
 
@@ -104,11 +108,29 @@ impl KeyPair {
 	/// Exports the key pair as a Wallet Import Format (WIF) string
 	///
 	/// Returns: The WIF encoding of this key pair
-	pub fn export_as_wif(&self) -> String {
+	pub fn export_as_wif(&self) -> Zeroizing<String> {
 		wif_from_private_key(&self.private_key())
 	}
 }
 
+impl ConstantTimeEq for KeyPair {
+	fn ct_eq(&self, other: &Self) -> Choice {
+		self.private_key.ct_eq(&other.private_key)
+	}
+}
+
+impl PartialEq for KeyPair {
+	fn eq(&self, other: &Self) -> bool {
+		self.ct_eq(other).into() && self.public_key == other.public_key
+	}
+}
+
+impl Eq for KeyPair {}
+
+// Relies on `Secp256r1PrivateKey`, and so on p256's `SecretKey` wiping itself when dropped. The
+// public key is not secret.
+impl ZeroizeOnDrop for KeyPair {}
+
 #[cfg(test)]
 mod tests {
 	use crate::key_pair::KeyPair;
@@ -122,8 +144,17 @@ mod tests {
 		let private_key_arr: &[u8; 32] = private_key.as_slice().try_into().unwrap();
 		let key_pair = KeyPair::from_private_key(private_key_arr).unwrap();
 		assert_eq!(
-			key_pair.export_as_wif(),
+			key_pair.export_as_wif().as_str(),
 			"L3tgppXLgdaeqSGSFw1Go3skBiy8vQAM7YMXvTHsKQtE16PBncSU"
 		);
 	}
+
+	#[test]
+	fn test_debug_redacts_private_key() {
+		let key_pair = KeyPair::new_random();
+		let debug = format!("{:?}", key_pair);
+		assert!(!debug.contains(&hex::encode(key_pair.private_key_bytes())));
+		assert_eq!(key_pair, key_pair.clone());
+		assert_ne!(key_pair, KeyPair::new_random());
+	}
 }
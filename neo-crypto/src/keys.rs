@@ -9,6 +9,8 @@
 //! - Generation of public and private keys.
 //! - Conversion between different formats and representations of keys and signatures.
 //! - Signing data with a private key and verifying signatures with a public key.
+//! - Deterministic RFC 6979 signing with optional added entropy, low-S normalization and DER
+//!   encoding of signatures.
 //! - Integration with external libraries like `neo-codec`, `p256`, and `rand_core` for cryptographic operations.
 //!
 //! ## Usage
@@ -58,7 +60,13 @@ use neo_codec::{encode::NeoSerializable, Decoder, Encoder};
 use num_integer::Integer;
 use num_traits::cast::ToPrimitive;
 use p256::{
-	ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey},
+	ecdsa::{
+		signature::{
+			hazmat::{PrehashSigner, RandomizedPrehashSigner},
+			Signer,
+		},
+		Signature, SigningKey, VerifyingKey,
+	},
 	elliptic_curve::{
 		sec1::{FromEncodedPoint, ToEncodedPoint},
 		Field,
@@ -66,12 +74,15 @@ use p256::{
 	EncodedPoint, FieldBytes, PublicKey, SecretKey,
 };
 use primitive_types::U256;
-use rand_core::OsRng;
+use rand_core::{CryptoRng, OsRng, RngCore};
 use rustc_serialize::hex::{FromHex, ToHex};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use signature::{Keypair, SignerMut, Verifier};
 use std::hash::{Hash, Hasher};
+use subtle::{Choice, ConstantTimeEq};
 use typenum::Unsigned;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 #[cfg_attr(feature = "substrate", serde(crate = "serde_substrate"))]
 #[derive(Debug, Copy, Clone)]
@@ -79,7 +90,11 @@ pub struct Secp256r1PublicKey {
 	inner: PublicKey,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// A secp256r1 private key.
+///
+/// The key is wiped from memory when dropped, `Debug` and `Display` never print it and equality
+/// is checked in constant time.
+#[derive(Clone)]
 pub struct Secp256r1PrivateKey {
 	inner: SecretKey,
 }
@@ -129,6 +144,11 @@ impl Secp256r1PublicKey {
 		Secp256r1PublicKey { inner: public_key }
 	}
 
+	/// The wrapped `PublicKey`, for the arithmetic of [`crate::batch_verifier`].
+	pub(crate) fn as_public_key(&self) -> &PublicKey {
+		&self.inner
+	}
+
 	/// Constructs a `Secp256r1PublicKey` from a byte slice.
 	///
 	/// Attempts to parse a byte slice as an encoded elliptic curve point and create a public key.
@@ -254,11 +274,23 @@ impl Secp256r1PrivateKey {
 			.map_err(|_| CryptoError::InvalidPrivateKey)
 	}
 
-	/// Returns the raw byte representation of the private key.
+	/// Returns the raw byte representation of the private key in a buffer that is wiped when
+	/// dropped.
+	///
+	/// - Returns: A 32-byte array representing the private key.
+	pub fn to_raw_bytes(&self) -> Zeroizing<[u8; 32]> {
+		self.secret_bytes()
+	}
+
+	/// Returns the raw byte representation of the private key in a buffer that is wiped when
+	/// dropped.
 	///
 	/// - Returns: A 32-byte array representing the private key.
-	pub fn to_raw_bytes(&self) -> [u8; 32] {
-		self.inner.clone().to_bytes().as_slice().try_into().unwrap()
+	pub fn secret_bytes(&self) -> Zeroizing<[u8; 32]> {
+		let mut field_bytes = self.inner.to_bytes();
+		let bytes = Zeroizing::new(field_bytes.as_slice().try_into().unwrap());
+		field_bytes.as_mut_slice().zeroize();
+		bytes
 	}
 
 	/// Converts the private key to its corresponding public key.
@@ -277,15 +309,100 @@ impl Secp256r1PrivateKey {
 	///
 	/// - Returns: A `Result` with the `Secp256r1Signature` or a `CryptoError`.
 	pub fn sign_tx(&self, message: &[u8]) -> Result<Secp256r1Signature, CryptoError> {
-		let signing_key = SigningKey::from_slice(&self.inner.to_bytes().as_slice())
-			.map_err(|_| CryptoError::InvalidPrivateKey)?;
+		let signing_key = SigningKey::from(&self.inner);
 		let (signature, _) =
 			signing_key.try_sign(message).map_err(|_| CryptoError::SigningError)?;
 
 		Ok(Secp256r1Signature { inner: signature })
 	}
+
+	/// Signs the SHA-256 hash of `message` with a nonce derived as described in RFC 6979.
+	///
+	/// The same key and message always give the same signature, which is what `sign_tx` produces.
+	///
+	/// - Parameter message: A byte slice representing the message to be signed.
+	///
+	/// - Returns: A `Result` with the `Secp256r1Signature` or a `CryptoError`.
+	pub fn sign_deterministic(&self, message: &[u8]) -> Result<Secp256r1Signature, CryptoError> {
+		self.sign_with_options(message, &SigningOptions::default())
+	}
+
+	/// Signs the SHA-256 hash of `message` with a RFC 6979 nonce, as configured by `options`.
+	///
+	/// - Parameters:
+	///     - message: A byte slice representing the message to be signed.
+	///     - options: The added entropy and normalization to apply.
+	///
+	/// - Returns: A `Result` with the `Secp256r1Signature` or a `CryptoError`.
+	pub fn sign_with_options(
+		&self,
+		message: &[u8],
+		options: &SigningOptions,
+	) -> Result<Secp256r1Signature, CryptoError> {
+		let signing_key = SigningKey::from(&self.inner);
+		let prehash = Sha256::digest(message);
+		let inner: Signature = match options.added_entropy {
+			// The entropy is fed to RFC 6979 as additional data, section 3.6.
+			Some(entropy) => signing_key
+				.sign_prehash_with_rng(&mut AddedEntropy(entropy), &prehash)
+				.map_err(|_| CryptoError::SigningError)?,
+			None => signing_key.sign_prehash(&prehash).map_err(|_| CryptoError::SigningError)?,
+		};
+
+		let signature = Secp256r1Signature { inner };
+		Ok(if options.low_s { signature.normalize_s() } else { signature })
+	}
+}
+
+/// Options for [`Secp256r1PrivateKey::sign_with_options`].
+///
+/// The default signs deterministically without normalization, like
+/// [`Secp256r1PrivateKey::sign_tx`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SigningOptions {
+	/// Extra data mixed into the RFC 6979 nonce. Signatures stay valid but differ for different
+	/// entropy, which protects against fault attacks on deterministic signing.
+	pub added_entropy: Option<[u8; 32]>,
+	/// Whether to return the signature with the lower of the two valid `s` values.
+	pub low_s: bool,
+}
+
+impl SigningOptions {
+	pub fn with_added_entropy(mut self, entropy: [u8; 32]) -> Self {
+		self.added_entropy = Some(entropy);
+		self
+	}
+
+	pub fn with_low_s(mut self, low_s: bool) -> Self {
+		self.low_s = low_s;
+		self
+	}
 }
 
+/// Hands fixed bytes to the signer where it asks an RNG for the additional data of RFC 6979.
+struct AddedEntropy([u8; 32]);
+
+impl RngCore for AddedEntropy {
+	fn next_u32(&mut self) -> u32 {
+		rand_core::impls::next_u32_via_fill(self)
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		rand_core::impls::next_u64_via_fill(self)
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		dest.copy_from_slice(&self.0[..dest.len()]);
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+impl CryptoRng for AddedEntropy {}
+
 impl Secp256r1Signature {
 	/// Creates a signature from the scalar values of `r` and `s`.
 	///
@@ -325,6 +442,11 @@ impl Secp256r1Signature {
 		Secp256r1Signature::from_scalars(x, y).unwrap()
 	}
 
+	/// The wrapped `Signature`, for the arithmetic of [`crate::batch_verifier`].
+	pub(crate) fn as_signature(&self) -> &Signature {
+		&self.inner
+	}
+
 	/// Constructs a `Secp256r1Signature` from a byte slice.
 	///
 	/// This method attempts to parse a 64-byte slice as an ECDSA signature.
@@ -339,7 +461,9 @@ impl Secp256r1Signature {
 		if bytes.len() != 64 {
 			return Err(CryptoError::InvalidFormat("Invalid signature length".to_string()))
 		}
-		Ok(Secp256r1Signature { inner: Signature::from_slice(bytes).unwrap() })
+		let inner = Signature::from_slice(bytes)
+			.map_err(|_| CryptoError::InvalidFormat("Invalid signature".to_string()))?;
+		Ok(Secp256r1Signature { inner })
 	}
 
 	/// Converts the signature into a 64-byte array.
@@ -358,14 +482,69 @@ impl Secp256r1Signature {
 
 		bytes
 	}
+
+	/// Parses an ASN.1 DER encoded signature, as produced by OpenSSL, HSMs and most other tooling.
+	///
+	/// - Parameter bytes: The DER encoded signature.
+	///
+	/// - Returns: A `Result<Secp256r1Signature, CryptoError>`.
+	pub fn from_der(bytes: &[u8]) -> Result<Self, CryptoError> {
+		Signature::from_der(bytes)
+			.map(|inner| Secp256r1Signature { inner })
+			.map_err(|_| CryptoError::InvalidFormat("Invalid DER signature".to_string()))
+	}
+
+	/// Encodes the signature in ASN.1 DER.
+	///
+	/// - Returns: The DER encoded signature.
+	pub fn to_der(&self) -> Vec<u8> {
+		self.inner.to_der().as_bytes().to_vec()
+	}
+
+	/// Whether `s` is at most half the curve order.
+	///
+	/// For every signature `(r, s)`, `(r, n - s)` is valid too. Neo accepts both, but tools that
+	/// reject malleable signatures only accept the low one.
+	pub fn is_low_s(&self) -> bool {
+		self.inner.normalize_s().is_none()
+	}
+
+	/// Returns the equivalent signature with a low `s`.
+	pub fn normalize_s(&self) -> Self {
+		self.inner.normalize_s().map_or(*self, |inner| Secp256r1Signature { inner })
+	}
+}
+
+impl fmt::Debug for Secp256r1PrivateKey {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Secp256r1PrivateKey([REDACTED])")
+	}
 }
 
 impl fmt::Display for Secp256r1PrivateKey {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "Secp256r1PrivateKey: {}\n", hex::encode(self.inner.to_bytes().as_slice()))
+		write!(f, "Secp256r1PrivateKey: [REDACTED]\n")
 	}
 }
 
+impl ConstantTimeEq for Secp256r1PrivateKey {
+	fn ct_eq(&self, other: &Self) -> Choice {
+		self.secret_bytes().ct_eq(&*other.secret_bytes())
+	}
+}
+
+impl PartialEq for Secp256r1PrivateKey {
+	fn eq(&self, other: &Self) -> bool {
+		self.ct_eq(other).into()
+	}
+}
+
+impl Eq for Secp256r1PrivateKey {}
+
+// There is nothing to wipe here: the only field is p256's `SecretKey`, whose `Drop` zeroizes the
+// scalar. This marker relies on that, so it must be revisited if fields are added or p256 changes.
+impl ZeroizeOnDrop for Secp256r1PrivateKey {}
+
 impl fmt::Display for Secp256r1PublicKey {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		write!(
@@ -433,27 +612,32 @@ impl NeoSerializable for Secp256r1PublicKey {
 	type Error = CryptoError;
 
 	fn size(&self) -> usize {
-		64
+		33
 	}
 
 	fn encode(&self, writer: &mut Encoder) {
-		writer.write_var_bytes(&self.to_vec());
+		writer.write_bytes(&self.get_encoded(true));
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let bytes = reader.read_var_bytes().unwrap();
+		let bytes = reader
+			.read_encoded_ec_point()
+			.map_err(|e| CryptoError::InvalidFormat(e.to_string()))?;
 		Secp256r1PublicKey::from_bytes(&bytes).map_err(|_| CryptoError::InvalidPublicKey)
 	}
 
 	fn to_array(&self) -> Vec<u8> {
-		self.get_encoded(false)
+		self.get_encoded(true)
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use crate::keys::{PublicKeyExtension, Secp256r1PublicKey};
-	use neo_codec::encode::NeoSerializable;
+	use crate::keys::{
+		PublicKeyExtension, Secp256r1PrivateKey, Secp256r1PublicKey, Secp256r1Signature,
+		SigningOptions,
+	};
+	use neo_codec::{encode::NeoSerializable, Decoder};
 	use rustc_serialize::hex::{FromHex, ToHex};
 
 	const ENCODED_POINT: &str =
@@ -497,6 +681,18 @@ mod tests {
 		assert_eq!(public_key.get_encoded(true), ENCODED_POINT.from_hex().unwrap());
 	}
 
+	#[test]
+	fn test_neo_serializable_public_key() {
+		let public_key = Secp256r1PublicKey::from_encoded(ENCODED_POINT).unwrap();
+		let bytes = public_key.to_array();
+		assert_eq!(bytes, ENCODED_POINT.from_hex().unwrap());
+		assert_eq!(public_key.size(), bytes.len());
+
+		let decoded = Secp256r1PublicKey::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded, public_key);
+		assert!(Secp256r1PublicKey::decode(&mut Decoder::new(&bytes[..32])).is_err());
+	}
+
 	#[test]
 	fn test_deserialize_public_key() {
 		let data = "036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
@@ -528,4 +724,108 @@ mod tests {
 		assert!(!(key1 < key1_uncompressed));
 		assert!(!(key1 > key1_uncompressed));
 	}
+
+	#[test]
+	fn test_private_key_redacted() {
+		let private_key = Secp256r1PrivateKey::from_bytes(
+			&"c7134d6fd8e73d819e82755c64c93788d8db0961929e025a53363c4cc02a6962"
+				.from_hex()
+				.unwrap(),
+		)
+		.unwrap();
+
+		let debug = format!("{:?}", private_key);
+		let display = format!("{}", private_key);
+		assert!(!debug.contains("c7134d6f"));
+		assert!(!display.contains("c7134d6f"));
+		assert!(debug.contains("REDACTED"));
+	}
+
+	#[test]
+	fn test_private_key_equality() {
+		let bytes = "c7134d6fd8e73d819e82755c64c93788d8db0961929e025a53363c4cc02a6962"
+			.from_hex()
+			.unwrap();
+		let key1 = Secp256r1PrivateKey::from_bytes(&bytes).unwrap();
+		let key2 = Secp256r1PrivateKey::from_bytes(&bytes).unwrap();
+		let key3 = Secp256r1PrivateKey::from_bytes(&[1u8; 32]).unwrap();
+
+		assert_eq!(key1, key2);
+		assert_ne!(key1, key3);
+		assert_eq!(key1.secret_bytes().as_slice(), bytes.as_slice());
+		assert_eq!(key1.to_raw_bytes(), key1.secret_bytes());
+	}
+
+	/// The P-256 key and signatures from RFC 6979, appendix A.2.5, with SHA-256.
+	const RFC6979_KEY: &str = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+
+	#[test]
+	fn test_sign_deterministic_rfc6979_vectors() {
+		let private_key =
+			Secp256r1PrivateKey::from_bytes(&RFC6979_KEY.from_hex().unwrap()).unwrap();
+		for (message, expected) in [
+			(
+				"sample",
+				"efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+				 f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+			),
+			(
+				"test",
+				"f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367\
+				 019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+			),
+		] {
+			let signature = private_key.sign_deterministic(message.as_bytes()).unwrap();
+			assert_eq!(signature.to_bytes().to_hex(), expected);
+			assert_eq!(private_key.sign_tx(message.as_bytes()).unwrap(), signature);
+		}
+	}
+
+	#[test]
+	fn test_sign_with_options() {
+		let private_key =
+			Secp256r1PrivateKey::from_bytes(&RFC6979_KEY.from_hex().unwrap()).unwrap();
+		let public_key = private_key.to_public_key();
+		let message = b"sample";
+		let deterministic = private_key.sign_deterministic(message).unwrap();
+		assert!(!deterministic.is_low_s());
+
+		let low_s = private_key
+			.sign_with_options(message, &SigningOptions::default().with_low_s(true))
+			.unwrap();
+		assert!(low_s.is_low_s());
+		assert_eq!(low_s.to_bytes()[..32], deterministic.to_bytes()[..32]);
+		assert_eq!(
+			low_s.to_bytes()[32..].to_hex(),
+			"0834e36ad29a83bf2bc9385e491d6099c8fdf9d1ed67aa7ea5f51f93782857a9"
+		);
+		assert_eq!(deterministic.normalize_s(), low_s);
+		assert_eq!(low_s.normalize_s(), low_s);
+
+		let entropy = SigningOptions::default().with_added_entropy([7; 32]);
+		let randomized = private_key.sign_with_options(message, &entropy).unwrap();
+		assert_ne!(randomized, deterministic);
+		assert_eq!(private_key.sign_with_options(message, &entropy).unwrap(), randomized);
+
+		for signature in [deterministic, low_s, randomized] {
+			assert!(public_key.verify(message, &signature).is_ok());
+		}
+	}
+
+	#[test]
+	fn test_signature_der() {
+		let signature = Secp256r1Signature::from_bytes(
+			&"efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+			  f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"
+				.from_hex()
+				.unwrap(),
+		)
+		.unwrap();
+		let der = "3046022100efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716\
+		           022100f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8";
+
+		assert_eq!(signature.to_der().to_hex(), der);
+		assert_eq!(Secp256r1Signature::from_der(&der.from_hex().unwrap()).unwrap(), signature);
+		assert!(Secp256r1Signature::from_der(&signature.to_bytes()).is_err());
+	}
 }
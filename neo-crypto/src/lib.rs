@@ -1,4 +1,5 @@
 pub mod base58_helper;
+pub mod batch_verifier;
 pub mod error;
 pub mod hash;
 pub mod key_pair;
@@ -1,5 +1,6 @@
 use crate::{error::CryptoError, hash::HashableForVec, keys::Secp256r1PrivateKey};
 use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
 
 /// Converts a WIF (Wallet Import Format) string into a `Secp256r1PrivateKey`.
 ///
@@ -19,9 +20,11 @@ use sha2::{Digest, Sha256};
 /// * The decoded data does not have the correct length, prefix, or suffix expected for a WIF.
 /// * The checksum of the WIF does not match the expected value.
 pub fn private_key_from_wif(wif: &str) -> Result<Secp256r1PrivateKey, CryptoError> {
-	let data = bs58::decode(wif)
-		.into_vec()
-		.map_err(|_| CryptoError::InvalidFormat("Incorrect WIF format.".to_string()))?;
+	let data = Zeroizing::new(
+		bs58::decode(wif)
+			.into_vec()
+			.map_err(|_| CryptoError::InvalidFormat("Incorrect WIF format.".to_string()))?,
+	);
 	if data.len() != 38 || data[0] != 0x80 || data[33] != 0x01 {
 		return Err(CryptoError::InvalidFormat("Incorrect WIF format.".to_string()))
 	}
@@ -31,7 +34,7 @@ pub fn private_key_from_wif(wif: &str) -> Result<Secp256r1PrivateKey, CryptoErro
 		return Err(CryptoError::InvalidFormat("Incorrect WIF checksum.".to_string()))
 	}
 
-	Secp256r1PrivateKey::from_bytes(&data[1..33].to_vec())
+	Secp256r1PrivateKey::from_bytes(&data[1..33])
 }
 
 /// Converts a `Secp256r1PrivateKey` into a WIF (Wallet Import Format) string.
@@ -43,17 +46,18 @@ pub fn private_key_from_wif(wif: &str) -> Result<Secp256r1PrivateKey, CryptoErro
 /// * `private_key` - A reference to the `Secp256r1PrivateKey` to be converted.
 ///
 /// # Returns
-/// A `String` containing the WIF representation of the provided private key.
-pub fn wif_from_private_key(private_key: &Secp256r1PrivateKey) -> String {
-	let mut extended_key: Vec<u8> = vec![0x80];
-	extended_key.extend(private_key.to_raw_bytes());
+/// A `String` containing the WIF representation of the provided private key, wiped when dropped.
+pub fn wif_from_private_key(private_key: &Secp256r1PrivateKey) -> Zeroizing<String> {
+	let mut extended_key = Zeroizing::new(Vec::with_capacity(38));
+	extended_key.push(0x80);
+	extended_key.extend_from_slice(private_key.secret_bytes().as_slice());
 	extended_key.push(0x01);
 
-	let hash = Sha256::digest(&Sha256::digest(&extended_key));
+	let hash = Sha256::digest(&Sha256::digest(extended_key.as_slice()));
 	let checksum = &hash[0..4];
 	extended_key.extend_from_slice(checksum);
 
-	bs58::encode(extended_key).into_string()
+	Zeroizing::new(bs58::encode(extended_key.as_slice()).into_string())
 }
 
 #[cfg(test)]
@@ -110,7 +114,7 @@ mod tests {
 
 		let wif = wif_from_private_key(&Secp256r1PrivateKey::from_slice(&pk).unwrap());
 
-		assert_eq!(wif, expected_wif);
+		assert_eq!(wif.as_str(), expected_wif);
 	}
 
 	#[test]
@@ -0,0 +1,418 @@
+use async_trait::async_trait;
+use neo_providers::{
+	core::responses::{
+		neo_application_log::ApplicationLog, neo_block::NeoBlock,
+		neo_transaction_result::TransactionResult,
+	},
+	Middleware, MiddlewareError,
+};
+use neo_types::contract_state::ContractState;
+use primitive_types::{H160, H256};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Debug,
+	sync::Mutex,
+};
+use thiserror::Error;
+use tracing::warn;
+
+/// Storage of the responses cached by a [`CacheMiddleware`], as JSON under a key naming the
+/// method and its params.
+pub trait CacheBackend: Send + Sync + Debug {
+	/// Returns the value stored under `key`, if any
+	fn get(&self, key: &str) -> Option<Vec<u8>>;
+
+	/// Stores `value` under `key`, replacing the previous value
+	fn insert(&self, key: &str, value: Vec<u8>);
+}
+
+/// An in-memory [`CacheBackend`] evicting the least recently used entries beyond its capacity.
+#[derive(Debug)]
+pub struct MemoryCache {
+	capacity: usize,
+	entries: Mutex<Lru>,
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+	// The value of each key and the tick it was last used at
+	values: HashMap<String, (u64, Vec<u8>)>,
+	// The key last used at each tick
+	ticks: BTreeMap<u64, String>,
+	tick: u64,
+}
+
+impl Lru {
+	fn touch(&mut self, key: &str) -> Option<&Vec<u8>> {
+		self.tick += 1;
+		let (tick, value) = self.values.get_mut(key)?;
+		let key = self.ticks.remove(tick).expect("every value has a tick");
+		*tick = self.tick;
+		self.ticks.insert(self.tick, key);
+		Some(value)
+	}
+}
+
+impl MemoryCache {
+	/// The number of entries kept by [`MemoryCache::default`]
+	pub const DEFAULT_CAPACITY: usize = 10_000;
+
+	/// Creates a cache keeping at most `capacity` entries
+	pub fn new(capacity: usize) -> Self {
+		Self { capacity: capacity.max(1), entries: Mutex::new(Lru::default()) }
+	}
+
+	/// The number of entries in the cache
+	pub fn len(&self) -> usize {
+		self.entries.lock().unwrap().values.len()
+	}
+
+	/// Whether the cache has no entries
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+impl Default for MemoryCache {
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_CAPACITY)
+	}
+}
+
+impl CacheBackend for MemoryCache {
+	fn get(&self, key: &str) -> Option<Vec<u8>> {
+		self.entries.lock().unwrap().touch(key).cloned()
+	}
+
+	fn insert(&self, key: &str, value: Vec<u8>) {
+		let mut lru = self.entries.lock().unwrap();
+		if lru.touch(key).is_some() {
+			lru.values.get_mut(key).expect("touched").1 = value;
+			return
+		}
+		if lru.values.len() >= self.capacity {
+			if let Some((_, oldest)) = lru.ticks.pop_first() {
+				lru.values.remove(&oldest);
+			}
+		}
+		let tick = lru.tick;
+		lru.ticks.insert(tick, key.to_string());
+		lru.values.insert(key.to_string(), (tick, value));
+	}
+}
+
+/// A [`CacheBackend`] persisting the entries in a [`sled`] database, so that they survive
+/// restarts.
+#[cfg(feature = "sled")]
+#[derive(Debug, Clone)]
+pub struct SledCache {
+	db: sled::Db,
+}
+
+#[cfg(feature = "sled")]
+impl SledCache {
+	/// Opens or creates the database at `path`
+	pub fn open(path: impl AsRef<std::path::Path>) -> sled::Result<Self> {
+		Ok(Self::new(sled::open(path)?))
+	}
+
+	/// Stores the entries in `db`
+	pub fn new(db: sled::Db) -> Self {
+		Self { db }
+	}
+}
+
+#[cfg(feature = "sled")]
+impl CacheBackend for SledCache {
+	fn get(&self, key: &str) -> Option<Vec<u8>> {
+		match self.db.get(key) {
+			Ok(value) => value.map(|value| value.to_vec()),
+			Err(err) => {
+				warn!(key, %err, "Failed to read the cache");
+				None
+			},
+		}
+	}
+
+	fn insert(&self, key: &str, value: Vec<u8>) {
+		if let Err(err) = self.db.insert(key, value) {
+			warn!(key, %err, "Failed to write the cache");
+		}
+	}
+}
+
+/// A [`CacheBackend`] persisting the entries in a SQLite database, so that they survive restarts.
+#[cfg(feature = "rusqlite")]
+#[derive(Debug)]
+pub struct SqliteCache {
+	conn: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "rusqlite")]
+impl SqliteCache {
+	/// Opens or creates the database at `path`
+	pub fn open(path: impl AsRef<std::path::Path>) -> rusqlite::Result<Self> {
+		Self::new(rusqlite::Connection::open(path)?)
+	}
+
+	/// Stores the entries in the `neo_cache` table of `conn`, creating it if needed
+	pub fn new(conn: rusqlite::Connection) -> rusqlite::Result<Self> {
+		conn.execute(
+			"CREATE TABLE IF NOT EXISTS neo_cache (key TEXT PRIMARY KEY, value BLOB NOT NULL)",
+			[],
+		)?;
+		Ok(Self { conn: Mutex::new(conn) })
+	}
+}
+
+#[cfg(feature = "rusqlite")]
+impl CacheBackend for SqliteCache {
+	fn get(&self, key: &str) -> Option<Vec<u8>> {
+		use rusqlite::OptionalExtension;
+
+		let conn = self.conn.lock().unwrap();
+		let value = conn
+			.query_row("SELECT value FROM neo_cache WHERE key = ?1", [key], |row| row.get(0))
+			.optional();
+		match value {
+			Ok(value) => value,
+			Err(err) => {
+				warn!(key, %err, "Failed to read the cache");
+				None
+			},
+		}
+	}
+
+	fn insert(&self, key: &str, value: Vec<u8>) {
+		let conn = self.conn.lock().unwrap();
+		let inserted = conn.execute(
+			"INSERT OR REPLACE INTO neo_cache (key, value) VALUES (?1, ?2)",
+			rusqlite::params![key, value],
+		);
+		if let Err(err) = inserted {
+			warn!(key, %err, "Failed to write the cache");
+		}
+	}
+}
+
+/// Middleware caching the responses about chain data that never changes once it's in a block:
+/// blocks, raw blocks, transactions, application logs and contract states.
+///
+/// Only final data is cached. A block is cached once its next block exists, as `nextblockhash`
+/// is missing on the tip, and a transaction once it's in a block. The `confirmations` of cached
+/// blocks and transactions are the ones of the first response. Everything else depends on the
+/// tip or on the node and is sent to the inner middleware, like
+/// [`CacheMiddleware::bypass`] does for any method.
+///
+/// A contract state changes when the contract is updated, which increments its update counter, so
+/// it's only final for a given update counter. `get_contract_state` always asks the node and caches
+/// the state under the contract hash and its update counter, and
+/// [`CacheMiddleware::get_contract_state_at`] serves a known version of the state from the cache.
+///
+/// ```no_run
+/// use neo_middleware::{cache::MemoryCache, CacheMiddleware};
+/// use neo_providers::{Http, Middleware, Provider};
+/// use std::convert::TryFrom;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Http>::try_from("http://seed1.neo.org:10332")?;
+/// let provider = CacheMiddleware::new(provider, MemoryCache::default());
+/// let block = provider.get_block_by_index(100, true).await?;
+/// // Sent once only
+/// let block = provider.get_block(block.hash, true).await?;
+/// let block = provider.get_block(block.hash, true).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CacheMiddleware<M, B = MemoryCache> {
+	inner: M,
+	cache: B,
+}
+
+impl<M, B> CacheMiddleware<M, B>
+where
+	M: Middleware,
+	B: CacheBackend,
+{
+	/// Creates a middleware caching the responses of `inner` in `cache`
+	pub fn new(inner: M, cache: B) -> Self {
+		Self { inner, cache }
+	}
+
+	/// The cache the responses are stored in
+	pub fn cache(&self) -> &B {
+		&self.cache
+	}
+
+	/// The inner middleware, to send a request without the cache, e.g. for up to date
+	/// confirmations
+	pub fn bypass(&self) -> &M {
+		&self.inner
+	}
+
+	/// Gets the state of the contract with `hash` as of its `update_counter`th update, from the
+	/// cache if it was returned before.
+	///
+	/// Fails with [`CacheMiddlewareError::UpdateCounterMismatch`] if the node returns another
+	/// version of the state, as it's lagging behind or the contract was updated since.
+	pub async fn get_contract_state_at(
+		&self,
+		hash: H160,
+		update_counter: i32,
+	) -> Result<ContractState, CacheMiddlewareError<M>> {
+		if let Some(state) = self.get(&contract_state_key(hash, update_counter)) {
+			return Ok(state)
+		}
+		let state = self.get_contract_state(hash).await?;
+		if state.update_counter != update_counter {
+			return Err(CacheMiddlewareError::UpdateCounterMismatch {
+				expected: update_counter,
+				found: state.update_counter,
+			})
+		}
+		Ok(state)
+	}
+
+	fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+		let value = self.cache.get(key)?;
+		match serde_json::from_slice(&value) {
+			Ok(value) => Some(value),
+			Err(err) => {
+				warn!(key, %err, "Ignoring a cached value that can't be deserialized");
+				None
+			},
+		}
+	}
+
+	fn insert<T: Serialize>(&self, key: &str, value: &T) {
+		match serde_json::to_vec(value) {
+			Ok(value) => self.cache.insert(key, value),
+			Err(err) => warn!(key, %err, "Failed to serialize a value to cache"),
+		}
+	}
+}
+
+fn contract_state_key(hash: H160, update_counter: i32) -> String {
+	format!("getcontractstate:{hash:#x}:{update_counter}")
+}
+
+#[derive(Error, Debug)]
+/// Error thrown when the client interacts with the cache middleware.
+pub enum CacheMiddlewareError<M: Middleware> {
+	/// Thrown when an internal middleware errors
+	#[error(transparent)]
+	MiddlewareError(M::Error),
+	/// Thrown when the node returns another version of a contract state than the one requested
+	#[error("expected the contract state of update {expected}, the node returned update {found}")]
+	UpdateCounterMismatch {
+		/// The update counter requested
+		expected: i32,
+		/// The update counter of the state returned by the node
+		found: i32,
+	},
+}
+
+impl<M: Middleware> MiddlewareError for CacheMiddlewareError<M> {
+	type Inner = M::Error;
+
+	fn from_err(src: M::Error) -> Self {
+		CacheMiddlewareError::MiddlewareError(src)
+	}
+
+	fn as_inner(&self) -> Option<&Self::Inner> {
+		match self {
+			CacheMiddlewareError::MiddlewareError(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<M, B> Middleware for CacheMiddleware<M, B>
+where
+	M: Middleware,
+	B: CacheBackend,
+{
+	type Error = CacheMiddlewareError<M>;
+	type Provider = M::Provider;
+	type Inner = M;
+
+	fn inner(&self) -> &M {
+		&self.inner
+	}
+
+	async fn get_block(&self, block_hash: H256, full_tx: bool) -> Result<NeoBlock, Self::Error> {
+		let key = format!("getblock:{block_hash:#x}:{full_tx}");
+		if let Some(block) = self.get(&key) {
+			return Ok(block)
+		}
+		let block = self
+			.inner
+			.get_block(block_hash, full_tx)
+			.await
+			.map_err(CacheMiddlewareError::MiddlewareError)?;
+		if block.next_block_hash.is_some() {
+			self.insert(&key, &block);
+		}
+		Ok(block)
+	}
+
+	async fn get_raw_block(&self, block_hash: H256) -> Result<String, Self::Error> {
+		let key = format!("getrawblock:{block_hash:#x}");
+		if let Some(block) = self.get(&key) {
+			return Ok(block)
+		}
+		let block = self
+			.inner
+			.get_raw_block(block_hash)
+			.await
+			.map_err(CacheMiddlewareError::MiddlewareError)?;
+		self.insert(&key, &block);
+		Ok(block)
+	}
+
+	async fn get_contract_state(&self, hash: H160) -> Result<ContractState, Self::Error> {
+		// The contract may have been updated since any cached state
+		let state = self
+			.inner
+			.get_contract_state(hash)
+			.await
+			.map_err(CacheMiddlewareError::MiddlewareError)?;
+		self.insert(&contract_state_key(hash, state.update_counter), &state);
+		Ok(state)
+	}
+
+	async fn get_transaction(&self, hash: H256) -> Result<Option<TransactionResult>, Self::Error> {
+		let key = format!("gettransaction:{hash:#x}");
+		if let Some(tx) = self.get(&key) {
+			return Ok(Some(tx))
+		}
+		let tx = self
+			.inner
+			.get_transaction(hash)
+			.await
+			.map_err(CacheMiddlewareError::MiddlewareError)?;
+		// Transactions in the memory pool aren't final
+		if let Some(tx) = tx.as_ref().filter(|tx| tx.block_hash.is_some()) {
+			self.insert(&key, tx);
+		}
+		Ok(tx)
+	}
+
+	async fn get_application_log(&self, tx_hash: H256) -> Result<ApplicationLog, Self::Error> {
+		let key = format!("getapplicationlog:{tx_hash:#x}");
+		if let Some(log) = self.get(&key) {
+			return Ok(log)
+		}
+		let log = self
+			.inner
+			.get_application_log(tx_hash)
+			.await
+			.map_err(CacheMiddlewareError::MiddlewareError)?;
+		self.insert(&key, &log);
+		Ok(log)
+	}
+}
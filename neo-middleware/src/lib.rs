@@ -20,6 +20,11 @@ pub use policy::{
 	AllowEverything, Policy, PolicyMiddleware, PolicyMiddlewareError, RejectEverything,
 };
 
+/// The [CacheMiddleware] keeps the blocks, transactions, application logs and contract states
+/// returned by the node in a [`CacheBackend`](cache::CacheBackend), instead of fetching them again.
+pub mod cache;
+pub use cache::{CacheMiddleware, CacheMiddlewareError};
+
 /// [MiddlewareBuilder] provides a way to compose many [`Middleware`]s in a concise way.
 pub mod builder;
 pub use builder::MiddlewareBuilder;
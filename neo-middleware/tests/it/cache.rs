@@ -0,0 +1,147 @@
+use neo_middleware::{cache::MemoryCache, CacheMiddleware, CacheMiddlewareError};
+use neo_providers::{Middleware, MockResponse, Provider};
+use primitive_types::{H160, H256};
+use serde_json::{json, Value};
+
+fn block(hash: H256, next_block_hash: Option<H256>) -> Value {
+	let mut block = json!({
+		"hash": format!("{hash:#x}"),
+		"size": 697,
+		"version": 0,
+		"previousblockhash": format!("{:#x}", H256::zero()),
+		"merkleroot": format!("{:#x}", H256::zero()),
+		"time": 1_627_896_461_306u64,
+		"index": 100,
+		"primary": 0,
+		"nextconsensus": "NgPkjjLTNcQad99iRYeXRUuowE4gxLAnDL",
+		"witnesses": [],
+		"tx": [],
+		"confirmations": 2,
+	});
+	if let Some(next) = next_block_hash {
+		block["nextblockhash"] = json!(format!("{next:#x}"));
+	}
+	block
+}
+
+fn contract_state(hash: H160, update_counter: i32) -> Value {
+	json!({
+		"id": 1,
+		"update_counter": update_counter,
+		"hash": format!("{hash:#x}"),
+		"nef": {
+			"magic": 860_243_278,
+			"compiler": "neon",
+			"source": null,
+			"tokens": [],
+			"script": "QA==",
+			"checksum": 1,
+		},
+		"manifest": {
+			"name": "Token",
+			"groups": [],
+			"features": {},
+			"supportedstandards": ["NEP-17"],
+			"permissions": [{ "contract": "*", "methods": "*" }],
+			"trusts": ["0x23ba2703c53263e8d6e522dc32203339dcd8eee9"],
+		},
+	})
+}
+
+#[tokio::test]
+async fn caches_final_blocks() {
+	let (provider, mock) = Provider::mocked();
+	let provider = CacheMiddleware::new(provider, MemoryCache::default());
+	let hash = H256::repeat_byte(1);
+
+	mock.push_response(MockResponse::Value(block(hash, Some(H256::repeat_byte(2)))));
+	assert_eq!(provider.get_block(hash, true).await.unwrap().hash, hash);
+	// Served from the cache, the mock has no response left
+	assert_eq!(provider.get_block(hash, true).await.unwrap().hash, hash);
+	assert_eq!(provider.cache().len(), 1);
+
+	// The block with the transaction hashes only is another entry
+	assert!(provider.get_block(hash, false).await.is_err());
+}
+
+#[tokio::test]
+async fn skips_the_tip() {
+	let (provider, mock) = Provider::mocked();
+	let provider = CacheMiddleware::new(provider, MemoryCache::default());
+	let hash = H256::repeat_byte(1);
+
+	mock.push_response(MockResponse::Value(block(hash, None)));
+	assert!(provider.get_block(hash, true).await.unwrap().next_block_hash.is_none());
+	assert!(provider.cache().is_empty());
+	assert!(provider.get_block(hash, true).await.is_err());
+}
+
+#[tokio::test]
+async fn caches_raw_blocks() {
+	let (provider, mock) = Provider::mocked();
+	let provider = CacheMiddleware::new(provider, MemoryCache::new(1));
+
+	mock.push_response(MockResponse::Value(json!("AAAA")));
+	mock.push_response(MockResponse::Value(json!("BBBB")));
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(1)).await.unwrap(), "BBBB");
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(1)).await.unwrap(), "BBBB");
+	// The least recently used block is evicted beyond the capacity
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(2)).await.unwrap(), "AAAA");
+	assert_eq!(provider.cache().len(), 1);
+	assert!(provider.get_raw_block(H256::repeat_byte(1)).await.is_err());
+
+	// Bypassing the cache always reaches the node
+	assert!(provider.bypass().get_raw_block(H256::repeat_byte(2)).await.is_err());
+}
+
+#[tokio::test]
+async fn caches_contract_states_by_update_counter() {
+	let (provider, mock) = Provider::mocked();
+	let provider = CacheMiddleware::new(provider, MemoryCache::default());
+	let hash = H160::repeat_byte(1);
+
+	// The contract may have been updated, so the node is always asked for the current state
+	mock.push_response(MockResponse::Value(contract_state(hash, 1)));
+	mock.push_response(MockResponse::Value(contract_state(hash, 0)));
+	assert_eq!(provider.get_contract_state(hash).await.unwrap().update_counter, 0);
+	assert_eq!(provider.get_contract_state(hash).await.unwrap().update_counter, 1);
+	assert_eq!(provider.cache().len(), 2);
+
+	// Known versions are served from the cache
+	assert_eq!(provider.get_contract_state_at(hash, 0).await.unwrap().update_counter, 0);
+	assert_eq!(provider.get_contract_state_at(hash, 1).await.unwrap().update_counter, 1);
+
+	mock.push_response(MockResponse::Value(contract_state(hash, 1)));
+	let err = provider.get_contract_state_at(hash, 2).await.unwrap_err();
+	assert!(matches!(err, CacheMiddlewareError::UpdateCounterMismatch { expected: 2, found: 1 }));
+}
+
+#[tokio::test]
+async fn cached_contract_states_round_trip() {
+	let (provider, mock) = Provider::mocked();
+	let provider = CacheMiddleware::new(provider, MemoryCache::default());
+	let hash = H160::repeat_byte(1);
+
+	mock.push_response(MockResponse::Value(contract_state(hash, 0)));
+	let state = provider.get_contract_state(hash).await.unwrap();
+	let cached = provider.get_contract_state_at(hash, 0).await.unwrap();
+	assert_eq!(cached.manifest.trusts, vec!["0x23ba2703c53263e8d6e522dc32203339dcd8eee9"]);
+	assert_eq!(serde_json::to_value(&cached).unwrap(), serde_json::to_value(&state).unwrap());
+}
+
+#[cfg(feature = "rusqlite")]
+#[tokio::test]
+async fn caches_in_sqlite() {
+	use neo_middleware::cache::SqliteCache;
+
+	let (provider, mock) = Provider::mocked();
+	let cache = SqliteCache::new(rusqlite::Connection::open_in_memory().unwrap()).unwrap();
+	let provider = CacheMiddleware::new(provider, cache);
+
+	mock.push_response(MockResponse::Value(json!("AAAA")));
+	mock.push_response(MockResponse::Value(json!("BBBB")));
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(1)).await.unwrap(), "BBBB");
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(1)).await.unwrap(), "BBBB");
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(2)).await.unwrap(), "AAAA");
+	assert_eq!(provider.get_raw_block(H256::repeat_byte(1)).await.unwrap(), "BBBB");
+}
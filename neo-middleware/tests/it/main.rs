@@ -7,6 +7,8 @@ use std::time::Duration;
 
 mod builder;
 
+mod cache;
+
 mod signer;
 
 mod stack;
@@ -3,6 +3,7 @@ use crate::{
 	utils::{private_key_from_wif, public_key_to_address},
 	ProviderError,
 };
+use neo_config::NeoConstants;
 use neo_crypto::{key_pair::KeyPair, keys::Secp256r1PublicKey, nep2::NEP2};
 use neo_types::{
 	address::Address,
@@ -111,6 +112,9 @@ pub struct Account {
 	pub encrypted_private_key: Option<String>,
 	pub signing_threshold: Option<u32>,
 	pub nr_of_participants: Option<u32>,
+	/// Whether the account is a deployed contract witnessed by its `verify` method
+	#[serde(default)]
+	pub is_deployed_contract: bool,
 }
 
 impl PartialEq for Account {
@@ -122,6 +126,7 @@ impl PartialEq for Account {
 			&& self.encrypted_private_key == other.encrypted_private_key
 			&& self.signing_threshold == other.signing_threshold
 			&& self.nr_of_participants == other.nr_of_participants
+			&& self.is_deployed_contract == other.is_deployed_contract
 	}
 }
 
@@ -134,6 +139,7 @@ impl Hash for Account {
 		self.encrypted_private_key.hash(state);
 		self.signing_threshold.hash(state);
 		self.nr_of_participants.hash(state);
+		self.is_deployed_contract.hash(state);
 	}
 }
 
@@ -221,6 +227,7 @@ impl AccountTrait for Account {
 			encrypted_private_key: None,
 			signing_threshold,
 			nr_of_participants,
+			is_deployed_contract: false,
 		}
 	}
 
@@ -241,6 +248,7 @@ impl AccountTrait for Account {
 			encrypted_private_key: None,
 			signing_threshold,
 			nr_of_participants,
+			is_deployed_contract: false,
 		})
 	}
 
@@ -263,6 +271,7 @@ impl AccountTrait for Account {
 			encrypted_private_key,
 			signing_threshold,
 			nr_of_participants,
+			is_deployed_contract: false,
 		}
 	}
 
@@ -352,15 +361,7 @@ impl AccountTrait for Account {
 		public_keys: &[Secp256r1PublicKey],
 		signing_threshold: u32,
 	) -> Result<Self, Self::Error> {
-		let script = VerificationScript::from_multi_sig(public_keys, signing_threshold as u8);
-
-		Ok(Self {
-			label: Some(script.script().to_base64()),
-			verification_script: Some(script),
-			signing_threshold: Some(signing_threshold),
-			nr_of_participants: Some(public_keys.len() as u32),
-			..Default::default()
-		})
+		Self::multi_sig(public_keys, signing_threshold)
 	}
 
 	fn from_address(address: &str) -> Result<Self, Self::Error> {
@@ -386,3 +387,142 @@ impl AccountTrait for Account {
 		self.signing_threshold.is_some() && self.nr_of_participants.is_some()
 	}
 }
+
+impl Account {
+	/// Creates a watch-only account for a deployed contract whose `verify` method acts as the
+	/// witness, e.g. a contract-owned treasury.
+	///
+	/// Contract accounts have no verification script, the node calls `verify` with the parameters
+	/// pushed by the invocation script instead. Use them as transaction signers through
+	/// [`ContractSigner`](crate::core::transaction::signers::contract_signer::ContractSigner).
+	pub fn from_contract(contract_hash: &H160) -> Self {
+		Self {
+			address_or_scripthash: AddressOrScriptHash::ScriptHash(*contract_hash),
+			label: Some(contract_hash.to_address()),
+			is_deployed_contract: true,
+			..Default::default()
+		}
+	}
+
+	/// Creates a multi-sig account that requires `threshold` signatures out of `public_keys`.
+	///
+	/// The keys are sorted like the node sorts them, so the resulting address is the same as the
+	/// one neo-cli or neon-js compute for the same set of keys, whatever order they are given in.
+	pub fn multi_sig(
+		public_keys: &[Secp256r1PublicKey],
+		threshold: u32,
+	) -> Result<Self, ProviderError> {
+		if public_keys.is_empty()
+			|| public_keys.len() > NeoConstants::MAX_PUBLIC_KEYS_PER_MULTI_SIG as usize
+		{
+			return Err(ProviderError::IllegalState(format!(
+				"Multi-sig accounts need between 1 and {} public keys, got {}",
+				NeoConstants::MAX_PUBLIC_KEYS_PER_MULTI_SIG,
+				public_keys.len()
+			)))
+		}
+		if threshold == 0 || threshold as usize > public_keys.len() {
+			return Err(ProviderError::IllegalState(format!(
+				"Signing threshold must be between 1 and {}, got {}",
+				public_keys.len(),
+				threshold
+			)))
+		}
+
+		let script = VerificationScript::from_multi_sig(public_keys, threshold as u8);
+		let script_hash = ScriptHash::from_script(script.script());
+
+		Ok(Self {
+			address_or_scripthash: AddressOrScriptHash::ScriptHash(script_hash),
+			label: Some(script_hash.to_address()),
+			verification_script: Some(script),
+			signing_threshold: Some(threshold),
+			nr_of_participants: Some(public_keys.len() as u32),
+			..Default::default()
+		})
+	}
+
+	/// Returns the public keys of a multi-sig account in the order they appear in its
+	/// verification script, which is also the order signatures must be given in.
+	pub fn multi_sig_public_keys(&self) -> Result<Vec<Secp256r1PublicKey>, ProviderError> {
+		match &self.verification_script {
+			Some(script) if script.is_multi_sig() =>
+				script.get_public_keys().map_err(|e| ProviderError::IllegalState(e.to_string())),
+			_ => Err(ProviderError::IllegalState("Account is not MultiSig".to_string())),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_config::TestConstants;
+
+	const PUBLIC_KEY_1: &str = "033a4d051b04b7fc0230d2b1aaedfd5a84be279a5361a7358db665ad7857787f1b";
+	const PUBLIC_KEY_2: &str = "020dcc7648c78a3118f2612866fe83ef19f40304f623399e1211a10f2b3d1c05cf";
+	const PUBLIC_KEY_3: &str = "038570e95d85825286db92c78317679bdd8ffe3c90d0af84291bf64132b66fcc99";
+
+	fn public_key(encoded: &str) -> Secp256r1PublicKey {
+		Secp256r1PublicKey::from_encoded(encoded).unwrap()
+	}
+
+	#[test]
+	fn test_multi_sig_committee_account() {
+		let account =
+			Account::multi_sig(&[public_key(TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY)], 1)
+				.unwrap();
+
+		assert_eq!(
+			account.verification_script.as_ref().unwrap().script(),
+			&hex::decode(TestConstants::COMMITTEE_ACCOUNT_VERIFICATION_SCRIPT).unwrap()
+		);
+		assert_eq!(
+			account.address_or_scripthash().address(),
+			TestConstants::COMMITTEE_ACCOUNT_ADDRESS
+		);
+		assert_eq!(
+			account.get_script_hash(),
+			H160::from_hex(TestConstants::COMMITTEE_ACCOUNT_SCRIPT_HASH).unwrap()
+		);
+		assert_eq!(account.get_signing_threshold().unwrap(), 1);
+		assert_eq!(account.get_nr_of_participants().unwrap(), 1);
+		assert!(account.is_multi_sig());
+	}
+
+	#[test]
+	fn test_multi_sig_key_order() {
+		let keys = [public_key(PUBLIC_KEY_1), public_key(PUBLIC_KEY_2), public_key(PUBLIC_KEY_3)];
+		let reversed = [keys[2].clone(), keys[1].clone(), keys[0].clone()];
+
+		let account = Account::multi_sig(&keys, 2).unwrap();
+		let account_reversed = Account::multi_sig(&reversed, 2).unwrap();
+		assert_eq!(account.get_script_hash(), account_reversed.get_script_hash());
+
+		// keys are ordered by their X coordinate, like the node orders EC points
+		let ordered = account.multi_sig_public_keys().unwrap();
+		assert_eq!(ordered, vec![keys[1].clone(), keys[0].clone(), keys[2].clone()]);
+		assert_eq!(account.get_signing_threshold().unwrap(), 2);
+		assert_eq!(account.get_nr_of_participants().unwrap(), 3);
+	}
+
+	#[test]
+	fn test_from_contract() {
+		let hash = H160::from_hex(TestConstants::COMMITTEE_ACCOUNT_SCRIPT_HASH).unwrap();
+		let account = Account::from_contract(&hash);
+
+		assert!(account.is_deployed_contract);
+		assert!(account.verification_script.is_none());
+		assert!(account.key_pair.is_none());
+		assert!(!account.is_multi_sig());
+		assert_eq!(account.get_script_hash(), hash);
+		assert_eq!(account.label.as_deref(), Some(TestConstants::COMMITTEE_ACCOUNT_ADDRESS));
+	}
+
+	#[test]
+	fn test_multi_sig_invalid_threshold() {
+		let keys = [public_key(PUBLIC_KEY_1), public_key(PUBLIC_KEY_2)];
+		assert!(Account::multi_sig(&keys, 0).is_err());
+		assert!(Account::multi_sig(&keys, 3).is_err());
+		assert!(Account::multi_sig(&[], 1).is_err());
+	}
+}
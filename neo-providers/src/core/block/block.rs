@@ -0,0 +1,163 @@
+use crate::core::{
+	block::{header::Header, merkle_tree},
+	transaction::{transaction::Transaction, transaction_error::TransactionError},
+};
+use base64::{engine::general_purpose, Engine};
+use neo_codec::{encode::NeoSerializable, Decoder};
+use primitive_types::H256;
+
+/// A block as the node serializes it, e.g. in the result of `getblock` with `verbose` set to
+/// false.
+///
+/// Decoding only checks the layout. Use [`Block::verify_merkle_root`] to check that the
+/// transactions are the ones the header commits to.
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "TransactionError", validate = "Self::validate")]
+pub struct Block {
+	pub header: Header,
+	#[neo(list, max = u16::MAX as usize)]
+	pub transactions: Vec<Transaction>,
+}
+
+impl Block {
+	/// Decodes a block from the base64 string returned by `get_raw_block`.
+	pub fn from_base64(raw: &str) -> Result<Self, TransactionError> {
+		let bytes = general_purpose::STANDARD
+			.decode(raw)
+			.map_err(|_| TransactionError::InvalidBlock)?;
+		let mut reader = Decoder::new(&bytes);
+		let block = Self::decode(&mut reader)?;
+		if reader.available() > 0 {
+			return Err(TransactionError::InvalidBlock)
+		}
+		Ok(block)
+	}
+
+	pub fn hash(&self) -> H256 {
+		self.header.hash()
+	}
+
+	/// Computes the Merkle root of the transaction hashes.
+	pub fn merkle_root(&self) -> H256 {
+		let hashes: Vec<H256> = self.transactions.iter().map(Transaction::calculate_hash).collect();
+		merkle_tree::compute_root(&hashes)
+	}
+
+	/// Returns whether the header's Merkle root matches the transactions.
+	pub fn verify_merkle_root(&self) -> bool {
+		self.merkle_root() == self.header.merkle_root
+	}
+
+	fn validate(&self) -> Result<(), TransactionError> {
+		// Transactions in a block are signed, with one witness per signer.
+		if self.transactions.iter().any(|tx| tx.witnesses.len() != tx.signers.len()) {
+			return Err(TransactionError::InvalidTransaction)
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::{
+		account::{Account, AccountTrait},
+		transaction::{
+			signers::{account_signer::AccountSigner, signer::Signer},
+			witness::Witness,
+		},
+	};
+	use primitive_types::H160;
+	use std::str::FromStr;
+
+	fn transaction(nonce: i32) -> Transaction {
+		let account = Account::from_script_hash(&H160::repeat_byte(0x05)).unwrap();
+		Transaction {
+			nonce,
+			valid_until_block: 100,
+			sys_fee: 1,
+			net_fee: 2,
+			signers: vec![Signer::Account(AccountSigner::called_by_entry(&account).unwrap())],
+			script: vec![0x11, 0x40],
+			witnesses: vec![Witness::from_scripts(vec![0x0c], vec![0x11])],
+			..Default::default()
+		}
+	}
+
+	fn block(transactions: Vec<Transaction>) -> Block {
+		let hashes: Vec<H256> = transactions.iter().map(Transaction::calculate_hash).collect();
+		Block {
+			header: Header {
+				version: 0,
+				prev_hash: H256::repeat_byte(0x01),
+				merkle_root: merkle_tree::compute_root(&hashes),
+				timestamp: 1_700_000_000_000,
+				nonce: 7,
+				index: 42,
+				primary_index: 0,
+				next_consensus: H160::repeat_byte(0x04),
+				witness: Witness::from_scripts(vec![0x0c, 0x01, 0xaa], vec![0x11]),
+			},
+			transactions,
+		}
+	}
+
+	#[test]
+	fn test_block_round_trip() {
+		let block = block(vec![transaction(1), transaction(2), transaction(3)]);
+		let bytes = block.to_array();
+		assert_eq!(bytes.len(), block.size());
+
+		let decoded = Block::from_base64(&general_purpose::STANDARD.encode(&bytes)).unwrap();
+		assert_eq!(decoded.header, block.header);
+		assert_eq!(decoded.to_array(), bytes);
+		assert!(decoded.verify_merkle_root());
+	}
+
+	#[test]
+	fn test_block_verify_merkle_root() {
+		let empty = block(vec![]);
+		assert_eq!(empty.header.merkle_root, H256::zero());
+		assert!(empty.verify_merkle_root());
+
+		let single = block(vec![transaction(1)]);
+		assert_eq!(single.merkle_root(), transaction(1).calculate_hash());
+
+		let mut tampered = block(vec![transaction(1), transaction(2)]);
+		tampered.transactions[1].sys_fee += 1;
+		assert!(!tampered.verify_merkle_root());
+
+		let mut reordered = block(vec![transaction(1), transaction(2)]);
+		reordered.transactions.swap(0, 1);
+		assert!(!reordered.verify_merkle_root());
+	}
+
+	#[test]
+	fn test_mainnet_genesis_block() {
+		// `getblock 0 false` on N3 MainNet, the genesis block has no transactions
+		let block = Block::from_base64(
+			"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACI6hnvVQEAAB2sK3wAAAAAAAAAAABrEj3YvscYZIhSu8eFleNTagWPnwEAAREA",
+		)
+		.unwrap();
+		assert_eq!(
+			block.hash(),
+			H256::from_str("1f4d1defa46faa5e7b9b8d3f79a06bec777d7c26c4aa5f6f5899a291daa87c15")
+				.unwrap()
+		);
+		assert!(block.transactions.is_empty());
+		assert!(block.verify_merkle_root());
+	}
+
+	#[test]
+	fn test_block_rejects_invalid_input() {
+		let bytes = block(vec![transaction(1)]).to_array();
+		for len in 0..bytes.len() {
+			assert!(Block::decode(&mut Decoder::new(&bytes[..len])).is_err());
+		}
+
+		let mut trailing = bytes.clone();
+		trailing.push(0);
+		assert!(Block::from_base64(&general_purpose::STANDARD.encode(&trailing)).is_err());
+		assert!(Block::from_base64("not base64").is_err());
+	}
+}
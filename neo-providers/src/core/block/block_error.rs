@@ -0,0 +1,22 @@
+use crate::core::transaction::transaction_error::TransactionError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BlockError {
+	#[error("Expected header {expected}, got {actual}")]
+	UnexpectedIndex { expected: u32, actual: u32 },
+	#[error("Header {0} doesn't link to the previous header")]
+	PrevHashMismatch(u32),
+	#[error("Header {0} isn't newer than the previous header")]
+	InvalidTimestamp(u32),
+	#[error("Witness of header {0} doesn't match the previous next consensus")]
+	NextConsensusMismatch(u32),
+	#[error("Invalid witness for header {0}: {1}")]
+	InvalidWitness(u32, String),
+	#[error("Invalid signatures for header {0}")]
+	InvalidSignature(u32),
+	#[error(transparent)]
+	Transaction(#[from] TransactionError),
+	#[error("Provider error: {0}")]
+	Provider(String),
+}
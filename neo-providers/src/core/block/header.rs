@@ -0,0 +1,176 @@
+use crate::core::transaction::{transaction_error::TransactionError, witness::Witness};
+use base64::{engine::general_purpose, Engine};
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_crypto::hash::HashableForVec;
+use primitive_types::{H160, H256};
+
+/// A block header as the node serializes it, e.g. in the result of `getblockheader` with
+/// `verbose` set to false.
+///
+/// Hashes are kept in the order of the hex strings returned in RPC JSON, the reverse of the wire.
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "TransactionError", validate = "Self::validate")]
+pub struct Header {
+	pub version: u32,
+	pub prev_hash: H256,
+	pub merkle_root: H256,
+	/// Milliseconds since the Unix epoch.
+	pub timestamp: u64,
+	pub nonce: u64,
+	pub index: u32,
+	pub primary_index: u8,
+	pub next_consensus: H160,
+	#[neo(with = "single_witness")]
+	pub witness: Witness,
+}
+
+impl Header {
+	/// Size of the fields covered by the hash, everything before the witness.
+	pub const UNSIGNED_SIZE: usize = 4 + 32 + 32 + 8 + 8 + 4 + 1 + 20;
+
+	/// Returns the hash of the header, the SHA-256 of its fields without the witness.
+	pub fn hash(&self) -> H256 {
+		let bytes = self.to_array();
+		let mut hash = bytes[..Self::UNSIGNED_SIZE].hash256();
+		hash.reverse();
+		H256::from_slice(&hash)
+	}
+
+	/// Returns the data the consensus nodes sign, the network magic followed by the hash in
+	/// wire order.
+	pub fn sign_data(&self, network_magic: u32) -> Vec<u8> {
+		let mut data = network_magic.to_le_bytes().to_vec();
+		data.extend_from_slice(&self.hash().to_array());
+		data
+	}
+
+	/// Decodes a header from the base64 string returned by `get_raw_block_header`.
+	pub fn from_base64(raw: &str) -> Result<Self, TransactionError> {
+		let bytes = general_purpose::STANDARD
+			.decode(raw)
+			.map_err(|_| TransactionError::InvalidBlock)?;
+		let mut reader = Decoder::new(&bytes);
+		let header = Self::decode(&mut reader)?;
+		if reader.available() > 0 {
+			return Err(TransactionError::InvalidBlock)
+		}
+		Ok(header)
+	}
+
+	fn validate(&self) -> Result<(), TransactionError> {
+		if self.version != 0 {
+			return Err(TransactionError::InvalidBlock)
+		}
+		Ok(())
+	}
+}
+
+/// The header has a list of witnesses on the wire, which must hold exactly one.
+mod single_witness {
+	use super::*;
+	use neo_codec::{CodecError, Decoder, Encoder};
+
+	pub fn size(witness: &Witness) -> usize {
+		1 + witness.size()
+	}
+
+	pub fn encode(witness: &Witness, writer: &mut Encoder) {
+		writer.write_u8(1);
+		witness.encode(writer);
+	}
+
+	pub fn decode(reader: &mut Decoder) -> Result<Witness, CodecError> {
+		if reader.read_u8()? != 1 {
+			return Err(CodecError::InvalidFormat)
+		}
+		reader.read_serializable()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	const MAINNET_GENESIS_HEADER: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACI6hnvVQEAAB2sK3wAAAAAAAAAAABrEj3YvscYZIhSu8eFleNTagWPnwEAARE=";
+
+	fn header() -> Header {
+		Header {
+			version: 0,
+			prev_hash: H256::repeat_byte(0x01),
+			merkle_root: H256::repeat_byte(0x02),
+			timestamp: 1_700_000_000_000,
+			nonce: 0x0102030405060708,
+			index: 42,
+			primary_index: 3,
+			next_consensus: H160::repeat_byte(0x04),
+			witness: Witness::from_scripts(vec![0x0c, 0x01, 0xaa], vec![0x11]),
+		}
+	}
+
+	#[test]
+	fn test_header_round_trip() {
+		let header = header();
+		let bytes = header.to_array();
+		assert_eq!(bytes.len(), header.size());
+		assert_eq!(bytes.len(), Header::UNSIGNED_SIZE + 1 + 4 + 2);
+		assert_eq!(&bytes[..4], &[0, 0, 0, 0]);
+		assert_eq!(&bytes[68..76], &1_700_000_000_000u64.to_le_bytes());
+		assert_eq!(bytes[Header::UNSIGNED_SIZE], 1);
+
+		let decoded = Header::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded, header);
+	}
+
+	#[test]
+	fn test_header_hash() {
+		let header = header();
+		let mut expected = header.to_array()[..Header::UNSIGNED_SIZE].hash256();
+		expected.reverse();
+		assert_eq!(header.hash().as_bytes(), expected.as_slice());
+
+		// The witness isn't covered by the hash.
+		let mut other = header.clone();
+		other.witness = Witness::new();
+		assert_eq!(other.hash(), header.hash());
+		other.nonce += 1;
+		assert_ne!(other.hash(), header.hash());
+	}
+
+	#[test]
+	fn test_mainnet_genesis_header() {
+		// `getblockheader 0 false` on N3 MainNet
+		let header = Header::from_base64(MAINNET_GENESIS_HEADER).unwrap();
+		assert_eq!(
+			header.hash(),
+			H256::from_str("1f4d1defa46faa5e7b9b8d3f79a06bec777d7c26c4aa5f6f5899a291daa87c15")
+				.unwrap()
+		);
+		assert_eq!(header.prev_hash, H256::zero());
+		assert_eq!(header.merkle_root, H256::zero());
+		assert_eq!(header.timestamp, 1468595301000);
+		assert_eq!(header.nonce, 2083236893);
+		assert_eq!(
+			header.next_consensus,
+			H160::from_str("9f8f056a53e39585c7bb52886418c7bed83d126b").unwrap()
+		);
+		assert_eq!(general_purpose::STANDARD.encode(header.to_array()), MAINNET_GENESIS_HEADER);
+	}
+
+	#[test]
+	fn test_header_rejects_invalid_input() {
+		let bytes = header().to_array();
+
+		let mut bad_version = bytes.clone();
+		bad_version[0] = 1;
+		assert!(Header::decode(&mut Decoder::new(&bad_version)).is_err());
+
+		let mut bad_witness_count = bytes.clone();
+		bad_witness_count[Header::UNSIGNED_SIZE] = 2;
+		assert!(Header::decode(&mut Decoder::new(&bad_witness_count)).is_err());
+
+		for len in 0..bytes.len() {
+			assert!(Header::decode(&mut Decoder::new(&bytes[..len])).is_err());
+		}
+	}
+}
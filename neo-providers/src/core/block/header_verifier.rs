@@ -0,0 +1,322 @@
+use crate::{
+	core::block::{block_error::BlockError, header::Header},
+	Middleware,
+};
+use neo_crypto::keys::Secp256r1Signature;
+use neo_types::{op_code::OpCode, script_hash::ScriptHashExtension};
+use primitive_types::H160;
+
+/// Verifies a chain of headers offline, starting from a trusted checkpoint.
+///
+/// Each header must extend the latest verified one: its index and timestamp increase, its
+/// `prev_hash` is the hash of the latest header, and its witness is the multi-sig of the
+/// consensus nodes the latest header named in `next_consensus`, signed over the header hash with
+/// the network magic.
+#[derive(Debug, Clone)]
+pub struct HeaderVerifier {
+	network_magic: u32,
+	latest: Header,
+}
+
+impl HeaderVerifier {
+	/// Size of a `PUSHDATA1` of a 64 byte signature in an invocation script.
+	const SIGNATURE_PUSH_SIZE: usize = 66;
+
+	/// Creates a verifier that trusts `checkpoint` without verifying it.
+	pub fn new(checkpoint: Header, network_magic: u32) -> Self {
+		Self { network_magic, latest: checkpoint }
+	}
+
+	/// The latest verified header, or the checkpoint if nothing has been verified yet.
+	pub fn latest(&self) -> &Header {
+		&self.latest
+	}
+
+	/// Verifies that `header` extends the latest header and makes it the latest.
+	pub fn verify(&mut self, header: Header) -> Result<(), BlockError> {
+		let index = header.index;
+		let expected = self
+			.latest
+			.index
+			.checked_add(1)
+			.ok_or(BlockError::UnexpectedIndex { expected: self.latest.index, actual: index })?;
+		if index != expected {
+			return Err(BlockError::UnexpectedIndex { expected, actual: index })
+		}
+		if header.prev_hash != self.latest.hash() {
+			return Err(BlockError::PrevHashMismatch(index))
+		}
+		if header.timestamp <= self.latest.timestamp {
+			return Err(BlockError::InvalidTimestamp(index))
+		}
+		self.verify_witness(&header)?;
+
+		self.latest = header;
+		Ok(())
+	}
+
+	/// Verifies `headers` in order, stopping at the first invalid one.
+	pub fn verify_all(
+		&mut self,
+		headers: impl IntoIterator<Item = Header>,
+	) -> Result<(), BlockError> {
+		headers.into_iter().try_for_each(|header| self.verify(header))
+	}
+
+	/// Fetches and verifies the headers after the latest one up to and including `index`.
+	pub async fn sync<M: Middleware>(&mut self, client: &M, index: u32) -> Result<(), BlockError> {
+		while self.latest.index < index {
+			let raw = client
+				.get_raw_block_header_by_index(self.latest.index + 1)
+				.await
+				.map_err(|e| BlockError::Provider(e.to_string()))?;
+			self.verify(Header::from_base64(&raw)?)?;
+		}
+		Ok(())
+	}
+
+	fn verify_witness(&self, header: &Header) -> Result<(), BlockError> {
+		let index = header.index;
+		let verification = &header.witness.verification;
+		let script_hash = H160::from_script(verification.script());
+		if script_hash != self.latest.next_consensus {
+			return Err(BlockError::NextConsensusMismatch(index))
+		}
+
+		let invalid = |e: String| BlockError::InvalidWitness(index, e);
+		let public_keys = verification.get_public_keys().map_err(|e| invalid(e.to_string()))?;
+		let threshold = verification.get_signing_threshold().map_err(|e| invalid(e.to_string()))?;
+		let signatures = Self::read_signatures(&header.witness.invocation)
+			.ok_or_else(|| invalid("malformed invocation script".to_string()))?;
+		if signatures.len() != threshold {
+			return Err(invalid(format!(
+				"expected {} signatures, got {}",
+				threshold,
+				signatures.len()
+			)))
+		}
+
+		// Like `System.Crypto.CheckMultisig`, the signatures must be in the same order as the
+		// public keys they belong to.
+		let sign_data = header.sign_data(self.network_magic);
+		let mut keys = public_keys.iter();
+		for signature in &signatures {
+			if !keys.by_ref().any(|key| key.verify(&sign_data, signature).is_ok()) {
+				return Err(BlockError::InvalidSignature(index))
+			}
+		}
+		Ok(())
+	}
+
+	/// Reads the signatures pushed by a consensus invocation script, `None` if the script does
+	/// anything else.
+	fn read_signatures(script: &[u8]) -> Option<Vec<Secp256r1Signature>> {
+		if script.is_empty() || script.len() % Self::SIGNATURE_PUSH_SIZE != 0 {
+			return None
+		}
+		script
+			.chunks(Self::SIGNATURE_PUSH_SIZE)
+			.map(|push| match push {
+				[op, 64, signature @ ..] if *op == OpCode::PushData1 as u8 =>
+					Secp256r1Signature::from_bytes(signature).ok(),
+				_ => None,
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::transaction::{
+			invocation_script::InvocationScript, verification_script::VerificationScript,
+			witness::Witness,
+		},
+		Provider,
+	};
+	use base64::{engine::general_purpose, Engine};
+	use neo_codec::encode::NeoSerializable;
+	use neo_crypto::key_pair::KeyPair;
+	use primitive_types::H256;
+
+	const MAGIC: u32 = 860833102;
+
+	const MAINNET_VALIDATORS_SCRIPT: &str = "150c2102486fd15702c4490a26703112a5cc1d0923fd697a33406bd5a1c00e0013b09a700c21024c7b7fb6c310fccf1ba33b082519d82964ea93868d676662d4a59ad548df0e7d0c2102aaec38470f6aad0042c6e877cfd8087d2676b0f516fddd362801b9bd3936399e0c2103b209fd4f53a7170ea4444e0cb0a6bb6a53c2bd016926989cf85f9b0fba17a70c0c2103b8d9d5771d8f513aa0869b9cc8d50986403b78c6da36890638c3d46a5adce04a0c2102ca0e27697b9c248f6f16e085fd0061e26f44da85b58ee835c110caa5ec3ba5540c2102df48f60e8f3e01c48ff40b9b7f1310d7a8b2a193188befe1c2e3df740e89509317419ed0dc3a";
+
+	struct Consensus {
+		key_pairs: Vec<KeyPair>,
+		script: VerificationScript,
+	}
+
+	impl Consensus {
+		fn new() -> Self {
+			let mut key_pairs: Vec<KeyPair> = (0..4).map(|_| KeyPair::new_random()).collect();
+			key_pairs.sort_by(|a, b| a.public_key().partial_cmp(&b.public_key()).unwrap());
+			let public_keys: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+			Self { script: VerificationScript::from_multi_sig(&public_keys, 3), key_pairs }
+		}
+
+		fn next_consensus(&self) -> H160 {
+			H160::from_script(self.script.script())
+		}
+
+		fn sign(&self, header: &mut Header, signers: &[usize]) {
+			let sign_data = header.sign_data(MAGIC);
+			let signatures: Vec<_> = signers
+				.iter()
+				.map(|&i| self.key_pairs[i].private_key().sign_tx(&sign_data).unwrap())
+				.collect();
+			header.witness = Witness::from_scripts_obj(
+				InvocationScript::from_signatures(&signatures),
+				self.script.clone(),
+			);
+		}
+	}
+
+	fn checkpoint(consensus: &Consensus) -> Header {
+		Header {
+			version: 0,
+			prev_hash: H256::repeat_byte(0x01),
+			merkle_root: H256::zero(),
+			timestamp: 1_700_000_000_000,
+			nonce: 1,
+			index: 100,
+			primary_index: 0,
+			next_consensus: consensus.next_consensus(),
+			witness: Witness::new(),
+		}
+	}
+
+	fn next(consensus: &Consensus, prev: &Header) -> Header {
+		let mut header = Header {
+			prev_hash: prev.hash(),
+			timestamp: prev.timestamp + 15_000,
+			nonce: prev.nonce + 1,
+			index: prev.index + 1,
+			..prev.clone()
+		};
+		consensus.sign(&mut header, &[0, 1, 3]);
+		header
+	}
+
+	#[test]
+	fn test_verify_chain() {
+		let consensus = Consensus::new();
+		let checkpoint = checkpoint(&consensus);
+		let first = next(&consensus, &checkpoint);
+		let second = next(&consensus, &first);
+
+		let mut verifier = HeaderVerifier::new(checkpoint, MAGIC);
+		verifier.verify_all([first, second.clone()]).unwrap();
+		assert_eq!(verifier.latest(), &second);
+	}
+
+	#[test]
+	fn test_verify_rejects_broken_linkage() {
+		let consensus = Consensus::new();
+		let checkpoint = checkpoint(&consensus);
+		let mut verifier = HeaderVerifier::new(checkpoint.clone(), MAGIC);
+
+		let mut skipped = next(&consensus, &checkpoint);
+		skipped.index += 1;
+		consensus.sign(&mut skipped, &[0, 1, 2]);
+		assert!(matches!(verifier.verify(skipped), Err(BlockError::UnexpectedIndex { .. })));
+
+		let mut unlinked = next(&consensus, &checkpoint);
+		unlinked.prev_hash = H256::zero();
+		consensus.sign(&mut unlinked, &[0, 1, 2]);
+		assert!(matches!(verifier.verify(unlinked), Err(BlockError::PrevHashMismatch(101))));
+
+		let mut stale = next(&consensus, &checkpoint);
+		stale.timestamp = checkpoint.timestamp;
+		consensus.sign(&mut stale, &[0, 1, 2]);
+		assert!(matches!(verifier.verify(stale), Err(BlockError::InvalidTimestamp(101))));
+
+		assert_eq!(verifier.latest(), &checkpoint);
+	}
+
+	#[test]
+	fn test_verify_rejects_invalid_witness() {
+		let consensus = Consensus::new();
+		let checkpoint = checkpoint(&consensus);
+		let mut verifier = HeaderVerifier::new(checkpoint.clone(), MAGIC);
+
+		let other = Consensus::new();
+		let mut wrong_consensus = next(&consensus, &checkpoint);
+		other.sign(&mut wrong_consensus, &[0, 1, 2]);
+		assert!(matches!(
+			verifier.verify(wrong_consensus),
+			Err(BlockError::NextConsensusMismatch(101))
+		));
+
+		let mut too_few = next(&consensus, &checkpoint);
+		consensus.sign(&mut too_few, &[0, 1]);
+		assert!(matches!(verifier.verify(too_few), Err(BlockError::InvalidWitness(101, _))));
+
+		let mut out_of_order = next(&consensus, &checkpoint);
+		consensus.sign(&mut out_of_order, &[1, 0, 2]);
+		assert!(matches!(verifier.verify(out_of_order), Err(BlockError::InvalidSignature(101))));
+
+		let mut modified = next(&consensus, &checkpoint);
+		modified.merkle_root = H256::repeat_byte(0xff);
+		assert!(matches!(verifier.verify(modified), Err(BlockError::InvalidSignature(101))));
+
+		let mut verifier = HeaderVerifier::new(checkpoint.clone(), MAGIC + 1);
+		assert!(matches!(
+			verifier.verify(next(&consensus, &checkpoint)),
+			Err(BlockError::InvalidSignature(101))
+		));
+	}
+
+	#[test]
+	fn test_mainnet_next_consensus() {
+		// `getblockheader 0 false` on N3 MainNet
+		let genesis = Header::from_base64("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACI6hnvVQEAAB2sK3wAAAAAAAAAAABrEj3YvscYZIhSu8eFleNTagWPnwEAARE=").unwrap();
+		assert_eq!(genesis.next_consensus.to_address(), "NVg7LjGcUSrgxgjX3zEgqaksfMaiS8Z6e1");
+
+		// Block 1 is witnessed by the 5-of-7 multi-sig of the MainNet standby validators
+		let script = VerificationScript::from(hex::decode(MAINNET_VALIDATORS_SCRIPT).unwrap());
+		assert_eq!(H160::from_script(script.script()), genesis.next_consensus);
+
+		let mut header = Header {
+			prev_hash: genesis.hash(),
+			timestamp: genesis.timestamp + 15_000,
+			index: 1,
+			..genesis.clone()
+		};
+		header.witness = Witness::from_scripts_obj(InvocationScript::from_signatures(&[]), script);
+		// The witness is the one of the next consensus, but it isn't signed
+		let mut verifier = HeaderVerifier::new(genesis, MAGIC);
+		assert!(matches!(verifier.verify(header), Err(BlockError::InvalidWitness(1, _))));
+	}
+
+	#[test]
+	fn test_read_signatures() {
+		assert!(HeaderVerifier::read_signatures(&[]).is_none());
+		assert!(HeaderVerifier::read_signatures(&[0x0c, 0x40]).is_none());
+
+		let mut script = vec![0x0c, 0x40];
+		script.extend([0u8; 64]);
+		assert!(HeaderVerifier::read_signatures(&script).is_none());
+		script[0] = 0x0d;
+		assert!(HeaderVerifier::read_signatures(&script).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_sync() {
+		let consensus = Consensus::new();
+		let checkpoint = checkpoint(&consensus);
+		let first = next(&consensus, &checkpoint);
+		let second = next(&consensus, &first);
+
+		let (provider, mock) = Provider::mocked();
+		for header in [&second, &first] {
+			mock.push(general_purpose::STANDARD.encode(header.to_array())).unwrap();
+		}
+
+		let mut verifier = HeaderVerifier::new(checkpoint, MAGIC);
+		verifier.sync(&provider, second.index).await.unwrap();
+		assert_eq!(verifier.latest(), &second);
+	}
+}
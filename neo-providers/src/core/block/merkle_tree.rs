@@ -0,0 +1,74 @@
+use neo_codec::encode::NeoSerializable;
+use neo_crypto::hash::HashableForVec;
+use primitive_types::H256;
+
+/// Computes the Merkle root of `hashes` the way the node does for block transactions.
+///
+/// Each parent is the double SHA-256 of its two children in wire order and a level with an odd
+/// number of nodes pairs its last node with itself. The root of a single hash is that hash and
+/// the root of no hashes is zero. Hashes are in the byte order of the RPC JSON.
+pub fn compute_root(hashes: &[H256]) -> H256 {
+	if hashes.is_empty() {
+		return H256::zero()
+	}
+
+	let mut level = hashes.to_vec();
+	while level.len() > 1 {
+		level = level
+			.chunks(2)
+			.map(|pair| {
+				let left = pair[0];
+				let right = pair.get(1).copied().unwrap_or(left);
+				parent(left, right)
+			})
+			.collect();
+	}
+	level[0]
+}
+
+fn parent(left: H256, right: H256) -> H256 {
+	let data = [left.to_array(), right.to_array()].concat();
+	let mut hash = data.hash256().hash256();
+	hash.reverse();
+	H256::from_slice(&hash)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hash(byte: u8) -> H256 {
+		H256::repeat_byte(byte)
+	}
+
+	#[test]
+	fn test_compute_root_trivial() {
+		assert_eq!(compute_root(&[]), H256::zero());
+		assert_eq!(compute_root(&[hash(1)]), hash(1));
+	}
+
+	#[test]
+	fn test_compute_root() {
+		assert_eq!(
+			compute_root(&[hash(1), hash(2)]),
+			H256::from_slice(
+				&hex::decode("b4100303b9e99ada4b479b0bb93d9b549cb057a1c4be08896bc982debe20ce39")
+					.unwrap()
+			)
+		);
+		// Children are hashed in wire order
+		let left: Vec<u8> = (0..32).collect();
+		let right: Vec<u8> = (32..64).collect();
+		assert_eq!(
+			compute_root(&[H256::from_slice(&left), H256::from_slice(&right)]),
+			H256::from_slice(
+				&hex::decode("acbeb35a6101ca45c44d286224eab1ab9948f0199eb12eba0623d8c30cf90610")
+					.unwrap()
+			)
+		);
+		assert_eq!(
+			compute_root(&[hash(1), hash(2), hash(3)]),
+			parent(parent(hash(1), hash(2)), parent(hash(3), hash(3)))
+		);
+	}
+}
@@ -0,0 +1,5 @@
+pub mod block;
+pub mod block_error;
+pub mod header;
+pub mod header_verifier;
+pub mod merkle_tree;
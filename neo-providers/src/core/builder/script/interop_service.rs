@@ -17,7 +17,7 @@ lazy_static! {
 pub enum InteropService {
 	#[strum(serialize = "System.Crypto.CheckSig")]
 	SystemCryptoCheckSig,
-	#[strum(serialize = "System.Crypto.CheckMultiSig")]
+	#[strum(serialize = "System.Crypto.CheckMultisig")]
 	SystemCryptoCheckMultiSig,
 	#[strum(serialize = "System.Contract.Call")]
 	SystemContractCall,
@@ -92,7 +92,7 @@ impl InteropService {
 			hash.clone()
 		} else {
 			let sha = self.to_string().as_bytes().to_vec().hash256();
-			let hash = hex::encode(sha)[..4].to_string();
+			let hash = hex::encode(&sha[..4]);
 			hashes.insert(self.to_string(), hash.clone());
 			hash
 		}
@@ -140,3 +140,21 @@ impl InteropService {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hash() {
+		// The first 4 bytes of the SHA256 of the name, as they follow `SYSCALL` in scripts
+		assert_eq!(InteropService::SystemCryptoCheckSig.hash(), "56e7b327");
+		assert_eq!(InteropService::SystemContractCall.hash(), "627d5b52");
+		// The node registers the lowercase `Multisig` name
+		assert_eq!(InteropService::SystemCryptoCheckMultiSig.hash(), "9ed0dc3a");
+		assert!(
+			InteropService::from_hash("56e7b327".to_string())
+				== Some(InteropService::SystemCryptoCheckSig)
+		);
+	}
+}
@@ -65,9 +65,7 @@ impl ScriptBuilder {
 	}
 
 	pub fn sys_call(&mut self, operation: InteropService) -> &mut Self {
-		self.op_code(&[OpCode::Syscall])
-			.push_data(operation.hash().into_bytes())
-			.unwrap()
+		self.op_code_with_arg(OpCode::Syscall, hex::decode(operation.hash()).unwrap())
 	}
 
 	pub fn push_params(&mut self, params: &[ContractParameter]) -> &mut Self {
@@ -225,7 +223,7 @@ impl ScriptBuilder {
 
 	pub fn build_verification_script(pub_key: &Secp256r1PublicKey) -> Bytes {
 		let mut sb = ScriptBuilder::new();
-		sb.push_data(pub_key.to_raw_bytes().to_vec())
+		sb.push_data(pub_key.get_encoded(true))
 			.unwrap()
 			.sys_call(InteropService::SystemCryptoCheckSig);
 		sb.to_bytes()
@@ -237,9 +235,9 @@ impl ScriptBuilder {
 	) -> Result<Bytes, BuilderError> {
 		let mut sb = ScriptBuilder::new();
 		sb.push_integer(BigInt::from(threshold)).unwrap();
-		pubkeys.sort_by(|a, b| a.to_raw_bytes().cmp(&b.to_raw_bytes()));
+		pubkeys.sort_by(|a, b| a.partial_cmp(b).unwrap());
 		for pk in pubkeys.iter() {
-			sb.push_data(pk.to_raw_bytes().to_vec()).unwrap();
+			sb.push_data(pk.get_encoded(true)).unwrap();
 		}
 		sb.push_integer(BigInt::from(pubkeys.len())).unwrap();
 		sb.sys_call(InteropService::SystemCryptoCheckMultiSig);
@@ -399,6 +397,13 @@ mod tests {
 		// assert_eq!(builder.to_bytes()[builder.len()-8..], hex!("001748768E00000000"));
 	}
 
+	#[test]
+	fn test_sys_call() {
+		let mut builder = ScriptBuilder::new();
+		builder.sys_call(InteropService::SystemCryptoCheckSig);
+		assert_eq!(builder.to_bytes(), hex!("4156e7b327"));
+	}
+
 	#[test]
 	fn test_verification_script() {
 		// let pubkey1 = hex!("035fdb1d1f06759547020891ae97c729327853aeb1256b6fe0473bc2e9fa42ff50");
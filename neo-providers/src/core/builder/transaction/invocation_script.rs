@@ -1,11 +1,14 @@
 use crate::core::{error::BuilderError, script::script_builder::ScriptBuilder};
 use getset::{Getters, Setters};
-use neo_codec::{encode::NeoSerializable, Decoder, Encoder};
-use neo_crypto::{hash::HashableForVec, key_pair::KeyPair, keys::Secp256r1Signature};
+use neo_codec::encode::NeoSerializable;
+use neo_crypto::{key_pair::KeyPair, keys::Secp256r1Signature};
 use neo_types::Bytes;
 use serde_derive::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters, Setters, Serialize, Deserialize)]
+#[derive(
+	Debug, Clone, PartialEq, Eq, Hash, Getters, Setters, Serialize, Deserialize, NeoSerializable,
+)]
+#[neo(error = "BuilderError")]
 #[getset(get_copy, set)]
 #[derive(educe::Educe)]
 // note `new` below: generate `new()` that calls Default
@@ -20,15 +23,17 @@ use serde_derive::{Deserialize, Serialize};
 	derive_more::From,
 )]
 pub struct InvocationScript {
+	#[neo(var_bytes, max = Self::MAX_SCRIPT_LENGTH)]
 	script: Bytes,
 }
 
 impl InvocationScript {
+	/// Maximum length of a witness invocation script accepted when decoding.
+	pub const MAX_SCRIPT_LENGTH: usize = 1024;
+
 	pub fn from_signature(signature: &Secp256r1Signature) -> Self {
 		let mut builder = ScriptBuilder::new();
-		builder
-			.push_data(signature.to_raw_bytes().to_vec())
-			.expect("TODO: panic message");
+		builder.push_data(signature.to_bytes().to_vec()).expect("TODO: panic message");
 		Self { script: builder.to_bytes() }
 	}
 
@@ -36,11 +41,11 @@ impl InvocationScript {
 		message: Bytes,
 		key_pair: &KeyPair,
 	) -> Result<Self, BuilderError> {
-		let message_hash = message.hash256();
-		let signature = key_pair.private_key.sign_tx(&message_hash)?;
+		// `sign_tx` hashes the message itself
+		let signature = key_pair.private_key.sign_tx(&message)?;
 		let mut builder = ScriptBuilder::new();
 		// Convert signature to bytes
-		let signature_bytes = signature.to_raw_bytes();
+		let signature_bytes = signature.to_bytes();
 		builder.push_data(signature_bytes.to_vec()).expect("Incorrect signature length");
 		Ok(Self { script: builder.to_bytes() })
 	}
@@ -48,7 +53,7 @@ impl InvocationScript {
 	pub fn from_signatures(signatures: &[Secp256r1Signature]) -> Self {
 		let mut builder = ScriptBuilder::new();
 		for signature in signatures {
-			let mut signature_bytes = signature.to_raw_bytes();
+			let mut signature_bytes = signature.to_bytes();
 			// signature.write_scalars(&mut signature_bytes).unwrap();
 
 			builder.push_data(signature_bytes.to_vec()).expect("Incorrect signature length");
@@ -56,25 +61,3 @@ impl InvocationScript {
 		Self { script: builder.to_bytes() }
 	}
 }
-
-impl NeoSerializable for InvocationScript {
-	type Error = BuilderError;
-
-	fn size(&self) -> usize {
-		self.script.len()
-	}
-
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_var_bytes(&self.script);
-	}
-
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let script = reader.read_var_bytes()?;
-		Ok(Self { script })
-	}
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
-	}
-}
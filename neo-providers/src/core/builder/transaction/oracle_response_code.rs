@@ -1,3 +1,4 @@
+use neo_codec::encode::NeoSerializable;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumString};
@@ -15,6 +16,7 @@ use strum_macros::{Display, EnumString};
 	Clone,
 	Hash,
 	Debug,
+	NeoSerializable,
 )]
 #[repr(u8)]
 pub enum OracleResponseCode {
@@ -11,106 +11,55 @@ use crate::core::{
 		witness_scope::WitnessScope,
 	},
 };
-use neo_codec::{
-	encode::{NeoSerializable, VarSizeTrait},
-	Decoder, Encoder,
-};
+use neo_codec::encode::NeoSerializable;
 use neo_config::NeoConstants;
 use primitive_types::H160;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Getters, Setters)]
+#[derive(Debug, Clone, Serialize, Deserialize, Getters, Setters, NeoSerializable)]
+#[neo(error = "TransactionError")]
 pub struct AccountSigner {
 	#[serde(
 		serialize_with = "serialize_script_hash",
 		deserialize_with = "deserialize_script_hash"
 	)]
 	signer_hash: H160,
+	#[neo(with = "crate::core::transaction::witness_scope::flags")]
 	scopes: Vec<WitnessScope>,
 	#[serde(
 		serialize_with = "serialize_vec_script_hash",
 		deserialize_with = "deserialize_vec_script_hash"
 	)]
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::CustomContracts)"
+	)]
 	allowed_contracts: Vec<H160>,
 	#[serde(
 		serialize_with = "serialize_vec_public_key",
 		deserialize_with = "deserialize_vec_public_key"
 	)]
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::CustomGroups)"
+	)]
 	allowed_groups: Vec<Secp256r1PublicKey>,
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::WitnessRules)"
+	)]
 	rules: Vec<WitnessRule>,
 	#[getset(get = "pub")]
+	#[neo(skip, default = "Account::from_address(signer_hash.to_address().as_str())?")]
 	pub account: Account,
+	#[neo(skip, default = "WitnessScope::None")]
 	scope: WitnessScope,
 }
 
-impl NeoSerializable for AccountSigner {
-	type Error = TransactionError;
-
-	fn size(&self) -> usize {
-		let mut size: usize = NeoConstants::HASH160_SIZE as usize;
-		if self.scopes.contains(&WitnessScope::CustomContracts) {
-			size += self.allowed_contracts.var_size();
-		}
-		if self.scopes.contains(&WitnessScope::CustomGroups) {
-			size += self.allowed_groups.var_size();
-		}
-		if self.scopes.contains(&WitnessScope::WitnessRules) {
-			size += self.rules.var_size();
-		}
-		size
-	}
-
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_serializable_fixed(&self.signer_hash);
-		writer.write_u8(WitnessScope::combine(&self.scopes));
-		if self.scopes.contains(&WitnessScope::CustomContracts) {
-			writer.write_serializable_variable_list(&self.allowed_contracts);
-		}
-		if self.scopes.contains(&WitnessScope::CustomGroups) {
-			writer.write_serializable_variable_list(&self.allowed_groups);
-		}
-		if self.scopes.contains(&WitnessScope::WitnessRules) {
-			writer.write_serializable_variable_list(&self.rules);
-		}
-	}
-
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
-	where
-		Self: Sized,
-	{
-		let signer_hash = reader.read_serializable::<H160>().unwrap();
-		let scopes = WitnessScope::split(reader.read_u8());
-		let mut allowed_contracts = vec![];
-		let mut allowed_groups = vec![];
-		let mut rules = vec![];
-		if scopes.contains(&WitnessScope::CustomContracts) {
-			allowed_contracts = reader.read_serializable_list::<H160>().unwrap();
-		}
-		if scopes.contains(&WitnessScope::CustomGroups) {
-			allowed_groups = reader.read_serializable_list::<Secp256r1PublicKey>().unwrap();
-		}
-		if scopes.contains(&WitnessScope::WitnessRules) {
-			rules = reader.read_serializable_list::<WitnessRule>().unwrap();
-		}
-		Ok(Self {
-			signer_hash,
-			scopes,
-			allowed_contracts,
-			allowed_groups,
-			rules,
-			account: Account::from_address(signer_hash.to_address().as_str()).unwrap(),
-			scope: WitnessScope::None,
-		})
-	}
-
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
-	}
-}
-
 impl PartialEq for AccountSigner {
 	fn eq(&self, other: &Self) -> bool {
 		self.signer_hash == other.signer_hash
@@ -192,7 +141,7 @@ impl AccountSigner {
 	fn new(account: &Account, scope: WitnessScope) -> Self {
 		Self {
 			signer_hash: account.get_script_hash().clone(),
-			scopes: vec![],
+			scopes: vec![scope.clone()],
 			allowed_contracts: vec![],
 			allowed_groups: vec![],
 			rules: vec![],
@@ -232,3 +181,20 @@ impl AccountSigner {
 		matches!(&self.account.verification_script(), Some(script) if script.is_multi_sig())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_config::TestConstants;
+
+	#[test]
+	fn test_scope() {
+		let hash = H160::from_hex(TestConstants::DEFAULT_ACCOUNT_SCRIPT_HASH).unwrap();
+		let signer = AccountSigner::called_by_entry_hash160(hash).unwrap();
+		assert_eq!(signer.get_signer_hash(), &hash);
+		assert_eq!(signer.get_scopes(), &vec![WitnessScope::CalledByEntry]);
+
+		let signer = AccountSigner::global_hash160(hash).unwrap();
+		assert_eq!(signer.get_scopes(), &vec![WitnessScope::Global]);
+	}
+}
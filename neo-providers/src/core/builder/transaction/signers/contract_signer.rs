@@ -1,5 +1,5 @@
 use crate::core::{
-	account::AccountTrait,
+	account::{Account, AccountTrait},
 	transaction::{
 		signers::{
 			account_signer::AccountSigner,
@@ -10,10 +10,7 @@ use crate::core::{
 		witness_scope::WitnessScope,
 	},
 };
-use neo_codec::{
-	encode::{NeoSerializable, VarSizeTrait},
-	Decoder, Encoder,
-};
+use neo_codec::encode::NeoSerializable;
 use neo_config::NeoConstants;
 use neo_crypto::keys::Secp256r1PublicKey;
 use neo_types::{contract_parameter::ContractParameter, *};
@@ -21,32 +18,52 @@ use primitive_types::H160;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone, Serialize, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Serialize, PartialEq, Deserialize, NeoSerializable)]
+#[neo(error = "TransactionError")]
 pub struct ContractSigner {
 	#[serde(
 		serialize_with = "serialize_script_hash",
 		deserialize_with = "deserialize_script_hash"
 	)]
 	signer_hash: H160,
+	#[neo(with = "crate::core::transaction::witness_scope::flags")]
 	scopes: Vec<WitnessScope>,
 	#[serde(
 		serialize_with = "serialize_vec_script_hash",
 		deserialize_with = "deserialize_vec_script_hash"
 	)]
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::CustomContracts)"
+	)]
 	allowed_contracts: Vec<H160>,
 	#[serde(
 		serialize_with = "serialize_vec_public_key",
 		deserialize_with = "deserialize_vec_public_key"
 	)]
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::CustomGroups)"
+	)]
 	allowed_groups: Vec<Secp256r1PublicKey>,
+	#[neo(
+		list,
+		max = NeoConstants::MAX_SIGNER_SUBITEMS as usize,
+		when = "scopes.contains(&WitnessScope::WitnessRules)"
+	)]
 	rules: Vec<WitnessRule>,
+	#[neo(skip)]
 	pub verify_params: Vec<ContractParameter>,
 	#[serde(
 		serialize_with = "serialize_script_hash",
 		deserialize_with = "deserialize_script_hash"
 	)]
 	#[serde(skip_deserializing)]
+	#[neo(skip, default = "signer_hash")]
 	contract_hash: H160,
+	#[neo(skip, default = "WitnessScope::None")]
 	scope: WitnessScope,
 }
 
@@ -120,8 +137,8 @@ impl ContractSigner {
 		verify_params: Vec<ContractParameter>,
 	) -> Self {
 		Self {
-			signer_hash: Default::default(),
-			scopes: vec![],
+			signer_hash: contract_hash,
+			scopes: vec![scope.clone()],
 			allowed_contracts: vec![],
 			allowed_groups: vec![],
 			rules: vec![],
@@ -138,72 +155,38 @@ impl ContractSigner {
 	pub fn global(contract_hash: H160, verify_params: &[ContractParameter]) -> Self {
 		Self::new(contract_hash, WitnessScope::Global, verify_params.to_vec())
 	}
-}
 
-impl NeoSerializable for ContractSigner {
-	type Error = TransactionError;
-
-	fn size(&self) -> usize {
-		let mut size: usize = NeoConstants::HASH160_SIZE as usize;
-		if self.scopes.contains(&WitnessScope::CustomContracts) {
-			size += self.allowed_contracts.var_size();
-		}
-		if self.scopes.contains(&WitnessScope::CustomGroups) {
-			size += self.allowed_groups.var_size();
+	/// Creates a called-by-entry signer for a contract account, see [`Account::from_contract`].
+	pub fn from_account(
+		account: &Account,
+		verify_params: &[ContractParameter],
+	) -> Result<Self, TransactionError> {
+		if !account.is_deployed_contract {
+			return Err(TransactionError::SignerConfiguration(
+				"Account is not a deployed contract".to_string(),
+			))
 		}
-		if self.scopes.contains(&WitnessScope::WitnessRules) {
-			size += self.rules.var_size();
-		}
-		size
+		Ok(Self::called_by_entry(account.get_script_hash(), verify_params))
 	}
 
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_serializable_fixed(&self.signer_hash);
-		writer.write_u8(WitnessScope::combine(&self.scopes));
-		if self.scopes.contains(&WitnessScope::CustomContracts) {
-			writer.write_serializable_variable_list(&self.allowed_contracts);
-		}
-		if self.scopes.contains(&WitnessScope::CustomGroups) {
-			writer.write_serializable_variable_list(&self.allowed_groups);
-		}
-		if self.scopes.contains(&WitnessScope::WitnessRules) {
-			writer.write_serializable_variable_list(&self.rules);
-		}
+	pub fn contract_hash(&self) -> &H160 {
+		&self.contract_hash
 	}
+}
 
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
-	where
-		Self: Sized,
-	{
-		let signer_hash = reader.read_serializable::<H160>().unwrap();
-		let scopes = WitnessScope::split(reader.read_u8());
-		let mut allowed_contracts = vec![];
-		let mut allowed_groups = vec![];
-		let mut rules = vec![];
-		if scopes.contains(&WitnessScope::CustomContracts) {
-			allowed_contracts = reader.read_serializable_list::<H160>().unwrap();
-		}
-		if scopes.contains(&WitnessScope::CustomGroups) {
-			allowed_groups = reader.read_serializable_list::<Secp256r1PublicKey>().unwrap();
-		}
-		if scopes.contains(&WitnessScope::WitnessRules) {
-			rules = reader.read_serializable_list::<WitnessRule>().unwrap();
-		}
-		Ok(Self {
-			signer_hash,
-			scopes,
-			allowed_contracts,
-			allowed_groups,
-			rules,
-			verify_params: vec![],
-			contract_hash: Default::default(),
-			scope: WitnessScope::None,
-		})
-	}
-
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scope() {
+		let hash = H160::repeat_byte(1);
+		let signer = ContractSigner::called_by_entry(hash, &[]);
+		// The contract is the signer
+		assert_eq!(signer.get_signer_hash(), &hash);
+		assert_eq!(signer.get_scopes(), &vec![WitnessScope::CalledByEntry]);
+
+		let signer = ContractSigner::global(hash, &[]);
+		assert_eq!(signer.get_scopes(), &vec![WitnessScope::Global]);
 	}
 }
@@ -316,6 +316,7 @@ impl Serialize for Signer {
 	}
 }
 
+// The wire format has no signer type, all variants share the `AccountSigner` layout.
 impl NeoSerializable for Signer {
 	type Error = TransactionError;
 
@@ -323,8 +324,7 @@ impl NeoSerializable for Signer {
 		match self {
 			Signer::Account(account_signer) => account_signer.size(),
 			Signer::Contract(contract_signer) => contract_signer.size(),
-			// Signer::Transaction(transaction_signer) => transaction_signer.size(),
-			_ => panic!("Unsupported signer type"),
+			Signer::Transaction(transaction_signer) => transaction_signer.size(),
 		}
 	}
 
@@ -332,8 +332,7 @@ impl NeoSerializable for Signer {
 		match self {
 			Signer::Account(account_signer) => account_signer.encode(writer),
 			Signer::Contract(contract_signer) => contract_signer.encode(writer),
-			// Signer::Transaction(transaction_signer) => transaction_signer.encode(writer),
-			_ => panic!("Unsupported signer type"),
+			Signer::Transaction(transaction_signer) => transaction_signer.encode(writer),
 		}
 	}
 
@@ -341,20 +340,12 @@ impl NeoSerializable for Signer {
 	where
 		Self: Sized,
 	{
-		match reader.read_u8() {
-			0 => Ok(Signer::Account(AccountSigner::decode(reader)?)),
-			1 => Ok(Signer::Contract(ContractSigner::decode(reader)?)),
-			// 2 => Ok(Signer::Transaction(TransactionSigner::decode(reader)?)),
-			_ => Err(TransactionError::InvalidTransaction),
-		}
+		Ok(Signer::Account(AccountSigner::decode(reader)?))
 	}
 
 	fn to_array(&self) -> Vec<u8> {
-		match self {
-			Signer::Account(account_signer) => account_signer.to_array(),
-			Signer::Contract(contract_signer) => contract_signer.to_array(),
-			// Signer::Transaction(transaction_signer) => transaction_signer.to_array(),
-			_ => panic!("Unsupported signer type"),
-		}
+		let mut writer = Encoder::new();
+		self.encode(&mut writer);
+		writer.to_bytes()
 	}
 }
@@ -154,17 +154,23 @@ impl NeoSerializable for TransactionSigner {
 		Self: Sized,
 	{
 		let mut signer = TransactionSigner::default();
-		signer.set_signer_hash(reader.read_serializable().unwrap());
-		let scopes = WitnessScope::split(reader.read_u8());
+		signer.set_signer_hash(reader.read_serializable()?);
+		let scopes = WitnessScope::split(reader.read_u8()?);
 		signer.set_scopes(scopes);
 		if signer.get_scopes().contains(&WitnessScope::CustomContracts) {
-			signer.allowed_contracts = Some(reader.read_serializable_list().unwrap());
+			signer.allowed_contracts = Some(
+				reader.read_serializable_list_max(NeoConstants::MAX_SIGNER_SUBITEMS as usize)?,
+			);
 		}
 		if signer.get_scopes().contains(&WitnessScope::CustomGroups) {
-			signer.allowed_groups = Some(reader.read_serializable_list().unwrap());
+			signer.allowed_groups = Some(
+				reader.read_serializable_list_max(NeoConstants::MAX_SIGNER_SUBITEMS as usize)?,
+			);
 		}
 		if signer.get_scopes().contains(&WitnessScope::WitnessRules) {
-			signer.rules = Some(reader.read_serializable_list().unwrap());
+			signer.rules = Some(
+				reader.read_serializable_list_max(NeoConstants::MAX_SIGNER_SUBITEMS as usize)?,
+			);
 		}
 		Ok(signer)
 	}
@@ -14,6 +14,7 @@ use neo_codec::{
 	encode::{NeoSerializable, VarSizeTrait},
 	Decoder, Encoder,
 };
+use neo_config::NeoConstants;
 use neo_crypto::hash::HashableForVec;
 use neo_types::{address::NameOrAddress, vm_state::VMState, *};
 use primitive_types::{H160, H256, U256};
@@ -110,11 +111,21 @@ impl Transaction {
 		let mut encoder = Encoder::new();
 		self.serialize_without_witnesses(&mut encoder);
 		let mut data = encoder.to_bytes().hash256();
-		data.splice(0..0, self.network_magic.unwrap().to_be_bytes());
+		data.splice(0..0, self.network_magic.unwrap().to_le_bytes());
 
 		Ok(data)
 	}
 
+	/// Computes the hash of the transaction, the SHA-256 of its serialization without witnesses,
+	/// in the byte order of the RPC JSON.
+	pub fn calculate_hash(&self) -> H256 {
+		let mut encoder = Encoder::new();
+		self.serialize_without_witnesses(&mut encoder);
+		let mut hash = encoder.to_bytes().hash256();
+		hash.reverse();
+		H256::from_slice(&hash)
+	}
+
 	fn serialize_without_witnesses(&self, writer: &mut Encoder) {
 		writer.write_u8(self.version);
 		writer.write_u32(self.nonce as u32);
@@ -155,24 +166,31 @@ impl NeoSerializable for Transaction {
 	where
 		Self: Sized,
 	{
-		let version = reader.read_u8();
-		let nonce = reader.read_u32();
-		let system_fee = reader.read_i64();
-		let network_fee = reader.read_i64();
-		let valid_until_block = reader.read_u32();
+		let version = reader.read_u8()?;
+		let nonce = reader.read_u32()?;
+		let system_fee = reader.read_i64()?;
+		let network_fee = reader.read_i64()?;
+		let valid_until_block = reader.read_u32()?;
 
 		// Read signers
-		let signers: Vec<Signer> = reader.read_serializable_list::<Signer>().unwrap();
+		let max_attributes = NeoConstants::MAX_TRANSACTION_ATTRIBUTES as usize;
+		let signers: Vec<Signer> = reader.read_serializable_list_max::<Signer>(max_attributes)?;
+		if signers.is_empty() {
+			return Err(TransactionError::NoSigners)
+		}
 
 		// Read attributes
-		let attributes: Vec<TransactionAttribute> =
-			reader.read_serializable_list::<TransactionAttribute>().unwrap();
+		let attributes: Vec<TransactionAttribute> = reader
+			.read_serializable_list_max::<TransactionAttribute>(max_attributes - signers.len())?;
 
-		let script = reader.read_var_bytes().unwrap().to_vec();
+		let script = reader.read_var_bytes_max(u16::MAX as usize)?;
+		if script.is_empty() {
+			return Err(TransactionError::EmptyScript)
+		}
 
 		let mut witnesses = vec![];
-		if (reader.available() > 0) {
-			witnesses.append(&mut reader.read_serializable_list::<Witness>().unwrap());
+		if reader.available() > 0 {
+			witnesses.append(&mut reader.read_serializable_list_max::<Witness>(signers.len())?);
 		}
 
 		Ok(Self {
@@ -202,3 +220,73 @@ impl NeoSerializable for Transaction {
 		writer.to_bytes()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::{account::Account, transaction::signers::account_signer::AccountSigner};
+	use neo_config::TestConstants;
+	use neo_crypto::key_pair::KeyPair;
+	use neo_types::op_code::OpCode;
+	use std::str::FromStr;
+
+	#[test]
+	fn test_hash_data_network_magic() {
+		let mut tx = Transaction::new();
+		// The N3 MainNet magic, "NEO3" in little-endian
+		tx.set_network_magic(860833102);
+		let data = tx.get_hash_data().unwrap();
+		assert_eq!(&data[..4], b"NEO3");
+		assert_eq!(data.len(), 36);
+	}
+
+	#[test]
+	fn test_signed_transaction_bytes() {
+		let private_key = hex::decode(TestConstants::DEFAULT_ACCOUNT_PRIVATE_KEY).unwrap();
+		let key_pair = KeyPair::from_private_key(&private_key.try_into().unwrap()).unwrap();
+		let account = Account::from_key_pair(key_pair.clone(), None, None).unwrap();
+
+		let mut tx = Transaction::new();
+		tx.nonce = 0x01020304;
+		tx.valid_until_block = 0x01020304;
+		tx.sys_fee = 10;
+		tx.net_fee = 1;
+		tx.signers = vec![AccountSigner::called_by_entry(&account).unwrap().into()];
+		tx.script = vec![OpCode::Push1 as u8];
+		tx.set_network_magic(860833102);
+		tx.add_witness(Witness::create(tx.get_hash_data().unwrap(), &key_pair).unwrap());
+
+		let expected = [
+			"00",               // version
+			"04030201",         // nonce
+			"0a00000000000000", // system fee
+			"0100000000000000", // network fee
+			"04030201",         // valid until block
+			// one signer, the script hash 69ecca587293047be4c59159bf8bc399985c160d in
+			// little-endian, called by entry
+			"01",
+			"0d165c9899c38bbf5991c5e47b04937258caec69",
+			"01",
+			"00",   // no attributes
+			"0111", // PUSH1
+			// one witness, the RFC 6979 signature over the network magic and the hash
+			"01",
+			"420c40",
+			"fecddfa6f29e14569e96b95a96ad83f1c65e93b622f409fb8d2cb31266be1006",
+			"1ac86c6f8baecef9996bddb38400cdf6bb7c7ab979e3c507d82f2fab8a696ec3",
+			"28",
+			TestConstants::DEFAULT_ACCOUNT_VERIFICATION_SCRIPT,
+		]
+		.concat();
+		assert_eq!(hex::encode(tx.to_array()), expected);
+		assert_eq!(
+			tx.calculate_hash(),
+			H256::from_str("979c6fd8d73ba2f16c04e47e305eaa1c7f1168390175a00254adaa7d0d8c1d67")
+				.unwrap()
+		);
+
+		let decoded = Transaction::decode(&mut Decoder::new(&tx.to_array())).unwrap();
+		assert_eq!(decoded.signers[0].get_signer_hash(), &account.get_script_hash());
+		assert_eq!(decoded.to_array(), tx.to_array());
+	}
+}
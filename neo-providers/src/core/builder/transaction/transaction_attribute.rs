@@ -1,74 +1,73 @@
 use crate::core::transaction::transaction_error::TransactionError;
-use neo_codec::{encode::NeoSerializable, CodecError, Decoder, Encoder};
-use neo_types::{Base64Encode, ExternBase64};
-use num_bigint::BigInt;
-use rustc_serialize::base64::FromBase64;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::hash::Hasher;
+use neo_codec::{encode::NeoSerializable, Decoder};
+use serde::{Deserialize, Serialize};
 
 use super::oracle_response_code::OracleResponseCode;
 
-#[derive(Serialize, Deserialize, PartialEq, Hash, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Hash, Debug, Clone, NeoSerializable)]
 #[serde(tag = "type")]
+#[neo(error = "TransactionError")]
 pub enum TransactionAttribute {
 	#[serde(rename = "HighPriority")]
+	#[neo(tag = 0x01)]
 	HighPriority,
 
 	#[serde(rename = "OracleResponse")]
+	#[neo(tag = 0x11)]
 	OracleResponse(OracleResponse),
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Hash, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Hash, Debug, Clone, NeoSerializable)]
+#[neo(error = "TransactionError")]
 struct OracleResponse {
-	pub id: u32,
+	pub id: u64,
 	pub response_code: OracleResponseCode,
-	pub result: String,
+	#[serde(with = "oracle_result")]
+	#[neo(var_bytes, max = TransactionAttribute::MAX_RESULT_SIZE)]
+	pub result: Vec<u8>,
+}
+
+/// The oracle result is base64 encoded in JSON but goes on the wire as raw var-bytes, so it's
+/// kept decoded and invalid base64 is rejected when deserializing.
+mod oracle_result {
+	use super::TransactionAttribute;
+	use neo_types::Base64Encode;
+	use rustc_serialize::base64::FromBase64;
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S>(result: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&result.to_base64())
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		let result = String::deserialize(deserializer)?
+			.from_base64()
+			.map_err(|err| D::Error::custom(format!("invalid oracle result: {}", err)))?;
+		if result.len() > TransactionAttribute::MAX_RESULT_SIZE {
+			return Err(D::Error::custom(format!(
+				"oracle result exceeds {} bytes",
+				TransactionAttribute::MAX_RESULT_SIZE
+			)))
+		}
+		Ok(result)
+	}
 }
 
 impl TransactionAttribute {
 	pub const MAX_RESULT_SIZE: usize = 0xffff;
 
 	pub fn to_bytes(&self) -> Vec<u8> {
-		let mut bytes = vec![];
-
-		match self {
-			TransactionAttribute::HighPriority => {
-				bytes.push(0x01);
-			},
-			TransactionAttribute::OracleResponse(OracleResponse { id, response_code, result }) => {
-				bytes.push(0x11);
-				bytes.extend(&id.to_be_bytes());
-				bytes.push(response_code.clone() as u8);
-				bytes.extend(result.as_bytes());
-			},
-		}
-
-		bytes
+		self.to_array()
 	}
 
 	pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
-		match bytes[0] {
-			0x01 => Ok(TransactionAttribute::HighPriority),
-			0x11 => {
-				if bytes.len() < 9 {
-					return Err("Not enough bytes for OracleResponse")
-				}
-				let mut array = [0; 8];
-				let slice_len = bytes[1..9].len();
-				array[8 - slice_len..].copy_from_slice(&bytes[1..9]);
-				let id = u64::from_be_bytes(array);
-				let response_code = OracleResponseCode::try_from(bytes[9]).unwrap();
-				let result =
-					String::from_utf8(bytes[10..].to_vec()).map_err(|_| "Invalid UTF-8").unwrap();
-
-				Ok(TransactionAttribute::OracleResponse(OracleResponse {
-					id: id as u32,
-					response_code,
-					result,
-				}))
-			},
-			_ => Err("Invalid attribute type byte"),
-		}
+		Self::decode(&mut Decoder::new(bytes)).map_err(|_| "Invalid transaction attribute")
 	}
 
 	pub fn to_json(&self) -> String {
@@ -76,54 +75,28 @@ impl TransactionAttribute {
 	}
 }
 
-impl NeoSerializable for TransactionAttribute {
-	type Error = TransactionError;
-
-	fn size(&self) -> usize {
-		match self {
-			TransactionAttribute::HighPriority => 1,
-			TransactionAttribute::OracleResponse(OracleResponse { id, response_code, result }) =>
-				1 + 9 + result.len(),
-		}
-	}
-
-	fn encode(&self, writer: &mut Encoder) {
-		match self {
-			TransactionAttribute::HighPriority => {
-				writer.write_u8(0x01);
-			},
-			TransactionAttribute::OracleResponse(OracleResponse { id, response_code, result }) => {
-				writer.write_u8(0x11);
-				let mut v = id.to_be_bytes();
-				v.reverse();
-				writer.write(&v);
-				writer.write_u8(response_code.clone() as u8);
-				writer.write_var_bytes(result.from_base64().unwrap().as_slice());
-			},
-		}
-	}
-
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		match reader.read_u8() {
-			0x01 => Ok(TransactionAttribute::HighPriority),
-			0x11 => {
-				let id = reader.read_u32();
-				let response_code = OracleResponseCode::try_from(reader.read_u8()).unwrap();
-				let result = reader.read_var_bytes().unwrap().to_base64();
-
-				Ok(TransactionAttribute::OracleResponse(OracleResponse {
-					id,
-					response_code,
-					result,
-				}))
-			},
-			_ => Err(TransactionError::InvalidTransaction),
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn test_oracle_response_json() {
+		let json = json!({ "type": "OracleResponse", "id": 1, "response_code": "Success", "result": "AQID" });
+		let attribute: TransactionAttribute = serde_json::from_value(json.clone()).unwrap();
+		match &attribute {
+			TransactionAttribute::OracleResponse(response) =>
+				assert_eq!(response.result, [1, 2, 3]),
+			attribute => panic!("unexpected attribute {:?}", attribute),
 		}
+		assert_eq!(serde_json::to_value(&attribute).unwrap(), json);
+		assert_eq!(TransactionAttribute::from_bytes(&attribute.to_bytes()).unwrap(), attribute);
 	}
 
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+	#[test]
+	fn test_invalid_oracle_result() {
+		let json = json!({ "type": "OracleResponse", "id": 1, "response_code": "Success", "result": "A?==" });
+		let err = serde_json::from_value::<TransactionAttribute>(json).unwrap_err();
+		assert!(err.to_string().contains("invalid oracle result"));
 	}
 }
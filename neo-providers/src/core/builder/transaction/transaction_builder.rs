@@ -21,7 +21,7 @@ use neo_codec::encode::NeoSerializable;
 use neo_config::NeoConstants;
 use neo_types::{
 	contract_parameter::ContractParameter, public_key_to_script_hash, script_hash::ScriptHash,
-	Bytes,
+	Base64Encode, Bytes,
 };
 use once_cell::sync::Lazy;
 use primitive_types::H160;
@@ -40,10 +40,14 @@ use crate::{
 		account::{Account, AccountTrait},
 		builder::{
 			error::BuilderError,
-			transaction::{transaction_error::TransactionError, witness::Witness},
+			transaction::{
+				invocation_script::InvocationScript, transaction_error::TransactionError,
+				witness::Witness,
+			},
 		},
 		transaction::{
 			signers::{
+				contract_signer::ContractSigner,
 				signer::{Signer, SignerType},
 				transaction_signer::TransactionSigner,
 			},
@@ -196,6 +200,12 @@ impl<P: JsonRpcClient> TransactionBuilder<P> {
 		self
 	}
 
+	// Set the provider used to calculate fees
+	pub fn provider(&mut self, provider: &'static Provider<P>) -> &mut Self {
+		self.provider = Some(provider);
+		self
+	}
+
 	// Get unsigned transaction
 	pub async fn get_unsigned_tx(&mut self) -> Result<Transaction, TransactionError> {
 		// Validate configuration
@@ -228,21 +238,26 @@ impl<P: JsonRpcClient> TransactionBuilder<P> {
 			return Err(TransactionError::NoScript)
 		}
 
+		let valid_until_block = self.valid_until_block.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"The block until which the transaction is valid is not set".to_string(),
+			)
+		})?;
+		let provider = self.provider.ok_or_else(|| {
+			TransactionError::IllegalState("No provider to calculate the fees".to_string())
+		})?;
+
 		let mut tx = Transaction::new();
-		// 	self.version,
-		// 	self.nonce,
-		// 	self.valid_until_block.unwrap(),
-		// 	self.clone().signers,
-		// 	0,
-		// 	0,
-		// 	self.clone().attributes,
-		// 	self.clone().script.unwrap(),
-		// 	vec![],
-		// );
+		tx.version = self.version;
+		tx.nonce = self.nonce as i32;
+		tx.valid_until_block = valid_until_block as i32;
+		tx.signers = self.signers.clone();
+		tx.attributes = self.attributes.clone();
+		tx.script = self.script.clone().unwrap();
 
 		// Get fees
-		let system_fee = 0; //self.get_system_fee().await.unwrap();
-		let network_fee = 0; //self.get_network_fee(&tx).await.unwrap();
+		let system_fee = self.get_system_fee(provider).await? + self.additional_system_fee;
+		let network_fee = self.get_network_fee(provider, &tx).await? + self.additional_network_fee;
 
 		// Check sender balance if needed
 		if let Some(fee_consumer) = &self.fee_consumer {
@@ -252,30 +267,99 @@ impl<P: JsonRpcClient> TransactionBuilder<P> {
 			}
 		}
 
-		// tx.set_network_fee(network_fee as i64);
-		// tx.set_system_fee(system_fee as i64);
-		// Build transaction
+		tx.sys_fee = system_fee as i64;
+		tx.net_fee = network_fee as i64;
 		Ok(tx)
 	}
 
-	// async fn get_system_fee(&self) -> Result<u64, TransactionError> {
-	// 	let script = self.script.as_ref().unwrap();
-	//
-	// 	let response = NEO_INSTANCE
-	// 		.read()
-	// 		.unwrap()
-	// 		.invoke_script(script.to_hex(), vec![self.signers[0].clone()])
-	// 		.request()
-	// 		.await
-	// 		.unwrap();
-	// 	Ok(u64::from_str(response.gas_consumed.as_str()).unwrap()) // example
-	// }
+	/// Calculates the system fee of the script as the GAS `invokescript` consumes running it.
+	async fn get_system_fee(&self, provider: &Provider<P>) -> Result<u64, TransactionError> {
+		let script = self.script.as_ref().ok_or(TransactionError::NoScript)?;
+		let result = provider.invoke_script(script.to_base64(), self.signers.clone()).await?;
+		if result.has_state_fault() {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"The script faulted while calculating the system fee: {}",
+				result.exception.unwrap_or_default()
+			)))
+		}
 
-	async fn get_network_fee(&mut self, tx: &Transaction) -> Result<u64, TransactionError> {
-		let fee = self.provider.unwrap().calculate_network_fee(tx.to_array().to_hex()).await?;
+		u64::from_str(&result.gas_consumed).map_err(|_| {
+			TransactionError::IllegalState(format!(
+				"The node returned an invalid amount of GAS consumed: {}",
+				result.gas_consumed
+			))
+		})
+	}
+
+	/// Calculates the network fee of `tx` with the node.
+	///
+	/// The fee is calculated on a copy of `tx` carrying the witnesses its signers will provide.
+	/// Contract signers get the `verify` parameters as invocation script and an empty verification
+	/// script, so the node prices them by running `verify`. Each contract is checked with
+	/// `invokecontractverify` first, as a `verify` that does not return `true` would make the fee
+	/// calculation and the transaction fail.
+	async fn get_network_fee(
+		&self,
+		provider: &Provider<P>,
+		tx: &Transaction,
+	) -> Result<u64, TransactionError> {
+		let mut tx = tx.clone();
+		tx.witnesses = vec![];
+		for signer in &self.signers {
+			if let Some(contract_signer) = signer.as_contract_signer() {
+				self.check_contract_verify(provider, contract_signer).await?;
+			}
+			tx.add_witness(Self::fee_witness(signer)?);
+		}
+
+		let fee = provider.calculate_network_fee(tx.to_array().to_base64()).await?;
 		Ok(fee)
 	}
 
+	// Witness used to calculate the network fee of a signer
+	fn fee_witness(signer: &Signer) -> Result<Witness, TransactionError> {
+		match signer {
+			Signer::Account(account_signer) => match account_signer.account.verification_script() {
+				Some(script) =>
+					Ok(Witness::from_scripts_obj(InvocationScript::new(), script.clone())),
+				None => Err(TransactionError::SignerConfiguration(format!(
+					"Account {} has no verification script to calculate the network fee with",
+					account_signer.account.address_or_scripthash().address()
+				))),
+			},
+			Signer::Contract(contract_signer) =>
+				Witness::create_contract_witness(contract_signer.verify_params.clone())
+					.map_err(|e| TransactionError::TransactionConfiguration(e.to_string())),
+			Signer::Transaction(_) => Err(TransactionError::SignerConfiguration(
+				"Transaction signers cannot be used to build transactions".to_string(),
+			)),
+		}
+	}
+
+	async fn check_contract_verify(
+		&self,
+		provider: &Provider<P>,
+		contract_signer: &ContractSigner,
+	) -> Result<(), TransactionError> {
+		let result = provider
+			.invoke_contract_verify(
+				*contract_signer.contract_hash(),
+				contract_signer.verify_params.clone(),
+				self.signers.clone(),
+			)
+			.await?;
+
+		let verified = result.stack.first().and_then(|item| item.as_bool());
+		if result.has_state_fault() || verified != Some(true) {
+			return Err(TransactionError::SignerConfiguration(format!(
+				"The verify method of contract {:?} does not return true: {}",
+				contract_signer.contract_hash(),
+				result.exception.unwrap_or_default()
+			)))
+		}
+		Ok(())
+	}
+
 	async fn get_sender_balance(&self) -> Result<u64, TransactionError> {
 		// Call network
 		let sender = &self.signers[0];
@@ -392,3 +476,146 @@ impl<P: JsonRpcClient> TransactionBuilder<P> {
 	// 	Ok(result.stack[0].as_int().unwrap() as u64)
 	// }
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		core::{
+			responses::neo_network_fee::NeoNetworkFee,
+			transaction::signers::contract_signer::ContractSigner,
+		},
+		MockProvider,
+	};
+	use neo_types::{
+		invocation_result::{InvocationResult, NeoVMStateType},
+		stack_item::StackItem,
+	};
+
+	fn contract_hash() -> H160 {
+		H160::from_str("05859de95ccbbd5668e0f055b208273634d4657f").unwrap()
+	}
+
+	fn invocation_result(
+		state: NeoVMStateType,
+		gas_consumed: &str,
+		stack: Vec<StackItem>,
+	) -> InvocationResult {
+		InvocationResult::new(
+			String::new(),
+			state,
+			gas_consumed.to_string(),
+			None,
+			None,
+			None,
+			stack,
+			None,
+			None,
+			None,
+		)
+	}
+
+	fn verify_result(verified: bool) -> InvocationResult {
+		invocation_result(
+			NeoVMStateType::Halt,
+			"1007390",
+			vec![StackItem::Boolean { value: verified }],
+		)
+	}
+
+	fn script_result() -> InvocationResult {
+		invocation_result(NeoVMStateType::Halt, "984060", vec![])
+	}
+
+	fn builder(mock: Provider<MockProvider>) -> TransactionBuilder<MockProvider> {
+		let account = Account::from_contract(&contract_hash());
+		let signer = ContractSigner::from_account(&account, &[ContractParameter::integer(1)]);
+
+		let mut builder = TransactionBuilder::new();
+		builder.provider(Box::leak(Box::new(mock))).set_script(vec![0x11]);
+		builder.valid_until_block(100).unwrap();
+		builder.set_signers(vec![signer.unwrap().into()]);
+		builder
+	}
+
+	#[tokio::test]
+	async fn test_contract_signer_network_fee() {
+		let (provider, mock) = Provider::mocked();
+		// responses are popped from the back
+		mock.push(NeoNetworkFee { network_fee: 1_230_000 }).unwrap();
+		mock.push(verify_result(true)).unwrap();
+		mock.push(script_result()).unwrap();
+
+		let tx = builder(provider).get_unsigned_tx().await.unwrap();
+		assert_eq!(tx.net_fee, 1_230_000);
+		assert_eq!(tx.sys_fee, 984_060);
+		assert_eq!(tx.signers[0].get_signer_hash(), &contract_hash());
+	}
+
+	#[tokio::test]
+	async fn test_additional_fees() {
+		let (provider, mock) = Provider::mocked();
+		mock.push(NeoNetworkFee { network_fee: 1_230_000 }).unwrap();
+		mock.push(verify_result(true)).unwrap();
+		mock.push(script_result()).unwrap();
+
+		let mut tx_builder = builder(provider);
+		tx_builder.additional_system_fee = 100;
+		tx_builder.additional_network_fee = 10;
+		let tx = tx_builder.get_unsigned_tx().await.unwrap();
+		assert_eq!(tx.sys_fee, 984_160);
+		assert_eq!(tx.net_fee, 1_230_010);
+	}
+
+	#[tokio::test]
+	async fn test_faulting_script() {
+		let (provider, mock) = Provider::mocked();
+		mock.push(invocation_result(NeoVMStateType::Fault, "0", vec![])).unwrap();
+
+		let result = builder(provider).get_unsigned_tx().await;
+		assert!(matches!(result, Err(TransactionError::TransactionConfiguration(_))));
+	}
+
+	#[tokio::test]
+	async fn test_missing_provider_or_valid_until_block() {
+		let (provider, _) = Provider::mocked();
+		let mut tx_builder = builder(provider);
+		tx_builder.valid_until_block = None;
+		let result = tx_builder.get_unsigned_tx().await;
+		assert!(matches!(result, Err(TransactionError::TransactionConfiguration(_))));
+
+		let (provider, _) = Provider::mocked();
+		let mut tx_builder = builder(provider);
+		tx_builder.provider = None;
+		let result = tx_builder.get_unsigned_tx().await;
+		assert!(matches!(result, Err(TransactionError::IllegalState(_))));
+	}
+
+	#[tokio::test]
+	async fn test_contract_signer_verify_fails() {
+		let (provider, mock) = Provider::mocked();
+		mock.push(verify_result(false)).unwrap();
+		mock.push(script_result()).unwrap();
+
+		let result = builder(provider).get_unsigned_tx().await;
+		assert!(matches!(result, Err(TransactionError::SignerConfiguration(_))));
+	}
+
+	#[test]
+	fn test_contract_fee_witness() {
+		let signer: Signer =
+			ContractSigner::called_by_entry(contract_hash(), &[ContractParameter::integer(1)])
+				.into();
+		let witness = TransactionBuilder::<MockProvider>::fee_witness(&signer).unwrap();
+
+		// PUSH1 as invocation script, no verification script
+		assert_eq!(witness.invocation.to_vec(), vec![0x11]);
+		assert!(witness.verification.script().is_empty());
+	}
+
+	#[test]
+	fn test_from_account_requires_contract() {
+		let account = Account::from_script_hash(&contract_hash()).unwrap();
+		assert!(ContractSigner::from_account(&account, &[]).is_err());
+	}
+}
@@ -3,7 +3,8 @@ use crate::core::{
 	script::{interop_service::InteropService, script_builder::ScriptBuilder},
 };
 use getset::{Getters, Setters};
-use neo_codec::{encode::NeoSerializable, Decoder, Encoder};
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_config::NeoConstants;
 use neo_crypto::keys::{PublicKeyExtension, Secp256r1PublicKey, Secp256r1Signature};
 use neo_types::{op_code::OpCode, Bytes};
 use num_bigint::BigInt;
@@ -11,13 +12,20 @@ use primitive_types::H160;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, vec};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters, Setters, Serialize, Deserialize)]
+#[derive(
+	Debug, Clone, PartialEq, Eq, Hash, Getters, Setters, Serialize, Deserialize, NeoSerializable,
+)]
+#[neo(error = "BuilderError")]
 pub struct VerificationScript {
 	#[getset(get = "pub", set = "pub")]
+	#[neo(var_bytes, max = Self::MAX_SCRIPT_LENGTH)]
 	script: Bytes,
 }
 
 impl VerificationScript {
+	/// Maximum length of a witness verification script accepted when decoding.
+	pub const MAX_SCRIPT_LENGTH: usize = 1024;
+
 	pub fn new() -> Self {
 		Self { script: Bytes::new() }
 	}
@@ -29,79 +37,102 @@ impl VerificationScript {
 	pub fn from_public_key(public_key: &Secp256r1PublicKey) -> Self {
 		let mut builder = ScriptBuilder::new();
 		builder
-			.push_data(public_key.to_raw_bytes().to_vec())
+			.push_data(public_key.get_encoded(true))
 			.unwrap()
-			.op_code(&vec![OpCode::Syscall])
-			.push_data(InteropService::SystemCryptoCheckSig.hash().into_bytes())
-			.unwrap();
+			.sys_call(InteropService::SystemCryptoCheckSig);
 		Self::from(builder.to_bytes())
 	}
 
+	/// Builds the verification script of a `threshold`-of-`public_keys.len()` multi-sig account.
+	///
+	/// The keys are sorted the same way the node sorts them, so the same set of keys always
+	/// results in the same script hash regardless of the order they are passed in.
 	pub fn from_multi_sig(public_keys: &[Secp256r1PublicKey], threshold: u8) -> Self {
-		// Build multi-sig script
+		let mut public_keys = public_keys.to_vec();
+		public_keys.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
 		let mut builder = ScriptBuilder::new();
 		builder
 			.push_integer(BigInt::from(threshold))
 			.expect("Threshold must be between 1 and 16");
-		for key in public_keys {
-			builder.push_data(key.to_vec()).unwrap();
+		for key in &public_keys {
+			builder.push_data(key.get_encoded(true)).unwrap();
 		}
 		builder
 			.push_integer(BigInt::from(public_keys.len()))
 			.unwrap()
-			.op_code(vec![OpCode::Syscall].as_slice())
-			.push_data(InteropService::SystemCryptoCheckMultiSig.hash().into_bytes())
-			.unwrap();
+			.sys_call(InteropService::SystemCryptoCheckMultiSig);
 		Self::from(builder.to_bytes())
 	}
 
 	pub fn is_single_sig(&self) -> bool {
-		self.script.len() == 35
+		self.script.len() == 40
 			&& self.script[0] == OpCode::PushData1 as u8
-			&& self.script[34] == OpCode::Syscall as u8
+			&& self.script[1] == 33
+			&& self.script[35] == OpCode::Syscall as u8
+			&& self.script[36..] == Self::interop_hash(InteropService::SystemCryptoCheckSig)
 	}
 
 	pub fn is_multi_sig(&self) -> bool {
-		if self.script.len() < 37 {
-			return false
-		}
-
-		let mut reader = Decoder::new(&self.script);
-
-		let n = reader.by_ref().read_var_int().unwrap();
-		if !(1..16).contains(&n) {
-			return false
-		}
+		self.parse_multi_sig().is_some()
+	}
 
-		let mut m = 0;
-		while reader.by_ref().read_u8() == OpCode::PushData1 as u8 {
-			let len = reader.by_ref().read_u8();
-			if len != 33 {
-				return false
+	/// Parses a multi-sig script into its signing threshold and public keys.
+	///
+	/// Returns `None` if the script is not a well formed multi-sig verification script.
+	fn parse_multi_sig(&self) -> Option<(usize, Vec<Secp256r1PublicKey>)> {
+		let script = &self.script;
+		let (threshold, mut offset) = Self::read_push_int(script, 0)?;
+
+		let mut public_keys = vec![];
+		while script.get(offset) == Some(&(OpCode::PushData1 as u8)) {
+			if script.get(offset + 1) != Some(&33) {
+				return None
 			}
-			let _ = reader.by_ref().skip(33);
-			m += 1;
+			let key = script.get(offset + 2..offset + 35)?;
+			public_keys.push(Secp256r1PublicKey::from_bytes(key).ok()?);
+			offset += 35;
 		}
 
-		if !(m >= n && m <= 16) {
-			return false
+		let (nr_of_keys, offset) = Self::read_push_int(script, offset)?;
+		if nr_of_keys != public_keys.len()
+			|| nr_of_keys > NeoConstants::MAX_PUBLIC_KEYS_PER_MULTI_SIG as usize
+			|| threshold < 1
+			|| threshold > nr_of_keys
+		{
+			return None
 		}
 
-		// additional checks
-		let service_bytes = &self.script[self.script.len() - 4..];
-		if service_bytes != &InteropService::SystemCryptoCheckMultiSig.hash().into_bytes() {
-			return false
+		if script.get(offset) != Some(&(OpCode::Syscall as u8))
+			|| script.get(offset + 1..)?
+				!= Self::interop_hash(InteropService::SystemCryptoCheckMultiSig)
+		{
+			return None
 		}
 
-		if m != reader.by_ref().read_var_int().unwrap() {
-			return false
-		}
+		Some((threshold, public_keys))
+	}
 
-		if reader.by_ref().read_u8() != OpCode::Syscall as u8 {
-			return false
+	/// Reads an integer pushed by `PUSH1`..`PUSH16`, `PUSHINT8` or `PUSHINT16` at `offset`.
+	///
+	/// Returns the value and the offset of the next instruction.
+	fn read_push_int(script: &[u8], offset: usize) -> Option<(usize, usize)> {
+		let opcode = *script.get(offset)?;
+		match opcode {
+			op if op >= OpCode::Push1 as u8 && op <= OpCode::Push16 as u8 =>
+				Some(((op - OpCode::Push0 as u8) as usize, offset + 1)),
+			op if op == OpCode::PushInt8 as u8 =>
+				Some((*script.get(offset + 1)? as usize, offset + 2)),
+			op if op == OpCode::PushInt16 as u8 => {
+				let bytes = script.get(offset + 1..offset + 3)?;
+				Some((u16::from_le_bytes([bytes[0], bytes[1]]) as usize, offset + 3))
+			},
+			_ => None,
 		}
+	}
 
-		true
+	fn interop_hash(service: InteropService) -> Vec<u8> {
+		hex::decode(service.hash()).unwrap()
 	}
 
 	// other methods
@@ -113,12 +144,16 @@ impl VerificationScript {
 		let mut reader = Decoder::new(&self.script);
 		let mut signatures = vec![];
 
-		while reader.by_ref().read_u8() == OpCode::PushData1 as u8 {
-			let len = reader.by_ref().read_u8();
-			let sig =
-				Secp256r1Signature::from_bytes(&reader.by_ref().read_bytes(len as usize).unwrap())
-					.unwrap();
-			signatures.push(sig);
+		while reader.read_u8() == Ok(OpCode::PushData1 as u8) {
+			let sig = reader
+				.read_u8()
+				.and_then(|len| reader.read_bytes(len as usize))
+				.ok()
+				.and_then(|bytes| Secp256r1Signature::from_bytes(&bytes).ok());
+			match sig {
+				Some(sig) => signatures.push(sig),
+				None => break,
+			}
 		}
 
 		signatures
@@ -127,28 +162,15 @@ impl VerificationScript {
 	pub fn get_public_keys(&self) -> Result<Vec<Secp256r1PublicKey>, BuilderError> {
 		if self.is_single_sig() {
 			let mut reader = Decoder::new(&self.script);
-			reader.by_ref().read_u8(); // skip pushdata1
-			reader.by_ref().read_u8(); // skip length
-
-			let mut point = [0; 33];
-			point.copy_from_slice(&reader.by_ref().read_bytes(33).unwrap());
+			reader.read_u8()?; // skip pushdata1
+			reader.read_u8()?; // skip length
 
-			let key = Secp256r1PublicKey::from_bytes(&point).unwrap();
+			let point = reader.read_bytes(33)?;
+			let key = Secp256r1PublicKey::from_bytes(&point)?;
 			return Ok(vec![key])
 		}
 
-		if self.is_multi_sig() {
-			let mut reader = Decoder::new(&self.script);
-			reader.by_ref().read_var_int().unwrap(); // skip threshold
-
-			let mut keys = vec![];
-			while reader.by_ref().read_u8() == OpCode::PushData1 as u8 {
-				reader.by_ref().read_u8(); // skip length
-				let mut point = [0; 33];
-				point.copy_from_slice(&reader.by_ref().read_bytes(33).unwrap());
-				keys.push(Secp256r1PublicKey::from_bytes(&point).unwrap());
-			}
-
+		if let Some((_, keys)) = self.parse_multi_sig() {
 			return Ok(keys)
 		}
 
@@ -158,9 +180,8 @@ impl VerificationScript {
 	pub fn get_signing_threshold(&self) -> Result<usize, BuilderError> {
 		if self.is_single_sig() {
 			Ok(1)
-		} else if self.is_multi_sig() {
-			let reader = &mut Decoder::new(&self.script);
-			Ok(reader.by_ref().read_var_int()? as usize)
+		} else if let Some((threshold, _)) = self.parse_multi_sig() {
+			Ok(threshold)
 		} else {
 			Err(BuilderError::InvalidScript("Invalid verification script".to_string()))
 		}
@@ -173,24 +194,34 @@ impl VerificationScript {
 	}
 }
 
-impl NeoSerializable for VerificationScript {
-	type Error = BuilderError;
-
-	fn size(&self) -> usize {
-		self.script.len()
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_config::TestConstants;
+
+	#[test]
+	fn test_from_public_key() {
+		let public_key =
+			Secp256r1PublicKey::from_encoded(TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY).unwrap();
+		let script = VerificationScript::from_public_key(&public_key);
+
+		// The compressed key is pushed, like the node does
+		assert_eq!(
+			script.script(),
+			&hex::decode(TestConstants::DEFAULT_ACCOUNT_VERIFICATION_SCRIPT).unwrap()
+		);
+		assert!(script.is_single_sig());
+		assert_eq!(script.get_public_keys().unwrap(), vec![public_key]);
 	}
 
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_var_bytes(&self.script);
-	}
-
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let script = reader.read_var_bytes()?;
-		Ok(Self { script })
-	}
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+	#[test]
+	fn test_from_multi_sig() {
+		let public_key =
+			Secp256r1PublicKey::from_encoded(TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY).unwrap();
+		let script = VerificationScript::from_multi_sig(&[public_key], 1);
+		assert_eq!(
+			script.script(),
+			&hex::decode(TestConstants::COMMITTEE_ACCOUNT_VERIFICATION_SCRIPT).unwrap()
+		);
 	}
 }
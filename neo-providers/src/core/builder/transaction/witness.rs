@@ -3,7 +3,7 @@ use crate::core::{
 	script::script_builder::ScriptBuilder,
 	transaction::{invocation_script::InvocationScript, verification_script::VerificationScript},
 };
-use neo_codec::{encode::NeoSerializable, Decoder, Encoder};
+use neo_codec::encode::NeoSerializable;
 use neo_crypto::{
 	key_pair::KeyPair,
 	keys::{Secp256r1PublicKey, Secp256r1Signature},
@@ -11,7 +11,8 @@ use neo_crypto::{
 use neo_types::{contract_parameter::ContractParameter, Bytes};
 use serde::{Deserialize, Serialize};
 
-#[derive(Hash, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[derive(Hash, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, NeoSerializable)]
+#[neo(error = "BuilderError")]
 pub struct Witness {
 	pub invocation: InvocationScript,
 	pub verification: VerificationScript,
@@ -39,8 +40,7 @@ impl Witness {
 	pub fn create(message_to_sign: Bytes, key_pair: &KeyPair) -> Result<Self, BuilderError> {
 		let invocation_script =
 			InvocationScript::from_message_and_key_pair(message_to_sign, key_pair).unwrap();
-		let verification_script =
-			VerificationScript::from(key_pair.public_key().to_raw_bytes().to_vec());
+		let verification_script = VerificationScript::from_public_key(&key_pair.public_key());
 		Ok(Self { invocation: invocation_script, verification: verification_script })
 	}
 
@@ -87,27 +87,3 @@ impl Witness {
 		})
 	}
 }
-
-impl NeoSerializable for Witness {
-	type Error = BuilderError;
-
-	fn size(&self) -> usize {
-		self.invocation.size() + self.verification.size()
-	}
-
-	fn encode(&self, writer: &mut Encoder) {
-		self.invocation.encode(writer);
-		self.verification.encode(writer);
-	}
-
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let invocation = InvocationScript::decode(reader)?;
-		let verification = VerificationScript::decode(reader)?;
-		Ok(Self { invocation, verification })
-	}
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
-	}
-}
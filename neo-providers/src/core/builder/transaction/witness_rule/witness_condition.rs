@@ -225,26 +225,45 @@ impl NeoSerializable for WitnessCondition {
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let byte = reader.read_u8();
+		Self::decode_nested(reader, WitnessCondition::MAX_NESTING_DEPTH)
+	}
+
+	fn to_array(&self) -> Vec<u8> {
+		let mut writer = Encoder::new();
+		self.encode(&mut writer);
+		writer.to_bytes()
+	}
+}
+
+impl WitnessCondition {
+	/// Decodes a condition, allowing `max_nest_depth` more levels of `Not`, `And` and `Or`.
+	fn decode_nested(
+		reader: &mut Decoder,
+		max_nest_depth: usize,
+	) -> Result<Self, TransactionError> {
+		let byte = reader.read_u8()?;
 		match byte {
 			WitnessCondition::BOOLEAN_BYTE => {
-				let b = reader.read_bool();
+				let b = reader.read_bool()?;
 				Ok(WitnessCondition::Boolean(b))
 			},
+			WitnessCondition::NOT_BYTE | WitnessCondition::OR_BYTE | WitnessCondition::AND_BYTE
+				if max_nest_depth == 0 =>
+				Err(TransactionError::InvalidWitnessCondition),
 			WitnessCondition::NOT_BYTE => {
-				let exp = WitnessCondition::decode(reader)?;
+				let exp = WitnessCondition::decode_nested(reader, max_nest_depth - 1)?;
 				Ok(WitnessCondition::Not(Box::from(exp)))
 			},
 			WitnessCondition::OR_BYTE | WitnessCondition::AND_BYTE => {
-				let len = reader.read_var_int()?;
-				if len > WitnessCondition::MAX_SUBITEMS as i64 {
-					return Err(TransactionError::InvalidWitnessCondition)
+				let len = reader.read_var_int_max(WitnessCondition::MAX_SUBITEMS as u64)?;
+				let mut exp = Vec::with_capacity(len as usize);
+				for _ in 0..len {
+					exp.push(WitnessCondition::decode_nested(reader, max_nest_depth - 1)?);
 				}
-				let exp = WitnessCondition::decode(reader)?;
 				if byte == WitnessCondition::OR_BYTE {
-					Ok(WitnessCondition::Or(vec![exp]))
+					Ok(WitnessCondition::Or(exp))
 				} else {
-					Ok(WitnessCondition::And(vec![exp]))
+					Ok(WitnessCondition::And(exp))
 				}
 			},
 			WitnessCondition::SCRIPT_HASH_BYTE | WitnessCondition::CALLED_BY_CONTRACT_BYTE => {
@@ -264,13 +283,37 @@ impl NeoSerializable for WitnessCondition {
 				}
 			},
 			WitnessCondition::CALLED_BY_ENTRY_BYTE => Ok(WitnessCondition::CalledByEntry),
-			_ => Err(TransactionError::InvalidTransaction),
+			_ => Err(TransactionError::InvalidWitnessCondition),
 		}
 	}
+}
 
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_and_condition() {
+		let condition = WitnessCondition::And(vec![
+			WitnessCondition::Boolean(true),
+			WitnessCondition::CalledByEntry,
+		]);
+		let bytes = condition.to_array();
+		assert_eq!(bytes, vec![0x02, 0x02, 0x00, 0x01, 0x20]);
+
+		let decoded = WitnessCondition::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded, condition);
+	}
+
+	#[test]
+	fn test_decode_max_nesting_depth() {
+		let nested = [0x01, 0x01, 0x00, 0x01];
+		assert!(WitnessCondition::decode(&mut Decoder::new(&nested)).is_ok());
+
+		let too_deep = [0x01, 0x01, 0x01, 0x00, 0x01];
+		assert!(WitnessCondition::decode(&mut Decoder::new(&too_deep)).is_err());
+
+		let truncated = [0x02, 0x03, 0x00, 0x01];
+		assert!(WitnessCondition::decode(&mut Decoder::new(&truncated)).is_err());
 	}
 }
@@ -31,9 +31,10 @@ impl NeoSerializable for WitnessRule {
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let action = reader.read_u8();
+		let action = WitnessAction::try_from(reader.read_u8()?)
+			.map_err(|_| TransactionError::InvalidWitnessCondition)?;
 		let condition = WitnessCondition::decode(reader)?;
-		Ok(Self { action: WitnessAction::try_from(action).unwrap(), condition })
+		Ok(Self { action, condition })
 	}
 	fn to_array(&self) -> Vec<u8> {
 		let mut writer = Encoder::new();
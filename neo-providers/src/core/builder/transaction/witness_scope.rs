@@ -73,3 +73,23 @@ impl WitnessScope {
 		scopes
 	}
 }
+
+/// Codec for a list of scopes, which goes on the wire as their combined flags byte.
+///
+/// Used with `#[neo(with = "...")]` on signer scopes.
+pub mod flags {
+	use super::WitnessScope;
+	use neo_codec::{CodecError, Decoder, Encoder};
+
+	pub fn size(_scopes: &[WitnessScope]) -> usize {
+		1
+	}
+
+	pub fn encode(scopes: &[WitnessScope], writer: &mut Encoder) {
+		writer.write_u8(WitnessScope::combine(scopes));
+	}
+
+	pub fn decode(reader: &mut Decoder) -> Result<Vec<WitnessScope>, CodecError> {
+		Ok(WitnessScope::split(reader.read_u8()?))
+	}
+}
@@ -1,6 +1,8 @@
 pub mod account;
+pub mod block;
 mod builder;
 pub mod responses;
+pub mod state;
 pub mod wallet;
 
 pub use builder::*;
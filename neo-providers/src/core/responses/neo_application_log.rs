@@ -1,4 +1,5 @@
-use neo_types::{invocation_result::Notification, stack_item::StackItem, vm_state::VMState, *};
+use crate::core::responses::notification::Notification;
+use neo_types::{stack_item::StackItem, vm_state::VMState, *};
 use primitive_types::H256;
 use serde::{Deserialize, Serialize};
 
@@ -10,19 +10,25 @@ pub struct NeoBlock {
 	pub hash: H256,
 	pub size: i32,
 	pub version: i32,
+	#[serde(rename = "previousblockhash")]
 	#[serde(serialize_with = "serialize_h256")]
 	#[serde(deserialize_with = "deserialize_h256")]
 	pub prev_block_hash: H256,
+	#[serde(rename = "merkleroot")]
 	#[serde(serialize_with = "serialize_h256")]
 	#[serde(deserialize_with = "deserialize_h256")]
 	pub merkle_root_hash: H256,
-	pub time: i32,
+	pub time: u64,
 	pub index: i32,
 	pub primary: Option<i32>,
+	#[serde(rename = "nextconsensus")]
 	pub next_consensus: String,
 	pub witnesses: Option<Vec<NeoWitness>>,
+	#[serde(rename = "tx")]
 	pub transactions: Option<Vec<TransactionResult>>,
+	#[serde(default)]
 	pub confirmations: i32,
+	#[serde(rename = "nextblockhash", default)]
 	#[serde(serialize_with = "serialize_h256_option")]
 	#[serde(deserialize_with = "deserialize_h256_option")]
 	pub next_block_hash: Option<H256>,
@@ -0,0 +1,12 @@
+use neo_types::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NeoNetworkFee {
+	#[serde(
+		rename = "networkfee",
+		serialize_with = "serialize_u64",
+		deserialize_with = "deserialize_u64"
+	)]
+	pub network_fee: u64,
+}
@@ -0,0 +1,3 @@
+pub mod mpt_node;
+pub mod state_error;
+pub mod state_proof;
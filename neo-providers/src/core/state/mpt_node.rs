@@ -0,0 +1,124 @@
+use crate::core::state::state_error::StateError;
+use neo_codec::encode::NeoSerializable;
+use neo_crypto::hash::HashableForVec;
+use neo_types::Bytes;
+use primitive_types::H256;
+
+/// A node of the Merkle Patricia Trie the state service keeps contract storage in, as it
+/// appears in the proofs returned by `getproof`.
+///
+/// Keys are paths of nibbles, one per byte. Nodes refer to their children by hash.
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "StateError")]
+pub enum MptNode {
+	#[neo(tag = 0x00)]
+	Branch(#[neo(with = "branch_children")] Vec<MptChild>),
+	#[neo(tag = 0x01)]
+	Extension {
+		#[neo(var_bytes, max = MptNode::MAX_KEY_LENGTH)]
+		key: Bytes,
+		next: MptChild,
+	},
+	#[neo(tag = 0x02)]
+	Leaf {
+		#[neo(var_bytes, max = MptNode::MAX_VALUE_LENGTH)]
+		value: Bytes,
+	},
+}
+
+/// A reference from a node to one of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "StateError")]
+pub enum MptChild {
+	#[neo(tag = 0x03)]
+	Hash(H256),
+	#[neo(tag = 0x04)]
+	Empty,
+}
+
+impl MptNode {
+	/// A branch has a child per nibble plus one for the value stored at the branch itself.
+	pub const BRANCH_CHILD_COUNT: usize = 17;
+	/// Maximum length in nibbles of a storage key, a 4 byte contract id and 64 bytes of key.
+	pub const MAX_KEY_LENGTH: usize = (4 + 64) * 2;
+	/// Maximum length of a serialized storage item.
+	pub const MAX_VALUE_LENGTH: usize = 3 + u16::MAX as usize + 1;
+
+	/// Returns the hash other nodes refer to this node by, the double SHA-256 of its encoding.
+	///
+	/// It is in RPC byte order, the reverse of the digest, like the child hashes in the encoding.
+	pub fn hash(&self) -> H256 {
+		node_hash(&self.to_array())
+	}
+}
+
+/// Hash of an encoded node, in RPC byte order.
+pub(crate) fn node_hash(node: &[u8]) -> H256 {
+	let mut hash = node.hash256().hash256();
+	hash.reverse();
+	H256::from_slice(&hash)
+}
+
+/// Splits `key` into nibbles, high nibble first.
+pub fn to_nibbles(key: &[u8]) -> Bytes {
+	key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+mod branch_children {
+	use super::*;
+	use neo_codec::{Decoder, Encoder};
+
+	pub fn size(children: &[MptChild]) -> usize {
+		children.iter().map(MptChild::size).sum()
+	}
+
+	pub fn encode(children: &[MptChild], writer: &mut Encoder) {
+		children.iter().for_each(|child| child.encode(writer));
+	}
+
+	pub fn decode(reader: &mut Decoder) -> Result<Vec<MptChild>, StateError> {
+		(0..MptNode::BRANCH_CHILD_COUNT).map(|_| MptChild::decode(reader)).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_codec::Decoder;
+
+	#[test]
+	fn test_node_round_trip() {
+		let mut children = vec![MptChild::Empty; MptNode::BRANCH_CHILD_COUNT];
+		children[3] = MptChild::Hash(H256::repeat_byte(0xaa));
+		for (node, expected_size) in [
+			(MptNode::Branch(children), 1 + 16 + 33),
+			(
+				MptNode::Extension { key: vec![0x0a, 0x0b], next: MptChild::Hash(H256::zero()) },
+				1 + 3 + 33,
+			),
+			(MptNode::Leaf { value: vec![1, 2, 3] }, 1 + 4),
+		] {
+			let bytes = node.to_array();
+			assert_eq!(bytes.len(), expected_size);
+			assert_eq!(node.size(), expected_size);
+			assert_eq!(MptNode::decode(&mut Decoder::new(&bytes)).unwrap(), node);
+		}
+	}
+
+	#[test]
+	fn test_node_rejects_invalid_input() {
+		// Children are referenced by hash, they can't be inlined.
+		let mut branch = vec![0x00, 0x02, 0x01];
+		branch.extend([0x04; 16]);
+		assert!(MptNode::decode(&mut Decoder::new(&branch)).is_err());
+
+		assert!(MptNode::decode(&mut Decoder::new(&[0x03])).is_err());
+		assert!(MptNode::decode(&mut Decoder::new(&[0x00, 0x04, 0x04])).is_err());
+	}
+
+	#[test]
+	fn test_to_nibbles() {
+		assert_eq!(to_nibbles(&[0xab, 0x01]), vec![0x0a, 0x0b, 0x00, 0x01]);
+		assert!(to_nibbles(&[]).is_empty());
+	}
+}
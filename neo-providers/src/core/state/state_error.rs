@@ -0,0 +1,15 @@
+use neo_codec::CodecError;
+use primitive_types::H256;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StateError {
+	#[error("Invalid proof: {0}")]
+	InvalidProof(String),
+	#[error("Proof is missing node {0:?}")]
+	MissingNode(H256),
+	#[error("Key not found")]
+	KeyNotFound,
+	#[error(transparent)]
+	CodecError(#[from] CodecError),
+}
@@ -0,0 +1,237 @@
+use crate::core::state::{
+	mpt_node::{node_hash, to_nibbles, MptChild, MptNode},
+	state_error::StateError,
+};
+use base64::{engine::general_purpose, Engine};
+use neo_codec::{encode::NeoSerializable, Decoder};
+use neo_types::Bytes;
+use primitive_types::H256;
+use std::collections::HashMap;
+
+/// A proof of a contract storage value, as returned by `getproof`.
+///
+/// The proof holds the storage key and the trie nodes on the path from the state root to the
+/// value, so the value can be checked against a trusted `StateRoot` without asking the node.
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "StateError")]
+pub struct StateProof {
+	#[neo(var_bytes, max = StateProof::MAX_STORAGE_KEY_SIZE)]
+	key: Bytes,
+	#[neo(with = "proof_nodes")]
+	nodes: Vec<Bytes>,
+}
+
+impl StateProof {
+	/// A storage key is the 4 byte contract id followed by up to 64 bytes of key.
+	const MAX_STORAGE_KEY_SIZE: usize = 4 + 64;
+	const MAX_NODES: usize = u8::MAX as usize;
+
+	pub fn new(key: Bytes, nodes: Vec<Bytes>) -> Self {
+		Self { key, nodes }
+	}
+
+	/// Decodes a proof from the base64 string returned by `get_proof`.
+	pub fn from_base64(proof: &str) -> Result<Self, StateError> {
+		let bytes = general_purpose::STANDARD
+			.decode(proof)
+			.map_err(|e| StateError::InvalidProof(e.to_string()))?;
+		let mut reader = Decoder::new(&bytes);
+		let proof = Self::decode(&mut reader)?;
+		if reader.available() > 0 {
+			return Err(StateError::InvalidProof("trailing bytes".to_string()))
+		}
+		Ok(proof)
+	}
+
+	/// The id of the contract the storage belongs to.
+	pub fn contract_id(&self) -> Option<i32> {
+		let id = self.key.get(..4)?;
+		Some(i32::from_le_bytes(id.try_into().unwrap()))
+	}
+
+	/// The storage key within the contract's storage.
+	pub fn storage_key(&self) -> &[u8] {
+		self.key.get(4..).unwrap_or_default()
+	}
+
+	/// Verifies the proof against `root_hash` and returns the proven value.
+	///
+	/// `root_hash` is given as in `StateRoot::root_hash`, in the byte order of the RPC JSON.
+	pub fn verify(&self, root_hash: H256) -> Result<Bytes, StateError> {
+		let nodes: HashMap<H256, &Bytes> =
+			self.nodes.iter().map(|node| (node_hash(node), node)).collect();
+
+		let path = to_nibbles(&self.key);
+		if path.is_empty() || path.len() > MptNode::MAX_KEY_LENGTH {
+			return Err(StateError::InvalidProof("invalid key length".to_string()))
+		}
+		let mut path = path.as_slice();
+
+		let mut hash = root_hash;
+		loop {
+			let data = nodes.get(&hash).ok_or(StateError::MissingNode(hash))?;
+			let mut reader = Decoder::new(data);
+			let node = MptNode::decode(&mut reader)?;
+			if reader.available() > 0 {
+				return Err(StateError::InvalidProof("trailing bytes in node".to_string()))
+			}
+
+			let next = match node {
+				MptNode::Leaf { value } if path.is_empty() => return Ok(value),
+				MptNode::Leaf { .. } => return Err(StateError::KeyNotFound),
+				MptNode::Branch(children) => match path.split_first() {
+					Some((nibble, rest)) => {
+						path = rest;
+						children[*nibble as usize]
+					},
+					None => children[MptNode::BRANCH_CHILD_COUNT - 1],
+				},
+				MptNode::Extension { key, next } if path.starts_with(&key) => {
+					path = &path[key.len()..];
+					next
+				},
+				MptNode::Extension { .. } => return Err(StateError::KeyNotFound),
+			};
+			hash = match next {
+				MptChild::Hash(hash) => hash,
+				MptChild::Empty => return Err(StateError::KeyNotFound),
+			};
+		}
+	}
+}
+
+mod proof_nodes {
+	use super::*;
+	use neo_codec::{
+		encode::{var_bytes_size, var_int_size},
+		CodecError, Encoder,
+	};
+
+	pub fn size(nodes: &[Bytes]) -> usize {
+		var_int_size(nodes.len())
+			+ nodes.iter().map(|node| var_bytes_size(node.len())).sum::<usize>()
+	}
+
+	pub fn encode(nodes: &[Bytes], writer: &mut Encoder) {
+		writer.write_var_int(nodes.len() as u64);
+		nodes.iter().for_each(|node| writer.write_var_bytes(node));
+	}
+
+	pub fn decode(reader: &mut Decoder) -> Result<Vec<Bytes>, CodecError> {
+		let count = reader.read_var_int_max(StateProof::MAX_NODES as u64)?;
+		(0..count).map(|_| reader.read_var_bytes()).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+
+	struct Trie {
+		nodes: Vec<MptNode>,
+	}
+
+	impl Trie {
+		fn add(&mut self, node: MptNode) -> MptChild {
+			let child = MptChild::Hash(node.hash());
+			self.nodes.push(node);
+			child
+		}
+
+		fn proof(&self, key: &[u8]) -> StateProof {
+			StateProof::new(key.to_vec(), self.nodes.iter().map(MptNode::to_array).collect())
+		}
+	}
+
+	/// Builds a trie holding `0x01000000ab -> [1]`, `0x01000000ac -> [2]` and `0x01000000 -> [3]`
+	/// and returns it with its root hash in RPC byte order.
+	fn trie() -> (Trie, H256) {
+		let mut trie = Trie { nodes: vec![] };
+		let first = trie.add(MptNode::Leaf { value: vec![1] });
+		let second = trie.add(MptNode::Leaf { value: vec![2] });
+		let third = trie.add(MptNode::Leaf { value: vec![3] });
+
+		let mut children = vec![MptChild::Empty; MptNode::BRANCH_CHILD_COUNT];
+		children[0x0b] = first;
+		children[0x0c] = second;
+		let inner = trie.add(MptNode::Branch(children));
+
+		let mut children = vec![MptChild::Empty; MptNode::BRANCH_CHILD_COUNT];
+		children[0x0a] = inner;
+		children[MptNode::BRANCH_CHILD_COUNT - 1] = third;
+		let branch = trie.add(MptNode::Branch(children));
+		let root = trie.add(MptNode::Extension { key: to_nibbles(&[1, 0, 0, 0]), next: branch });
+
+		let MptChild::Hash(root) = root else { unreachable!() };
+		(trie, root)
+	}
+
+	#[test]
+	fn test_verify() {
+		let (trie, root) = trie();
+		assert_eq!(trie.proof(&[1, 0, 0, 0, 0xab]).verify(root), Ok(vec![1]));
+		assert_eq!(trie.proof(&[1, 0, 0, 0, 0xac]).verify(root), Ok(vec![2]));
+		assert_eq!(trie.proof(&[1, 0, 0, 0]).verify(root), Ok(vec![3]));
+
+		let proof = trie.proof(&[1, 0, 0, 0, 0xab]);
+		assert_eq!(proof.contract_id(), Some(1));
+		assert_eq!(proof.storage_key(), &[0xab]);
+	}
+
+	#[test]
+	fn test_verify_rejects_invalid_proof() {
+		let (mut trie, root) = trie();
+		for key in [&[1, 0, 0, 0, 0xad][..], &[1, 0, 0, 0, 0xab, 0x01], &[2, 0, 0, 0]] {
+			assert_eq!(trie.proof(key).verify(root), Err(StateError::KeyNotFound));
+		}
+		assert!(trie.proof(&[]).verify(root).is_err());
+		assert!(trie.proof(&[1, 0, 0, 0, 0xab]).verify(H256::zero()).is_err());
+
+		// Replacing a value changes the hashes up to the root.
+		trie.nodes[0] = MptNode::Leaf { value: vec![9] };
+		assert!(matches!(
+			trie.proof(&[1, 0, 0, 0, 0xab]).verify(root),
+			Err(StateError::MissingNode(_))
+		));
+	}
+
+	/// A proof of the NeoToken account state of `0xf563ea40bc283d4d0e05c48ea305b3f2a07340ef`
+	/// and its root hash, encoded outside this crate by following the node's `Node` and
+	/// `GetProof` serialization: the nodes are `[type][fields]` with children as `0x03` and the
+	/// double SHA-256 of their encoding, or `0x04` when empty.
+	const PROOF: &str = "Gfv///8U70BzoPKzBaOOxAUOTT0ovEDqY/UFUgADXGYjhTBrKxdX1NyElW2DWOG1CtbPJl+zVjQNC1cUi+0EBAQEBAQEBAQEBAQEBAOMdE+GcE7zVYXkJZNZpxjOFNWSRfN6I5x5gCCCsLTITgQtAQoLDw8PDw8PAQQOAxylYePC85bNC5ILBrrurzx9V1YCeTlSr2YdQmeUQkc4UgAEBAOc7mqipXP418N8CBz0tz7k/+FTqb5gx0k0HQCvURalYQQEBAQEBAQEBAQEBANguVDy5qlaHutydRe13pNjSmDxjTJZQ/j84dzGdZcF9wRJASYEAAcDCgAPAgsDAAUKAwgODAQABQAOBA0DDQIICwwEAA4KBgMPBQN5mgBDEqfSpTgQSn4bU+qmQL43Xmpdo66B3st4pT8vKwwCCkEDIQFkIQLoAwA=";
+	const ROOT_HASH: &str = "0x58c64b58b09939f613691b820832551d8204d879af0179c9ad8e780195f7073c";
+
+	#[test]
+	fn test_verify_encoded_proof() {
+		let proof = StateProof::from_base64(PROOF).unwrap();
+		assert_eq!(proof.contract_id(), Some(-5));
+		assert_eq!(
+			proof.storage_key(),
+			hex::decode("14ef4073a0f2b305a38ec4050e4d3d28bc40ea63f5").unwrap()
+		);
+
+		let root = H256::from_str(ROOT_HASH).unwrap();
+		assert_eq!(proof.verify(root), Ok(hex::decode("41032101642102e80300").unwrap()));
+		assert_eq!(general_purpose::STANDARD.encode(proof.to_array()), PROOF);
+		assert!(proof.verify(H256::from_low_u64_be(1)).is_err());
+	}
+
+	#[test]
+	fn test_proof_round_trip() {
+		let (trie, root) = trie();
+		let proof = trie.proof(&[1, 0, 0, 0, 0xac]);
+		let bytes = proof.to_array();
+		assert_eq!(bytes.len(), proof.size());
+
+		let decoded = StateProof::from_base64(&general_purpose::STANDARD.encode(&bytes)).unwrap();
+		assert_eq!(decoded, proof);
+		assert_eq!(decoded.verify(root), Ok(vec![2]));
+
+		assert!(StateProof::from_base64("not base64").is_err());
+		for len in 0..bytes.len() {
+			assert!(StateProof::decode(&mut Decoder::new(&bytes[..len])).is_err());
+		}
+	}
+}
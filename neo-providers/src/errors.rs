@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::{error::Error, fmt::Debug};
 use thiserror::Error;
 
-use crate::JsonRpcError;
+use crate::{JsonRpcError, NeoRpcErrorKind};
 
 /// An `RpcError` is an abstraction over error types returned by a
 /// [`crate::JsonRpcClient`].
@@ -179,6 +179,13 @@ pub enum ProviderError {
 	TypeError(#[from] TypeError),
 }
 
+impl ProviderError {
+	/// The meaning of the error response of the node, if the error is one
+	pub fn neo_error_kind(&self) -> Option<NeoRpcErrorKind> {
+		RpcError::as_error_response(self).map(JsonRpcError::kind)
+	}
+}
+
 impl RpcError for ProviderError {
 	fn as_error_response(&self) -> Option<&super::JsonRpcError> {
 		if let ProviderError::JsonRpcClientError(err) = self {
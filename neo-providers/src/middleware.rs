@@ -421,8 +421,9 @@ pub trait Middleware: Sync + Send + Debug {
 		self.inner().import_priv_key(priv_key).await.map_err(MiddlewareError::from_err)
 	}
 
-	async fn calculate_network_fee(&self, hex: String) -> Result<u64, Self::Error> {
-		self.inner().calculate_network_fee(hex).await.map_err(MiddlewareError::from_err)
+	/// Calculates the network fee of the base64 encoded transaction `tx_base64`
+	async fn calculate_network_fee(&self, tx_base64: String) -> Result<u64, Self::Error> {
+		self.inner().calculate_network_fee(tx_base64).await.map_err(MiddlewareError::from_err)
 	}
 
 	async fn list_address(&self) -> Result<Vec<NeoAddress>, Self::Error> {
@@ -0,0 +1,409 @@
+use crate::{errors::ProviderError, BatchClient, Provider};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// A batch of JSON-RPC calls, sent to the node in as few requests as the chunk size allows.
+///
+/// Every call of the batch is a [`BatchCall`] whose result is deserialized as `R`, e.g. the
+/// [`calls`] of common methods. The result of each call is returned in its place, so that one
+/// failing call doesn't fail the batch.
+///
+/// Batches can be sent with any [`BatchClient`], including an [`Http`](crate::Http) transport
+/// wrapped in a [`RetryClient`](crate::RetryClient),
+/// [`RateLimitedClient`](crate::RateLimitedClient) or [`FailoverClient`](crate::FailoverClient).
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{calls::get_block_hash, Http, Provider};
+/// use std::convert::TryFrom;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::<Http>::try_from("http://seed1.neo.org:10332")?;
+/// let hashes = (0..20_000u32)
+/// 	.fold(provider.batch(), |batch, index| batch.add(get_block_hash(index)))
+/// 	.send()
+/// 	.await?
+/// 	.into_iter()
+/// 	.collect::<Result<Vec<_>, _>>()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "batches do nothing unless sent"]
+pub struct BatchRequest<'a, C, R = Value> {
+	client: &'a C,
+	calls: Vec<(String, Value)>,
+	chunk_size: usize,
+	// The first params that couldn't be serialized, reported by `send`
+	error: Option<serde_json::Error>,
+	result: PhantomData<fn() -> R>,
+}
+
+impl<'a, C: BatchClient, R: DeserializeOwned> BatchRequest<'a, C, R> {
+	/// The number of calls sent per request by default
+	pub const DEFAULT_CHUNK_SIZE: usize = 100;
+
+	/// Creates an empty batch sent with `client`
+	pub fn new(client: &'a C) -> Self {
+		Self {
+			client,
+			calls: Vec::new(),
+			chunk_size: Self::DEFAULT_CHUNK_SIZE,
+			error: None,
+			result: PhantomData,
+		}
+	}
+
+	/// Adds a call, e.g. `get_block_hash(index)`.
+	pub fn add(mut self, call: BatchCall<R>) -> Self {
+		match call.params {
+			Ok(params) => self.calls.push((call.method, params)),
+			Err(err) => {
+				self.error.get_or_insert(err);
+			},
+		}
+		self
+	}
+
+	/// Sets the maximum number of calls sent per request, so that large batches don't exceed the
+	/// request size the node accepts.
+	pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+		self.chunk_size = chunk_size.max(1);
+		self
+	}
+
+	/// The number of calls in the batch
+	pub fn len(&self) -> usize {
+		self.calls.len()
+	}
+
+	/// Whether the batch has no calls
+	pub fn is_empty(&self) -> bool {
+		self.calls.is_empty()
+	}
+
+	/// Sends the calls, one chunk after the other, and returns their results in the order they
+	/// were added.
+	pub async fn send(self) -> Result<Vec<Result<R, ProviderError>>, ProviderError> {
+		if let Some(err) = self.error {
+			return Err(err.into())
+		}
+		let mut results = Vec::with_capacity(self.calls.len());
+		for chunk in self.calls.chunks(self.chunk_size) {
+			let responses = self.client.fetch_batch(chunk).await.map_err(Into::into)?;
+			results.extend(responses.into_iter().map(|response| match response {
+				Ok(value) => serde_json::from_value(value).map_err(ProviderError::from),
+				Err(err) => Err(err.into()),
+			}));
+		}
+		Ok(results)
+	}
+}
+
+/// A call of a [`BatchRequest`], tying a JSON-RPC method and its params to the type its result is
+/// deserialized as.
+#[derive(Debug)]
+#[must_use = "calls do nothing unless added to a batch"]
+pub struct BatchCall<R> {
+	method: String,
+	params: Result<Value, serde_json::Error>,
+	result: PhantomData<fn() -> R>,
+}
+
+impl<R> BatchCall<R> {
+	/// A call of `method` with `params` whose result is deserialized as `R`, for the methods
+	/// without a constructor in [`calls`].
+	pub fn new<T: Serialize>(method: impl Into<String>, params: T) -> Self {
+		let params = if std::mem::size_of::<T>() == 0 {
+			Ok(Value::Array(Vec::new()))
+		} else {
+			serde_json::to_value(params)
+		};
+		Self { method: method.into(), params, result: PhantomData }
+	}
+
+	/// The method called
+	pub fn method(&self) -> &str {
+		&self.method
+	}
+
+	/// The same call with its result left as JSON, so that it can be batched with calls of other
+	/// methods.
+	pub fn untyped(self) -> BatchCall<Value> {
+		BatchCall { method: self.method, params: self.params, result: PhantomData }
+	}
+}
+
+/// The [`BatchCall`]s of common methods, with the params and result of the
+/// [`Middleware`](crate::Middleware) method of the same name.
+pub mod calls {
+	use super::BatchCall;
+	use crate::core::responses::{
+		neo_application_log::ApplicationLog, neo_balances::Nep17Balances, neo_block::NeoBlock,
+		neo_get_state_root::StateRoot, neo_transaction_result::TransactionResult,
+	};
+	use neo_types::{contract_state::ContractState, serde_value::ValueExtension};
+	use primitive_types::{H160, H256};
+
+	/// `getbestblockhash`
+	pub fn get_best_block_hash() -> BatchCall<H256> {
+		BatchCall::new("getbestblockhash", ())
+	}
+
+	/// `getblockcount`
+	pub fn get_block_count() -> BatchCall<u32> {
+		BatchCall::new("getblockcount", ())
+	}
+
+	/// `getblockhash` of the block at `block_index`
+	pub fn get_block_hash(block_index: u32) -> BatchCall<H256> {
+		BatchCall::new("getblockhash", [block_index.to_value()])
+	}
+
+	/// `getblock` of the block at `index`, with its transactions
+	pub fn get_block_by_index(index: u32) -> BatchCall<NeoBlock> {
+		BatchCall::new("getblock", [index.to_value(), 1.to_value()])
+	}
+
+	/// `getblock` of the block at `index`, serialized in base64
+	pub fn get_raw_block_by_index(index: u32) -> BatchCall<String> {
+		BatchCall::new("getblock", [index.to_value(), 0.to_value()])
+	}
+
+	/// `getblockheader` of the block with `block_hash`
+	pub fn get_block_header(block_hash: H256) -> BatchCall<NeoBlock> {
+		BatchCall::new("getblockheader", [block_hash.to_value(), 1.to_value()])
+	}
+
+	/// `getblockheader` of the block at `index`
+	pub fn get_block_header_by_index(index: u32) -> BatchCall<NeoBlock> {
+		BatchCall::new("getblockheader", [index.to_value(), 1.to_value()])
+	}
+
+	/// `getrawtransaction` of the transaction with `hash`, in JSON
+	pub fn get_transaction(hash: H256) -> BatchCall<Option<TransactionResult>> {
+		BatchCall::new("getrawtransaction", [hash.to_value(), 1.to_value()])
+	}
+
+	/// `gettransactionheight` of the transaction with `tx_hash`
+	pub fn get_transaction_height(tx_hash: H256) -> BatchCall<u32> {
+		BatchCall::new("gettransactionheight", [tx_hash.to_value()])
+	}
+
+	/// `getapplicationlog` of the transaction with `tx_hash`
+	pub fn get_application_log(tx_hash: H256) -> BatchCall<ApplicationLog> {
+		BatchCall::new("getapplicationlog", [tx_hash.to_value()])
+	}
+
+	/// `getcontractstate` of the contract with `hash`
+	pub fn get_contract_state(hash: H160) -> BatchCall<ContractState> {
+		BatchCall::new("getcontractstate", [hash.to_value()])
+	}
+
+	/// `getstorage` of `key` in the storage of the contract with `contract_hash`
+	pub fn get_storage(contract_hash: H160, key: &str) -> BatchCall<String> {
+		BatchCall::new("getstorage", [contract_hash.to_value(), key.to_value()])
+	}
+
+	/// `getnep17balances` of the account with `script_hash`
+	pub fn get_nep17_balances(script_hash: H160) -> BatchCall<Nep17Balances> {
+		BatchCall::new("getnep17balances", [script_hash.to_value()])
+	}
+
+	/// `getstateroot` of the block at `block_index`
+	pub fn get_state_root(block_index: u32) -> BatchCall<StateRoot> {
+		BatchCall::new("getstateroot", [block_index.to_value()])
+	}
+}
+
+impl<P: BatchClient> Provider<P> {
+	/// Starts a batch of calls sent to the node together, see [`BatchRequest`].
+	pub fn batch<R: DeserializeOwned>(&self) -> BatchRequest<'_, P, R> {
+		BatchRequest::new(self.as_ref())
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::{calls::*, *};
+	use crate::{
+		FailoverClient, Http, NeoRetryPolicy, NeoRpcErrorKind, RateLimitedClient,
+		RetryClientBuilder, RpcError,
+	};
+	use primitive_types::H256;
+	use serde_json::json;
+	use std::{
+		io::{BufRead, BufReader, Read, Write},
+		net::TcpListener,
+		str::FromStr,
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		},
+	};
+
+	/// Serves JSON-RPC over HTTP, answering each request body with `respond`. Returns a provider
+	/// connected to the server and the number of requests it received.
+	fn serve(
+		respond: impl Fn(Value) -> Value + Send + 'static,
+	) -> (Provider<Http>, Arc<AtomicUsize>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let requests = Arc::new(AtomicUsize::new(0));
+		let counter = requests.clone();
+		std::thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = stream.unwrap();
+				let mut reader = BufReader::new(stream.try_clone().unwrap());
+				let mut length = 0;
+				loop {
+					let mut line = String::new();
+					reader.read_line(&mut line).unwrap();
+					let line = line.trim_end().to_lowercase();
+					if line.is_empty() {
+						break
+					}
+					if let Some(value) = line.strip_prefix("content-length:") {
+						length = value.trim().parse().unwrap();
+					}
+				}
+				let mut body = vec![0; length];
+				reader.read_exact(&mut body).unwrap();
+				counter.fetch_add(1, Ordering::SeqCst);
+
+				let response = respond(serde_json::from_slice(&body).unwrap()).to_string();
+				let _ = write!(
+					stream,
+					"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{response}",
+					response.len()
+				);
+			}
+		});
+		(Provider::<Http>::try_from(url.as_str()).unwrap(), requests)
+	}
+
+	/// Answers `getblockhash` with a hash ending with the index, in reverse order, and fails for
+	/// block `13`.
+	fn block_hashes(request: Value) -> Value {
+		let responses: Vec<Value> = request
+			.as_array()
+			.unwrap()
+			.iter()
+			.rev()
+			.map(|call| {
+				let index = call["params"][0].as_u64().unwrap();
+				match index {
+					13 => json!({ "jsonrpc": "2.0", "id": call["id"], "error": {
+						"code": -101, "message": "Unknown block"
+					}}),
+					_ => json!({
+						"jsonrpc": "2.0", "id": call["id"], "result": H256::from_low_u64_be(index)
+					}),
+				}
+			})
+			.collect();
+		Value::Array(responses)
+	}
+
+	#[tokio::test]
+	async fn test_batch() {
+		let (provider, requests) = serve(block_hashes);
+		let results = (10..15u32)
+			.fold(provider.batch(), |batch, index| batch.add(get_block_hash(index)))
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(requests.load(Ordering::SeqCst), 1);
+		assert_eq!(results.len(), 5);
+		assert_eq!(results[0].as_ref().unwrap(), &H256::from_low_u64_be(10));
+		assert_eq!(results[2].as_ref().unwrap(), &H256::from_low_u64_be(12));
+		let error = results[3].as_ref().unwrap_err().as_error_response().unwrap();
+		assert_eq!(error.kind(), NeoRpcErrorKind::UnknownBlock);
+		assert_eq!(results[4].as_ref().unwrap(), &H256::from_low_u64_be(14));
+
+		// A result of another type fails only its call
+		let call = BatchCall::<u32>::new("getblockhash", [1]);
+		let results = provider.batch().add(call).send().await.unwrap();
+		assert!(results[0].as_ref().unwrap_err().as_serde_error().is_some());
+
+		assert!(provider.batch::<Value>().send().await.unwrap().is_empty());
+		assert_eq!(requests.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn test_batch_chunks() {
+		let (provider, requests) = serve(block_hashes);
+		let results = (0..5u32)
+			.fold(provider.batch().chunk_size(2), |batch, index| batch.add(get_block_hash(index)))
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(requests.load(Ordering::SeqCst), 3);
+		let hashes: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+		assert_eq!(hashes, (0..5).map(H256::from_low_u64_be).collect::<Vec<_>>());
+	}
+
+	#[tokio::test]
+	async fn test_batch_of_several_methods() {
+		let (provider, _) = serve(|request| {
+			assert_eq!(request[0]["method"], "getblockcount");
+			assert_eq!(request[0]["params"], json!([]));
+			assert_eq!(request[1]["method"], "getblockhash");
+			json!([
+				{ "jsonrpc": "2.0", "id": request[0]["id"], "result": 42 },
+				{ "jsonrpc": "2.0", "id": request[1]["id"], "result": H256::zero() },
+			])
+		});
+		let results = provider
+			.batch()
+			.add(get_block_count().untyped())
+			.add(get_block_hash(41).untyped())
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(results[0].as_ref().unwrap(), &json!(42));
+		assert_eq!(results[1].as_ref().unwrap(), &json!(H256::zero()));
+	}
+
+	#[tokio::test]
+	async fn test_rejected_batch() {
+		let (provider, _) = serve(
+			|_| json!({ "jsonrpc": "2.0", "id": 0, "error": { "code": -32600, "message": "Invalid Request" } }),
+		);
+		let err = provider.batch().add(get_block_hash(1)).send().await.unwrap_err();
+		assert_eq!(err.neo_error_kind(), Some(NeoRpcErrorKind::InvalidRequest));
+	}
+
+	#[tokio::test]
+	async fn test_batch_through_wrappers() {
+		let (server, requests) = serve(block_hashes);
+		let server = server.as_ref().clone();
+		// Nothing listens on port 1, so the first node of the pool can't be reached
+		let unreachable = Http::from_str("http://127.0.0.1:1").unwrap();
+
+		let failover = FailoverClient::new([unreachable.clone(), server.clone()]);
+		let client = RetryClientBuilder::default()
+			.build(RateLimitedClient::new(failover, 100), Box::new(NeoRetryPolicy));
+		let provider = Provider::new(client);
+		let results = provider
+			.batch()
+			.add(get_block_hash(12))
+			.add(get_block_hash(13))
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(results[0].as_ref().unwrap(), &H256::from_low_u64_be(12));
+		let error = results[1].as_ref().unwrap_err().as_error_response().unwrap();
+		assert_eq!(error.kind(), NeoRpcErrorKind::UnknownBlock);
+		assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+		// A batch broadcasting a transaction is only sent to the best node, like the call
+		let provider = Provider::new(FailoverClient::new([unreachable, server]));
+		let batch = provider.batch::<Value>().add(BatchCall::new("sendrawtransaction", ["AA=="]));
+		assert!(batch.send().await.is_err());
+		assert_eq!(requests.load(Ordering::SeqCst), 1);
+	}
+}
@@ -2,10 +2,8 @@ use std::fmt::Debug;
 
 use async_trait::async_trait;
 use auto_impl::auto_impl;
-use primitive_types::U256;
-
 use serde::{de::DeserializeOwned, Serialize};
-use serde_json::value::RawValue;
+use serde_json::{value::RawValue, Value};
 
 use crate::{ProviderError, RpcError};
 
@@ -25,14 +23,48 @@ pub trait JsonRpcClient: Debug + Send + Sync {
 		R: DeserializeOwned + Send;
 }
 
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+/// A transport implementation supporting JSON-RPC batches.
+pub trait BatchClient: JsonRpcClient {
+	/// Sends the `calls`, pairs of a method and its params, in a single JSON-RPC batch and returns
+	/// their results in the same order.
+	///
+	/// The result of each call is returned in its place, so that a call failing doesn't fail the
+	/// others. The whole batch fails if the node can't be reached or rejects the batch.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error>;
+}
+
+// `auto_impl` forwards to the inner future, whose error type is `C::Error` rather than
+// `<&C as JsonRpcClient>::Error`, so the pointer impls are written out.
+macro_rules! forward_batch_client {
+	($($ptr:ty),*) => {$(
+		#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+		#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+		impl<C: BatchClient + ?Sized> BatchClient for $ptr {
+			async fn fetch_batch(
+				&self,
+				calls: &[(String, Value)],
+			) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+				(**self).fetch_batch(calls).await
+			}
+		}
+	)*};
+}
+
+forward_batch_client!(&C, Box<C>, std::sync::Arc<C>);
+
 /// A transport implementation supporting pub sub subscriptions.
 pub trait PubsubClient: JsonRpcClient {
 	/// The type of stream this transport returns
 	type NotificationStream: futures_core::Stream<Item = Box<RawValue>> + Send + Unpin;
 
 	/// Add a subscription to this transport
-	fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error>;
+	fn subscribe<T: Into<String>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error>;
 
 	/// Remove a subscription from this transport
-	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error>;
+	fn unsubscribe<T: Into<String>>(&self, id: T) -> Result<(), Self::Error>;
 }
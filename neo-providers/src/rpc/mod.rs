@@ -6,8 +6,17 @@ pub use provider::*;
 mod transports;
 pub use transports::*;
 
+mod batch;
+pub use batch::{calls, BatchCall, BatchRequest};
+
 mod connections;
 pub use connections::*;
 
 mod pubsub;
 pub use pubsub::{PubsubClient, SubscriptionStream};
+
+mod subscription;
+pub use subscription::{
+	BlockFilter, ExecutionEvent, ExecutionFilter, NeoEvent, NotificationEvent, NotificationFilter,
+	TransactionFilter,
+};
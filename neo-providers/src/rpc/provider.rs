@@ -1,6 +1,7 @@
 use crate::{
 	call_raw::CallBuilder, errors::ProviderError, rpc::pubsub::PubsubClient, utils,
-	Http as HttpProvider, JsonRpcClient, MiddlewareError, MockProvider, RwClient,
+	Http as HttpProvider, JsonRpcClient, JsonRpcClientWrapper, MiddlewareError, MockProvider,
+	QuorumProvider, RwClient,
 };
 
 pub use crate::Middleware;
@@ -22,6 +23,7 @@ use crate::{
 			neo_get_version::NeoVersion,
 			neo_get_wallet_balance::Balance,
 			neo_list_plugins::Plugin,
+			neo_network_fee::NeoNetworkFee,
 			neo_send_raw_transaction::RawTransaction,
 			neo_transfers::{Nep11Transfers, Nep17Transfers},
 			neo_validate_address::ValidateAddress,
@@ -34,7 +36,14 @@ use crate::{
 		},
 		utils::VecValueExtension,
 	},
-	rpc::provider::sealed::Sealed,
+	rpc::{
+		provider::sealed::Sealed,
+		subscription::{
+			BlockFilter, ExecutionEvent, ExecutionFilter, NeoEvent, NotificationEvent,
+			NotificationFilter, TransactionFilter,
+		},
+	},
+	SubscriptionStream,
 };
 
 use crate::core::responses::neo_transaction_result::TransactionResult;
@@ -42,13 +51,13 @@ use crate::core::responses::neo_transaction_result::TransactionResult;
 use crate::{HttpRateLimitRetryPolicy, RetryClient};
 use async_trait::async_trait;
 use futures_util::lock::Mutex;
+use neo_config::{NeoConfig, DEFAULT_BLOCK_TIME};
 use neo_crypto::keys::Secp256r1Signature;
 use neo_types::{
 	address::{Address, NameOrAddress},
 	block::{Block, BlockId},
 	contract_parameter::ContractParameter,
 	contract_state::ContractState,
-	filter::{Filter, FilterBlockOption},
 	invocation_result::{InvocationResult, PendingSignature},
 	log::Log,
 	native_contract_state::NativeContractState,
@@ -56,9 +65,11 @@ use neo_types::{
 	serde_value::ValueExtension,
 	stack_item::StackItem,
 	syncing::SyncingStatus,
+	vm_state::VMState,
 	Bytes,
 };
-use primitive_types::{H160, H256 as TxHash, H256, U256};
+use once_cell::sync::OnceCell;
+use primitive_types::{H160, H256 as TxHash, H256};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::{
@@ -93,19 +104,6 @@ impl FromStr for NodeClient {
 	}
 }
 
-/// Types of filters supported by the JSON-RPC.
-#[derive(Clone, Debug)]
-pub enum FilterKind<'a> {
-	/// `neo_newBlockFilter`
-	Logs(&'a Filter),
-
-	/// `neo_newBlockFilter` filter
-	NewBlocks,
-
-	/// `neo_newPendingTransactionFilter` filter
-	PendingTransactions,
-}
-
 /// An abstract provider for interacting with the [Neo JSON RPC
 /// API](https://github.com/neo/wiki/wiki/JSON-RPC). Must be instantiated
 /// with a data transport which implements the [`JsonRpcClient`](trait@crate::JsonRpcClient) trait
@@ -134,6 +132,7 @@ pub struct Provider<P> {
 	inner: P,
 	nns: Option<Address>,
 	interval: Option<Duration>,
+	config: OnceCell<NeoConfig>,
 	from: Option<Address>,
 	_node_client: Arc<Mutex<Option<NodeClient>>>,
 }
@@ -152,6 +151,7 @@ impl<P: JsonRpcClient> Provider<P> {
 			inner: provider,
 			nns: None,
 			interval: None,
+			config: OnceCell::new(),
 			from: None,
 			_node_client: Arc::new(Mutex::new(None)),
 		}
@@ -230,6 +230,11 @@ impl<P: JsonRpcClient> Middleware for Provider<P> {
 		self.from.clone()
 	}
 
+	fn config(&self) -> &NeoConfig {
+		// The default config needs a Tokio runtime, so it is only created when first needed
+		self.config.get_or_init(NeoConfig::default)
+	}
+
 	//////////////////////// Neo methods////////////////////////////
 
 	fn nns_resolver(&self) -> H160 {
@@ -462,8 +467,10 @@ impl<P: JsonRpcClient> Middleware for Provider<P> {
 		self.request("importprivkey", params).await
 	}
 
-	async fn calculate_network_fee(&self, hex: String) -> Result<u64, ProviderError> {
-		self.request("calculatenetworkfee", vec![hex.to_value()]).await
+	async fn calculate_network_fee(&self, tx_base64: String) -> Result<u64, ProviderError> {
+		let fee: NeoNetworkFee =
+			self.request("calculatenetworkfee", vec![tx_base64.to_value()]).await?;
+		Ok(fee.network_fee)
 	}
 
 	async fn list_address(&self) -> Result<Vec<NeoAddress>, ProviderError> {
@@ -746,20 +753,98 @@ impl<P: JsonRpcClient> Middleware for Provider<P> {
 }
 
 impl<P: JsonRpcClient> Provider<P> {
-	/// Sets the default polling interval for event filters and pending transactions
-	/// (default: 7 seconds)
+	/// Sets the polling interval of the `watch_*` streams, overriding the one of the config
 	pub fn set_interval<T: Into<Duration>>(&mut self, interval: T) -> &mut Self {
 		self.interval = Some(interval.into());
 		self
 	}
 
-	/// Sets the default polling interval for event filters and pending transactions
-	/// (default: 7 seconds)
+	/// Sets the polling interval of the `watch_*` streams, overriding the one of the config
 	#[must_use]
 	pub fn interval<T: Into<Duration>>(mut self, interval: T) -> Self {
 		self.set_interval(interval);
 		self
 	}
+
+	/// The polling interval of the `watch_*` streams: the one set with [`Self::set_interval`],
+	/// else the `polling_interval` of the config, else the default block time.
+	pub fn get_interval(&self) -> Duration {
+		self.interval.unwrap_or_else(|| {
+			let millis = self
+				.config
+				.get()
+				.map_or(DEFAULT_BLOCK_TIME, |config| config.polling_interval as u64);
+			Duration::from_millis(millis)
+		})
+	}
+
+	/// Sets the config of the network the node belongs to. Without one, [`NeoConfig::default`] is
+	/// used.
+	#[must_use]
+	pub fn with_config(mut self, config: NeoConfig) -> Self {
+		self.config = OnceCell::with_value(config);
+		self
+	}
+}
+
+/// Subscriptions of the neo-go WebSocket API. They are reissued after the
+/// transport reconnects, so a stream outlives the connection it was made on.
+impl<P: PubsubClient> Provider<P> {
+	/// Subscribes to `event`, optionally with a filter the node applies before
+	/// sending anything. The filter must match the event, see [`NeoEvent`].
+	pub async fn subscribe<R, F>(
+		&self,
+		event: NeoEvent,
+		filter: Option<F>,
+	) -> Result<SubscriptionStream<'_, P, R>, ProviderError>
+	where
+		R: DeserializeOwned,
+		F: Debug + Serialize + Send + Sync,
+	{
+		let id: String = match filter {
+			Some(filter) => self.request("subscribe", (event, filter)).await?,
+			None => self.request("subscribe", [event]).await?,
+		};
+		SubscriptionStream::new(id, self).map_err(Into::into)
+	}
+
+	/// Streams the blocks added to the chain.
+	pub async fn subscribe_blocks(
+		&self,
+		filter: Option<BlockFilter>,
+	) -> Result<SubscriptionStream<'_, P, NeoBlock>, ProviderError> {
+		self.subscribe(NeoEvent::BlockAdded, filter).await
+	}
+
+	/// Streams the transactions added to the memory pool.
+	pub async fn subscribe_transactions(
+		&self,
+		filter: Option<TransactionFilter>,
+	) -> Result<SubscriptionStream<'_, P, TransactionResult>, ProviderError> {
+		self.subscribe(NeoEvent::TransactionAdded, filter).await
+	}
+
+	/// Streams the notifications emitted by `contract`, or by any contract if
+	/// `None`, optionally only those named `name`.
+	pub async fn subscribe_notifications(
+		&self,
+		contract: Option<H160>,
+		name: Option<&str>,
+	) -> Result<SubscriptionStream<'_, P, NotificationEvent>, ProviderError> {
+		let filter = NotificationFilter { contract, name: name.map(str::to_string) };
+		let filter = (filter != NotificationFilter::default()).then_some(filter);
+		self.subscribe(NeoEvent::NotificationFromExecution, filter).await
+	}
+
+	/// Streams the executions of transactions and blocks, optionally only those
+	/// that ended in `state`.
+	pub async fn subscribe_executions(
+		&self,
+		state: Option<VMState>,
+	) -> Result<SubscriptionStream<'_, P, ExecutionEvent>, ProviderError> {
+		let filter = state.map(|state| ExecutionFilter { state: Some(state), container: None });
+		self.subscribe(NeoEvent::TransactionExecuted, filter).await
+	}
 }
 
 #[cfg(all(feature = "ipc", any(unix, windows)))]
@@ -801,6 +886,13 @@ where
 	}
 }
 
+impl<T: JsonRpcClientWrapper> Provider<QuorumProvider<T>> {
+	/// Provider that uses a quorum
+	pub fn quorum(inner: QuorumProvider<T>) -> Self {
+		Self::new(inner)
+	}
+}
+
 impl Provider<MockProvider> {
 	/// Returns a `Provider` instantiated with an internal "mock" transport.
 	///
@@ -996,7 +1088,8 @@ pub fn is_local_endpoint(endpoint: &str) -> bool {
 					return domain.contains("localhost") || domain.contains("localdev.me"),
 				Host::Ipv4(ipv4) =>
 					return ipv4 == Ipv4Addr::LOCALHOST
-						|| ipv4.is_link_local() || ipv4.is_loopback()
+						|| ipv4.is_link_local()
+						|| ipv4.is_loopback()
 						|| ipv4.is_private(),
 				Host::Ipv6(ipv6) => return ipv6.is_loopback(),
 			}
@@ -2,7 +2,6 @@ use crate::{JsonRpcClient, Middleware, Provider};
 
 use futures_util::stream::Stream;
 use pin_project::{pin_project, pinned_drop};
-use primitive_types::U256;
 use serde::de::DeserializeOwned;
 use serde_json::value::RawValue;
 use std::{
@@ -19,18 +18,18 @@ pub trait PubsubClient: JsonRpcClient {
 	type NotificationStream: futures_core::Stream<Item = Box<RawValue>> + Send + Unpin;
 
 	/// Add a subscription to this transport
-	fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error>;
+	fn subscribe<T: Into<String>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error>;
 
 	/// Remove a subscription from this transport
-	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error>;
+	fn unsubscribe<T: Into<String>>(&self, id: T) -> Result<(), Self::Error>;
 }
 
 #[must_use = "subscriptions do nothing unless you stream them"]
 #[pin_project(PinnedDrop)]
-/// Streams data from an installed filter via `neo_subscribe`
+/// Streams data from a subscription made with `subscribe`
 pub struct SubscriptionStream<'a, P: PubsubClient, R: DeserializeOwned> {
-	/// The subscription's installed id on the neo node
-	pub id: U256,
+	/// The id of the subscription, a string like the ids neo-go nodes return
+	pub id: String,
 
 	loaded_elements: VecDeque<R>,
 
@@ -54,9 +53,9 @@ where
 	/// Instantiating this directly with a known ID will likely cause any
 	/// existing streams with that ID to end. To avoid this, start a new stream
 	/// using [`Provider::subscribe`] instead of `SubscriptionStream::new`.
-	pub fn new(id: U256, provider: &'a Provider<P>) -> Result<Self, P::Error> {
+	pub fn new(id: String, provider: &'a Provider<P>) -> Result<Self, P::Error> {
 		// Call the underlying PubsubClient's subscribe
-		let rx = provider.as_ref().subscribe(id)?;
+		let rx = provider.as_ref().subscribe(id.clone())?;
 		Ok(Self { id, provider, rx, ret: PhantomData, loaded_elements: VecDeque::new() })
 	}
 
@@ -114,6 +113,6 @@ where
 		// on drop it removes the handler from the websocket so that it stops
 		// getting populated. We need to call `unsubscribe` explicitly to cancel
 		// the subscription
-		let _ = (*self.provider).as_ref().unsubscribe(self.id);
+		let _ = (*self.provider).as_ref().unsubscribe(self.id.clone());
 	}
 }
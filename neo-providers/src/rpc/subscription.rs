@@ -0,0 +1,302 @@
+//! Events and filters of the WebSocket `subscribe` API of neo-go nodes.
+//!
+//! A subscription is made with `subscribe` and the name of an event, optionally followed by a
+//! filter the node applies before sending anything. Notifications don't carry the id of the
+//! subscription they belong to, only the event name, so the client applies the filters again to
+//! route each notification to the subscriptions it matches.
+
+use crate::core::responses::{neo_application_log::Execution, notification::Notification};
+use neo_types::{
+	script_hash::ScriptHashExtension,
+	util::{encode_string_h160, encode_string_h256},
+	vm_state::VMState,
+	*,
+};
+use primitive_types::{H160, H256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// An event a WebSocket subscription can be made to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NeoEvent {
+	/// A block was added to the chain. Filtered by [`BlockFilter`].
+	#[serde(rename = "block_added")]
+	BlockAdded,
+	/// A transaction was added to the memory pool. Filtered by [`TransactionFilter`].
+	#[serde(rename = "transaction_added")]
+	TransactionAdded,
+	/// A contract emitted a notification during an execution. Filtered by
+	/// [`NotificationFilter`].
+	#[serde(rename = "notification_from_execution")]
+	NotificationFromExecution,
+	/// A transaction or block trigger was executed. Filtered by [`ExecutionFilter`].
+	#[serde(rename = "transaction_executed")]
+	TransactionExecuted,
+}
+
+impl NeoEvent {
+	/// The name of the event in `subscribe` requests and notifications.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			NeoEvent::BlockAdded => "block_added",
+			NeoEvent::TransactionAdded => "transaction_added",
+			NeoEvent::NotificationFromExecution => "notification_from_execution",
+			NeoEvent::TransactionExecuted => "transaction_executed",
+		}
+	}
+}
+
+/// Matches blocks by the consensus node that proposed them and by index.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub primary: Option<u8>,
+	/// The first index to send.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub since: Option<u32>,
+	/// The last index to send.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub till: Option<u32>,
+}
+
+/// Matches transactions by sender or by any of their signers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionFilter {
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub sender: Option<H160>,
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub signer: Option<H160>,
+}
+
+/// Matches notifications by the contract that emitted them and by event name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationFilter {
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub contract: Option<H160>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+}
+
+impl NotificationFilter {
+	/// Whether `notification` was emitted by the contract and has the name of the filter.
+	pub fn matches(&self, notification: &Notification) -> bool {
+		self.contract.map_or(true, |contract| contract == notification.contract)
+			&& self.name.as_deref().map_or(true, |name| name == notification.event_name)
+	}
+}
+
+/// Matches executions by the VM state they ended in and by the hash of the transaction or block
+/// that was executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub state: Option<VMState>,
+	#[serde(
+		default,
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_h256_option",
+		deserialize_with = "deserialize_h256_option"
+	)]
+	pub container: Option<H256>,
+}
+
+/// A notification sent for [`NeoEvent::NotificationFromExecution`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationEvent {
+	/// The hash of the transaction or block whose execution emitted the notification.
+	#[serde(serialize_with = "serialize_h256", deserialize_with = "deserialize_h256")]
+	pub container: H256,
+	#[serde(flatten)]
+	pub notification: Notification,
+}
+
+/// An execution sent for [`NeoEvent::TransactionExecuted`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionEvent {
+	/// The hash of the transaction or block that was executed.
+	#[serde(serialize_with = "serialize_h256", deserialize_with = "deserialize_h256")]
+	pub container: H256,
+	#[serde(flatten)]
+	pub execution: Execution,
+}
+
+/// The filter of a subscription, used to route notifications to the subscriptions they match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum SubscriptionFilter {
+	Block(BlockFilter),
+	Transaction(TransactionFilter),
+	Notification(NotificationFilter),
+	Execution(ExecutionFilter),
+}
+
+impl SubscriptionFilter {
+	/// Reads the event and filter from the params of a `subscribe` request, `None` if they are
+	/// malformed.
+	pub(crate) fn from_params(params: &str) -> Option<(String, Option<Self>)> {
+		let (event, filter): (String, Option<Value>) = match serde_json::from_str(params).ok()? {
+			Value::Array(mut params) if (1..=2).contains(&params.len()) => {
+				let filter = if params.len() == 2 { params.pop() } else { None };
+				(serde_json::from_value(params.pop()?).ok()?, filter)
+			},
+			_ => return None,
+		};
+		let filter = match filter {
+			None => None,
+			Some(filter) => Some(match serde_json::from_value(Value::String(event.clone())) {
+				Ok(NeoEvent::BlockAdded) => Self::Block(serde_json::from_value(filter).ok()?),
+				Ok(NeoEvent::TransactionAdded) =>
+					Self::Transaction(serde_json::from_value(filter).ok()?),
+				Ok(NeoEvent::NotificationFromExecution) =>
+					Self::Notification(serde_json::from_value(filter).ok()?),
+				Ok(NeoEvent::TransactionExecuted) =>
+					Self::Execution(serde_json::from_value(filter).ok()?),
+				Err(_) => return None,
+			}),
+		};
+		Some((event, filter))
+	}
+
+	/// Whether the node would have sent `item` for a subscription with this filter.
+	pub(crate) fn matches(&self, item: &Value) -> bool {
+		let field = |name: &str| item.get(name).and_then(Value::as_str);
+		let same_hash = |name: &str, hash: String| {
+			field(name).is_some_and(|value| value.eq_ignore_ascii_case(&hash))
+		};
+		match self {
+			Self::Block(filter) => {
+				let index = item.get("index").and_then(Value::as_u64).unwrap_or_default();
+				let primary = item.get("primary").and_then(Value::as_u64);
+				filter.primary.map_or(true, |p| primary == Some(p as u64))
+					&& filter.since.map_or(true, |since| index >= since as u64)
+					&& filter.till.map_or(true, |till| index <= till as u64)
+			},
+			Self::Transaction(filter) => {
+				let mut signers =
+					item.get("signers").and_then(Value::as_array).into_iter().flatten();
+				filter
+					.sender
+					.map_or(true, |sender| field("sender") == Some(sender.to_address().as_str()))
+					&& filter.signer.map_or(true, |signer| {
+						let signer = encode_string_h160(&signer);
+						signers.any(|s| {
+							s.get("account")
+								.and_then(Value::as_str)
+								.is_some_and(|account| account.eq_ignore_ascii_case(&signer))
+						})
+					})
+			},
+			Self::Notification(filter) => {
+				let name = filter.name.as_deref();
+				filter.contract.map_or(true, |c| same_hash("contract", encode_string_h160(&c)))
+					&& name.map_or(true, |name| field("eventname") == Some(name))
+			},
+			Self::Execution(filter) => {
+				let state = filter.state.map(|state| state.to_string());
+				state.map_or(true, |state| field("vmstate") == Some(state.as_str()))
+					&& filter
+						.container
+						.map_or(true, |c| same_hash("container", encode_string_h256(&c)))
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_types::util::parse_address;
+	use serde_json::json;
+
+	const CONTRACT: &str = "0xd2a4cff31913016155e38e474a2c06d08be276cf";
+
+	#[test]
+	fn test_filter_params() {
+		let filter = NotificationFilter {
+			contract: Some(parse_address(CONTRACT)),
+			name: Some("Transfer".to_string()),
+		};
+		let params =
+			serde_json::to_string(&(NeoEvent::NotificationFromExecution, &filter)).unwrap();
+		assert_eq!(
+			params,
+			format!(
+				r#"["notification_from_execution",{{"contract":"{CONTRACT}","name":"Transfer"}}]"#
+			)
+		);
+		assert_eq!(
+			SubscriptionFilter::from_params(&params),
+			Some((
+				"notification_from_execution".to_string(),
+				Some(SubscriptionFilter::Notification(filter))
+			))
+		);
+
+		let params = serde_json::to_string(&[NeoEvent::BlockAdded]).unwrap();
+		assert_eq!(
+			SubscriptionFilter::from_params(&params),
+			Some(("block_added".to_string(), None))
+		);
+		assert_eq!(
+			serde_json::to_string(&ExecutionFilter { state: Some(VMState::Halt), container: None })
+				.unwrap(),
+			r#"{"state":"HALT"}"#
+		);
+		assert!(SubscriptionFilter::from_params(r#"["unknown_event",{}]"#).is_none());
+		assert!(SubscriptionFilter::from_params("[]").is_none());
+	}
+
+	#[test]
+	fn test_filter_matches() {
+		let block = json!({ "index": 10, "primary": 2 });
+		let filter =
+			|primary, since, till| SubscriptionFilter::Block(BlockFilter { primary, since, till });
+		assert!(filter(Some(2), Some(10), Some(10)).matches(&block));
+		assert!(!filter(Some(1), None, None).matches(&block));
+		assert!(!filter(None, Some(11), None).matches(&block));
+		assert!(!filter(None, None, Some(9)).matches(&block));
+
+		let signer = parse_address("0x69ecca587293047be4c59159bf8bc399985c160d");
+		let transaction = json!({
+			"sender": signer.to_address(),
+			"signers": [{ "account": "0x69ecca587293047be4c59159bf8bc399985c160d" }],
+		});
+		let filter =
+			|sender, signer| SubscriptionFilter::Transaction(TransactionFilter { sender, signer });
+		assert!(filter(Some(signer), Some(signer)).matches(&transaction));
+		assert!(!filter(Some(H160::zero()), None).matches(&transaction));
+		assert!(!filter(None, Some(H160::zero())).matches(&transaction));
+
+		let notification = json!({ "contract": CONTRACT, "eventname": "Transfer" });
+		let filter = |contract, name: Option<&str>| {
+			SubscriptionFilter::Notification(NotificationFilter {
+				contract,
+				name: name.map(str::to_string),
+			})
+		};
+		assert!(filter(Some(parse_address(CONTRACT)), Some("Transfer")).matches(&notification));
+		assert!(!filter(None, Some("Approval")).matches(&notification));
+		assert!(!filter(Some(H160::zero()), None).matches(&notification));
+
+		let execution = json!({ "container": format!("0x{}", "ab".repeat(32)), "vmstate": "HALT" });
+		let filter =
+			|state, container| SubscriptionFilter::Execution(ExecutionFilter { state, container });
+		assert!(filter(Some(VMState::Halt), Some(H256::repeat_byte(0xab))).matches(&execution));
+		assert!(!filter(Some(VMState::Fault), None).matches(&execution));
+		assert!(!filter(None, Some(H256::zero())).matches(&execution));
+	}
+}
@@ -2,8 +2,6 @@
 
 use base64::{engine::general_purpose, Engine};
 use jsonwebtoken::{encode, errors::Error, get_current_timestamp, Algorithm, EncodingKey, Header};
-use neo_types::Bytes;
-use primitive_types::U256;
 use serde::{
 	de::{self, MapAccess, Unexpected, Visitor},
 	Deserialize, Serialize,
@@ -13,58 +11,232 @@ use std::fmt;
 use thiserror::Error;
 
 /// A JSON-RPC 2.0 error
-#[derive(Deserialize, Debug, Clone, Error)]
+#[derive(Serialize, Deserialize, Debug, Clone, Error)]
 pub struct JsonRpcError {
 	/// The error code
 	pub code: i64,
 	/// The error message
 	pub message: String,
 	/// Additional data
+	#[serde(skip_serializing_if = "Option::is_none")]
 	pub data: Option<Value>,
 }
 
-/// Recursively traverses the value, looking for hex data that it can extract.
-///
-/// Inspired by neo-js logic:
-/// <https://github.com/neo-io/neo.js/blob/9f990c57f0486728902d4b8e049536f2bb3487ee/packages/providers/src.ts/json-rpc-provider.ts#L25-L53>
-fn spelunk_revert(value: &Value) -> Option<Bytes> {
-	match value {
-		Value::String(s) => Some(s.as_bytes().to_vec()),
-		Value::Object(o) => o.values().flat_map(spelunk_revert).next(),
-		_ => None,
-	}
-}
-
 impl JsonRpcError {
-	/// Determine if the error output of the `neo_call` RPC request is a revert
+	/// Classifies the error by the codes of the N3 `RpcServer` plugin, which neo-go shares.
 	///
-	/// Note that this may return false positives if called on an error from
-	/// other RPC requests
-	pub fn is_revert(&self) -> bool {
-		// Ganache says "revert" not "reverted"
-		self.message.contains("revert")
+	/// Nodes released before these codes report every unknown item as `-100` and every rejected
+	/// transaction as `-500`, so the message and data of these are matched as well.
+	pub fn kind(&self) -> NeoRpcErrorKind {
+		use NeoRpcErrorKind::*;
+		match self.code {
+			-100 => self.legacy_unknown_kind(),
+			-101 => UnknownBlock,
+			-102 => UnknownContract,
+			-103 => UnknownTransaction,
+			-104 => UnknownStorageItem,
+			-105 => UnknownScriptContainer,
+			-106 => UnknownStateRoot,
+			-107 => UnknownSession,
+			-108 => UnknownIterator,
+			-109 => UnknownHeight,
+			-300 => InsufficientFundsWallet,
+			-301 => WalletFeeLimit,
+			-302 => NoOpenedWallet,
+			-303 => WalletNotFound,
+			-304 => WalletNotSupported,
+			-500 => self.legacy_verification_kind(),
+			-501 => AlreadyExists,
+			-502 => MempoolFull,
+			-503 => AlreadyInPool,
+			-504 => InsufficientNetworkFee,
+			-505 => PolicyFailed,
+			-506 => InvalidScript,
+			-507 => InvalidAttribute,
+			-508 => InvalidSignature,
+			-509 => InvalidSize,
+			-510 => Expired,
+			-511 => InsufficientFunds,
+			-512 => InvalidVerificationFunction,
+			-600 => AccessDenied,
+			-601 => SessionsDisabled,
+			-602 => OracleDisabled,
+			-603 => OracleRequestFinished,
+			-604 => OracleRequestNotFound,
+			-605 => OracleNotDesignatedNode,
+			-606 => UnsupportedState,
+			-607 => InvalidProof,
+			-608 => ExecutionFailed,
+			-32700 => ParseError,
+			-32600 => InvalidRequest,
+			-32601 => MethodNotFound,
+			-32602 => InvalidParams,
+			-32603 => InternalError,
+			_ => Other,
+		}
 	}
 
-	/// Attempt to extract revert data from the JsonRpcError be recursively
-	/// traversing the error's data field
-	///
-	/// This returns the first hex it finds in the data object, and its
-	/// behavior may change with `serde_json` internal changes.
-	///
-	/// If no hex object is found, it will return an empty bytes IFF the error
-	/// is a revert
-	///
-	/// Inspired by neo-js logic:
-	/// <https://github.com/neo-io/neo.js/blob/9f990c57f0486728902d4b8e049536f2bb3487ee/packages/providers/src.ts/json-rpc-provider.ts#L25-L53>
-	pub fn as_revert_data(&self) -> Option<Bytes> {
-		self.is_revert()
-			.then(|| self.data.as_ref().and_then(spelunk_revert).unwrap_or_default())
+	/// The message and data, lowercase and without spaces, e.g. `unknownblock`
+	fn reason(&self) -> String {
+		let data = self.data.as_ref().map(ToString::to_string).unwrap_or_default();
+		format!("{}{data}", self.message).to_lowercase().replace([' ', '_'], "")
+	}
+
+	fn legacy_unknown_kind(&self) -> NeoRpcErrorKind {
+		use NeoRpcErrorKind::*;
+		let reason = self.reason();
+		[
+			("block", UnknownBlock),
+			("contract", UnknownContract),
+			("transaction", UnknownTransaction),
+			("storage", UnknownStorageItem),
+			("scriptcontainer", UnknownScriptContainer),
+			("stateroot", UnknownStateRoot),
+			("session", UnknownSession),
+			("iterator", UnknownIterator),
+			("height", UnknownHeight),
+		]
+		.into_iter()
+		.find(|(item, _)| reason.contains(&format!("unknown{item}")))
+		.map_or(Other, |(_, kind)| kind)
 	}
 
-	// Decode revert data (if any) into a decodeable type
-	// pub fn decode_revert_data<E: AbiDecode>(&self) -> Option<E> {
-	// 	E::decode(&self.as_revert_data()?).ok()
-	// }
+	fn legacy_verification_kind(&self) -> NeoRpcErrorKind {
+		use NeoRpcErrorKind::*;
+		let reason = self.reason();
+		// Named after the `VerifyResult` of the node
+		[
+			("alreadyinpool", AlreadyInPool),
+			("alreadyexists", AlreadyExists),
+			("outofmemory", MempoolFull),
+			("insufficientfunds", InsufficientFunds),
+			("policyfail", PolicyFailed),
+			("expired", Expired),
+			("invalidsignature", InvalidSignature),
+			("invalidscript", InvalidScript),
+			("invalidattribute", InvalidAttribute),
+			("oversize", InvalidSize),
+		]
+		.into_iter()
+		.find(|(result, _)| reason.contains(result))
+		.map_or(VerificationFailed, |(_, kind)| kind)
+	}
+}
+
+/// The meaning of the error code of a [`JsonRpcError`] returned by a Neo node, see
+/// [`JsonRpcError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NeoRpcErrorKind {
+	/// The block doesn't exist, or the node didn't reach it yet
+	UnknownBlock,
+	/// No contract is deployed with the hash or id
+	UnknownContract,
+	/// The transaction isn't in the chain nor in the memory pool
+	UnknownTransaction,
+	/// The contract has no value stored under the key
+	UnknownStorageItem,
+	/// No transaction or block has the hash of the application log
+	UnknownScriptContainer,
+	/// The node has no state root for the index
+	UnknownStateRoot,
+	/// The iterator session expired or was terminated
+	UnknownSession,
+	/// The session has no iterator with the id
+	UnknownIterator,
+	/// The height is above the one of the node
+	UnknownHeight,
+	/// The wallet opened on the node can't pay for the transaction
+	InsufficientFundsWallet,
+	/// The fee exceeds the maximum set for the wallet opened on the node
+	WalletFeeLimit,
+	/// No wallet is opened on the node
+	NoOpenedWallet,
+	/// The wallet file doesn't exist on the node
+	WalletNotFound,
+	/// The format of the wallet file isn't supported
+	WalletNotSupported,
+	/// The transaction was rejected for another reason
+	VerificationFailed,
+	/// The transaction is already in the chain
+	AlreadyExists,
+	/// The memory pool is full and the transaction's fee too low to replace another one
+	MempoolFull,
+	/// The transaction is already in the memory pool
+	AlreadyInPool,
+	/// The network fee doesn't cover the verification of the transaction
+	InsufficientNetworkFee,
+	/// The transaction violates the policy, e.g. a blocked account
+	PolicyFailed,
+	/// The script of the transaction is invalid
+	InvalidScript,
+	/// An attribute of the transaction is invalid
+	InvalidAttribute,
+	/// A witness of the transaction doesn't verify
+	InvalidSignature,
+	/// The transaction is too large
+	InvalidSize,
+	/// The `valid_until_block` of the transaction is passed
+	Expired,
+	/// The sender can't pay the fees of the transaction
+	InsufficientFunds,
+	/// The `verify` method of the contract is missing or invalid
+	InvalidVerificationFunction,
+	/// The node refused the request
+	AccessDenied,
+	/// Iterator sessions aren't enabled on the node
+	SessionsDisabled,
+	/// The oracle service isn't enabled on the node
+	OracleDisabled,
+	/// The oracle request was already answered
+	OracleRequestFinished,
+	/// No oracle request has the id
+	OracleRequestNotFound,
+	/// The node isn't a designated oracle node
+	OracleNotDesignatedNode,
+	/// The state is no longer kept by the node
+	UnsupportedState,
+	/// The state proof is invalid
+	InvalidProof,
+	/// The script faulted
+	ExecutionFailed,
+	/// The request isn't valid JSON
+	ParseError,
+	/// The request isn't a valid JSON-RPC request
+	InvalidRequest,
+	/// The method doesn't exist or isn't enabled on the node
+	MethodNotFound,
+	/// The params of the request are invalid
+	InvalidParams,
+	/// The node failed to process the request
+	InternalError,
+	/// A code this crate doesn't know
+	Other,
+}
+
+impl NeoRpcErrorKind {
+	/// Whether the error is about an iterator session
+	pub fn is_session_error(&self) -> bool {
+		matches!(self, Self::UnknownSession | Self::UnknownIterator | Self::SessionsDisabled)
+	}
+
+	/// Whether a transaction was rejected because of its content, so that sending it again can't
+	/// succeed
+	pub fn is_invalid_transaction(&self) -> bool {
+		matches!(
+			self,
+			Self::AlreadyExists
+				| Self::AlreadyInPool
+				| Self::InsufficientNetworkFee
+				| Self::PolicyFailed
+				| Self::InvalidScript
+				| Self::InvalidAttribute
+				| Self::InvalidSignature
+				| Self::InvalidSize
+				| Self::Expired
+				| Self::InsufficientFunds
+				| Self::InvalidVerificationFunction
+		)
+	}
 }
 
 impl fmt::Display for JsonRpcError {
@@ -104,7 +276,7 @@ pub enum Response<'a> {
 
 #[derive(Deserialize, Debug)]
 pub struct Params<'a> {
-	pub subscription: U256,
+	pub subscription: String,
 	#[serde(borrow)]
 	pub result: &'a RawValue,
 }
@@ -457,4 +629,50 @@ mod tests {
 			claims
 		);
 	}
+
+	#[test]
+	fn test_error_kind() {
+		let error = |code: i64, message: &str, data: Option<&str>| JsonRpcError {
+			code,
+			message: message.to_string(),
+			data: data.map(|data| Value::String(data.to_string())),
+		};
+		assert_eq!(error(-101, "Unknown block", None).kind(), NeoRpcErrorKind::UnknownBlock);
+		assert_eq!(
+			error(-104, "Unknown storage item", None).kind(),
+			NeoRpcErrorKind::UnknownStorageItem
+		);
+		assert_eq!(error(-510, "Expired transaction", None).kind(), NeoRpcErrorKind::Expired);
+		assert_eq!(
+			error(-502, "Memory pool capacity reached", None).kind(),
+			NeoRpcErrorKind::MempoolFull
+		);
+		assert_eq!(error(-32601, "Method not found", None).kind(), NeoRpcErrorKind::MethodNotFound);
+		assert_eq!(error(-32000, "error occurred", None).kind(), NeoRpcErrorKind::Other);
+		assert!(error(-107, "Unknown session", None).kind().is_session_error());
+
+		// Nodes without the dedicated codes
+		assert_eq!(error(-100, "Unknown block", None).kind(), NeoRpcErrorKind::UnknownBlock);
+		assert_eq!(
+			error(-100, "Unknown transaction", None).kind(),
+			NeoRpcErrorKind::UnknownTransaction
+		);
+		assert_eq!(error(-100, "Unknown contract", None).kind(), NeoRpcErrorKind::UnknownContract);
+		assert_eq!(
+			error(-500, "InsufficientFunds", None).kind(),
+			NeoRpcErrorKind::InsufficientFunds
+		);
+		assert_eq!(
+			error(-500, "Inventory verification failed", Some("PolicyFail")).kind(),
+			NeoRpcErrorKind::PolicyFailed
+		);
+		assert_eq!(error(-500, "AlreadyExists", None).kind(), NeoRpcErrorKind::AlreadyExists);
+		assert_eq!(
+			error(-500, "Invalid signature", None).kind(),
+			NeoRpcErrorKind::InvalidSignature
+		);
+		assert_eq!(error(-500, "Server is busy", None).kind(), NeoRpcErrorKind::VerificationFailed);
+		assert!(error(-500, "InsufficientFunds", None).kind().is_invalid_transaction());
+		assert!(!error(-500, "Server is busy", None).kind().is_invalid_transaction());
+	}
 }
@@ -0,0 +1,466 @@
+//! A [JsonRpcClient] implementation that routes requests to the healthiest of a pool of nodes and
+//! fails over to the next one when a node can't be reached.
+
+use super::quorum::QuorumParams;
+use crate::{errors::ProviderError, BatchClient, JsonRpcClient, JsonRpcError, RpcError};
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{fmt::Debug, sync::Mutex};
+use thiserror::Error;
+use tracing::debug;
+
+/// Methods that must reach the network at most once, so they are sent to a single node and never
+/// retried on another one.
+const PINNED_METHODS: &[&str] =
+	&["sendrawtransaction", "submitblock", "sendfrom", "sendmany", "sendtoaddress"];
+
+/// A client routing each request to the best healthy node of a pool.
+///
+/// Nodes are ranked by health: nodes that failed `max_errors` times in a row are benched for the
+/// `cooldown`, and nodes more than `max_lag` blocks behind the highest node are avoided. Healthy
+/// nodes are then ordered by their lag and latency, ties keeping the order of the pool.
+///
+/// Reads are retried on the next node when a node can't be reached. A JSON-RPC error response is
+/// the node's answer to the request and returned as is. Methods that broadcast, like
+/// `sendrawtransaction`, are only sent to the best node, so that a timed out request isn't
+/// broadcast twice.
+///
+/// The heights of the nodes are updated whenever they answer `getblockcount`, which
+/// [`FailoverClient::check_health`] sends to every node.
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{FailoverClient, Http, Provider};
+/// use std::str::FromStr;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let seeds = ["http://seed1.neo.org:10332", "http://seed2.neo.org:10332"];
+/// let nodes = seeds.iter().map(|seed| Http::from_str(seed)).collect::<Result<Vec<_>, _>>()?;
+/// let provider = Provider::new(FailoverClient::new(nodes));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FailoverClient<T = Box<dyn super::JsonRpcClientWrapper>> {
+	nodes: Vec<Node<T>>,
+	/// How many consecutive failures bench a node
+	max_errors: u32,
+	/// How long a benched node is skipped
+	cooldown: Duration,
+	/// How many blocks a node may be behind the highest node
+	max_lag: u32,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+	client: T,
+	state: Mutex<NodeState>,
+}
+
+#[derive(Debug, Default)]
+struct NodeState {
+	latency: Option<Duration>,
+	consecutive_errors: u32,
+	requests: u64,
+	errors: u64,
+	height: Option<u32>,
+	failed_at: Option<Instant>,
+}
+
+/// A snapshot of the health of a node of a [`FailoverClient`], e.g. for metrics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+	/// Whether requests are routed to the node
+	pub healthy: bool,
+	/// Moving average of the time the node takes to answer
+	pub latency: Option<Duration>,
+	/// Failures since the last answer of the node
+	pub consecutive_errors: u32,
+	/// Requests sent to the node
+	pub requests: u64,
+	/// Requests to the node that failed
+	pub errors: u64,
+	/// The last block count reported by the node
+	pub height: Option<u32>,
+	/// How many blocks the node is behind the highest node
+	pub lag: Option<u32>,
+}
+
+impl<T> FailoverClient<T> {
+	/// Creates a client over `nodes` with the default thresholds of [`FailoverClientBuilder`]
+	pub fn new(nodes: impl IntoIterator<Item = T>) -> Self {
+		FailoverClientBuilder::default().build(nodes)
+	}
+
+	/// Convenience method for creating a `FailoverClientBuilder`
+	pub fn builder() -> FailoverClientBuilder {
+		FailoverClientBuilder::default()
+	}
+
+	/// The clients of the nodes, in the order of the pool
+	pub fn nodes(&self) -> impl Iterator<Item = &T> {
+		self.nodes.iter().map(|node| &node.client)
+	}
+
+	/// The health of every node, in the order of the pool
+	pub fn health(&self) -> Vec<NodeHealth> {
+		let states: Vec<_> = self.nodes.iter().map(|node| node.state.lock().unwrap()).collect();
+		let best = states.iter().filter_map(|state| state.height).max();
+		states
+			.iter()
+			.map(|state| {
+				let lag = state.height.zip(best).map(|(height, best)| best - height);
+				let benched = state.consecutive_errors >= self.max_errors
+					&& state.failed_at.is_some_and(|at| at.elapsed() < self.cooldown);
+				NodeHealth {
+					healthy: !benched && lag.map_or(true, |lag| lag <= self.max_lag),
+					latency: state.latency,
+					consecutive_errors: state.consecutive_errors,
+					requests: state.requests,
+					errors: state.errors,
+					height: state.height,
+					lag,
+				}
+			})
+			.collect()
+	}
+
+	/// The indices of the nodes, best first. Unhealthy nodes are kept last, as a last resort.
+	fn ranked(&self) -> Vec<usize> {
+		let health = self.health();
+		let mut ranked: Vec<usize> = (0..self.nodes.len()).collect();
+		ranked.sort_by_key(|index| {
+			let health = &health[*index];
+			(!health.healthy, health.lag.unwrap_or_default(), health.latency.unwrap_or_default())
+		});
+		ranked
+	}
+
+	/// Records a request to node `index` that took `latency`. A node that answered, even with an
+	/// error response, is healthy, and `height` is the block count it reported if any.
+	fn record(&self, index: usize, latency: Duration, answered: bool, height: Option<u32>) {
+		let mut state = self.nodes[index].state.lock().unwrap();
+		state.requests += 1;
+		if answered {
+			state.consecutive_errors = 0;
+			state.latency = Some(match state.latency {
+				Some(average) => average.mul_f64(0.8) + latency.mul_f64(0.2),
+				None => latency,
+			});
+			state.height = height.or(state.height);
+		} else {
+			state.consecutive_errors += 1;
+			state.errors += 1;
+			state.failed_at = Some(Instant::now());
+		}
+	}
+}
+
+impl<T: JsonRpcClient> FailoverClient<T> {
+	/// Sends `getblockcount` to every node, updating their height, latency and errors.
+	pub async fn check_health(&self) {
+		let params = QuorumParams::Zst;
+		join_all((0..self.nodes.len()).map(|index| self.call(index, "getblockcount", &params)))
+			.await;
+	}
+
+	async fn call(
+		&self,
+		index: usize,
+		method: &str,
+		params: &QuorumParams,
+	) -> Result<Value, T::Error> {
+		let node = &self.nodes[index];
+		let start = Instant::now();
+		let result = match params {
+			QuorumParams::Value(params) => JsonRpcClient::fetch(&node.client, method, params).await,
+			QuorumParams::Zst => JsonRpcClient::fetch(&node.client, method, ()).await,
+		};
+		let answered = result.as_ref().map_or_else(RpcError::is_error_response, |_| true);
+		let height = match (&result, method) {
+			(Ok(count), "getblockcount") => count.as_u64().map(|count| count as u32),
+			_ => None,
+		};
+		self.record(index, start.elapsed(), answered, height);
+		result
+	}
+}
+
+impl<T: BatchClient> FailoverClient<T> {
+	async fn call_batch(
+		&self,
+		index: usize,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, T::Error>>, T::Error> {
+		let start = Instant::now();
+		let result = self.nodes[index].client.fetch_batch(calls).await;
+		let answered = result.as_ref().map_or_else(RpcError::is_error_response, |_| true);
+		let height = result.as_ref().ok().and_then(|results| {
+			calls
+				.iter()
+				.zip(results)
+				.filter(|((method, _), _)| method == "getblockcount")
+				.find_map(|(_, result)| result.as_ref().ok()?.as_u64())
+				.map(|count| count as u32)
+		});
+		self.record(index, start.elapsed(), answered, height);
+		result
+	}
+}
+
+/// Builder for a [`FailoverClient`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FailoverClientBuilder {
+	max_errors: u32,
+	cooldown: Duration,
+	max_lag: u32,
+}
+
+impl Default for FailoverClientBuilder {
+	fn default() -> Self {
+		Self { max_errors: 3, cooldown: Duration::from_secs(30), max_lag: 3 }
+	}
+}
+
+impl FailoverClientBuilder {
+	/// Sets how many consecutive failures bench a node.
+	pub fn max_errors(mut self, max_errors: u32) -> Self {
+		self.max_errors = max_errors;
+		self
+	}
+
+	/// Sets how long a benched node is skipped before it's tried again.
+	pub fn cooldown(mut self, cooldown: Duration) -> Self {
+		self.cooldown = cooldown;
+		self
+	}
+
+	/// Sets how many blocks a node may be behind the highest node and still be healthy.
+	pub fn max_lag(mut self, max_lag: u32) -> Self {
+		self.max_lag = max_lag;
+		self
+	}
+
+	/// Creates the client over `nodes`.
+	pub fn build<T>(self, nodes: impl IntoIterator<Item = T>) -> FailoverClient<T> {
+		FailoverClient {
+			nodes: nodes
+				.into_iter()
+				.map(|client| Node { client, state: Mutex::new(NodeState::default()) })
+				.collect(),
+			max_errors: self.max_errors,
+			cooldown: self.cooldown,
+			max_lag: self.max_lag,
+		}
+	}
+}
+
+/// Error thrown when sending a request to a [`FailoverClient`]
+#[derive(Error, Debug)]
+pub enum FailoverClientError {
+	/// Thrown if the pool is empty
+	#[error("no nodes to send the request to")]
+	NoNodes,
+	/// The error of the node that answered the request, or of the node a pinned request was sent
+	/// to
+	#[error(transparent)]
+	Node(ProviderError),
+	/// Thrown if no node could be reached, with the error of every node tried
+	#[error("all nodes failed: {0:?}")]
+	AllNodesFailed(Vec<ProviderError>),
+	/// Thrown if the params couldn't be serialized or the response deserialized
+	#[error(transparent)]
+	SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for FailoverClientError {
+	fn as_error_response(&self) -> Option<&JsonRpcError> {
+		match self {
+			FailoverClientError::Node(err) => err.as_error_response(),
+			_ => None,
+		}
+	}
+
+	fn as_serde_error(&self) -> Option<&serde_json::Error> {
+		match self {
+			FailoverClientError::Node(err) => err.as_serde_error(),
+			FailoverClientError::SerdeJson(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<FailoverClientError> for ProviderError {
+	fn from(src: FailoverClientError) -> Self {
+		ProviderError::JsonRpcClientError(Box::new(src))
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: JsonRpcClient> JsonRpcClient for FailoverClient<C> {
+	type Error = FailoverClientError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let params = QuorumParams::new(params)?;
+		let ranked = self.ranked();
+		let Some(best) = ranked.first() else { return Err(FailoverClientError::NoNodes) };
+
+		if PINNED_METHODS.contains(&method) {
+			let response = self
+				.call(*best, method, &params)
+				.await
+				.map_err(|err| FailoverClientError::Node(err.into()))?;
+			return Ok(serde_json::from_value(response)?)
+		}
+
+		let mut errors = Vec::new();
+		for index in ranked {
+			match self.call(index, method, &params).await {
+				Ok(response) => return Ok(serde_json::from_value(response)?),
+				Err(err) if err.is_error_response() =>
+					return Err(FailoverClientError::Node(err.into())),
+				Err(err) => {
+					debug!(node = index, %err, "Node failed, trying the next one");
+					errors.push(err.into());
+				},
+			}
+		}
+		Err(FailoverClientError::AllNodesFailed(errors))
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: BatchClient> BatchClient for FailoverClient<C> {
+	/// Sends the batch to the best node, failing over like a request. A batch with a call of a
+	/// broadcasting method is only sent to the best node.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+		let ranked = self.ranked();
+		if ranked.is_empty() {
+			return Err(FailoverClientError::NoNodes)
+		}
+		let pinned = calls.iter().any(|(method, _)| PINNED_METHODS.contains(&method.as_str()));
+		let nodes = if pinned { &ranked[..1] } else { &ranked[..] };
+
+		let mut errors = Vec::new();
+		for &index in nodes {
+			match self.call_batch(index, calls).await {
+				Ok(results) =>
+					return Ok(results
+						.into_iter()
+						.map(|result| result.map_err(|err| FailoverClientError::Node(err.into())))
+						.collect()),
+				Err(err) if pinned || err.is_error_response() =>
+					return Err(FailoverClientError::Node(err.into())),
+				Err(err) => {
+					debug!(node = index, %err, "Node failed, trying the next one");
+					errors.push(err.into());
+				},
+			}
+		}
+		Err(FailoverClientError::AllNodesFailed(errors))
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use crate::{MockProvider, MockResponse};
+	use serde_json::json;
+
+	/// A node per list of responses, answering with them in order. A node without responses
+	/// fails like an unreachable one.
+	fn pool(builder: FailoverClientBuilder, nodes: &[&[Value]]) -> FailoverClient<MockProvider> {
+		builder.build(nodes.iter().map(|responses| {
+			let mock = MockProvider::new();
+			responses
+				.iter()
+				.rev()
+				.for_each(|response| mock.push_response(MockResponse::Value(response.clone())));
+			mock
+		}))
+	}
+
+	fn requests(client: &FailoverClient<MockProvider>) -> Vec<u64> {
+		client.health().iter().map(|health| health.requests).collect()
+	}
+
+	#[tokio::test]
+	async fn test_reads_fail_over() {
+		let client = pool(FailoverClient::builder(), &[&[], &[json!("0x01"), json!("0x02")]]);
+		let hash: String = client.fetch("getbestblockhash", ()).await.unwrap();
+		assert_eq!(hash, "0x01");
+		assert_eq!(client.health()[0].errors, 1);
+		assert!(client.health()[0].healthy);
+
+		// A benched node isn't tried first
+		let client =
+			pool(FailoverClient::builder().max_errors(1), &[&[], &[json!("0x01"), json!("0x02")]]);
+		let _: String = client.fetch("getbestblockhash", ()).await.unwrap();
+		assert!(!client.health()[0].healthy);
+		let hash: String = client.fetch("getbestblockhash", ()).await.unwrap();
+		assert_eq!(hash, "0x02");
+		assert_eq!(requests(&client), vec![1, 2]);
+
+		let client = pool(FailoverClient::builder(), &[&[], &[]]);
+		let err = client.fetch::<_, String>("getbestblockhash", ()).await.unwrap_err();
+		assert!(matches!(err, FailoverClientError::AllNodesFailed(errors) if errors.len() == 2));
+	}
+
+	#[tokio::test]
+	async fn test_error_responses_are_answers() {
+		let mock = MockProvider::new();
+		let error = JsonRpcError { code: -100, message: "Unknown block".to_string(), data: None };
+		mock.push_response(MockResponse::Error(error));
+		let client = FailoverClient::new([mock, MockProvider::new()]);
+		let err = client.fetch::<_, Value>("getblock", [100, 1]).await.unwrap_err();
+		assert_eq!(err.as_error_response().unwrap().code, -100);
+		assert_eq!(requests(&client), vec![1, 0]);
+		assert_eq!(client.health()[0].consecutive_errors, 0);
+	}
+
+	#[tokio::test]
+	async fn test_broadcasts_are_pinned() {
+		let client = pool(FailoverClient::builder(), &[&[], &[json!({ "hash": "0x01" })]]);
+		let err = client.fetch::<_, Value>("sendrawtransaction", ["AA=="]).await.unwrap_err();
+		assert!(matches!(err, FailoverClientError::Node(_)));
+		assert_eq!(requests(&client), vec![1, 0]);
+	}
+
+	#[tokio::test]
+	async fn test_lagging_nodes_are_avoided() {
+		let client = pool(
+			FailoverClient::builder(),
+			&[&[json!(90), json!("0x01")], &[json!(100), json!("0x02")]],
+		);
+		client.check_health().await;
+		let health = client.health();
+		assert_eq!(
+			(health[0].height, health[0].lag, health[0].healthy),
+			(Some(90), Some(10), false)
+		);
+		assert_eq!(
+			(health[1].height, health[1].lag, health[1].healthy),
+			(Some(100), Some(0), true)
+		);
+
+		let hash: String = client.fetch("getbestblockhash", ()).await.unwrap();
+		assert_eq!(hash, "0x02");
+		let node = client.nodes().nth(1).unwrap();
+		node.assert_request("getblockcount", ()).unwrap();
+		node.assert_request("getbestblockhash", ()).unwrap();
+	}
+}
@@ -1,10 +1,11 @@
 // Code adapted from: https://github.com/althea-net/guac_rs/tree/master/web3/src/jsonrpc
 
 use super::common::{Authorization, JsonRpcError, Request, Response};
-use crate::{errors::ProviderError, JsonRpcClient};
+use crate::{errors::ProviderError, BatchClient, JsonRpcClient};
 use async_trait::async_trait;
 use reqwest::{header, header::HeaderValue, Client, Error as ReqwestError};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::{
 	str::FromStr,
 	sync::atomic::{AtomicU64, Ordering},
@@ -93,6 +94,8 @@ impl JsonRpcClient for Provider {
 		let payload = Request::new(next_id, method, params);
 
 		let res = self.client.post(self.url.as_ref()).json(&payload).send().await?;
+		// Proxies in front of a node refuse requests with a status and no JSON-RPC body
+		let status = res.error_for_status_ref().err();
 		let body = res.bytes().await?;
 
 		let raw = match serde_json::from_slice(&body) {
@@ -106,9 +109,12 @@ impl JsonRpcClient for Provider {
 				return Err(err)
 			},
 			Err(err) =>
-				return Err(ClientError::SerdeJson {
-					err,
-					text: String::from_utf8_lossy(&body).to_string(),
+				return Err(match status {
+					Some(status) => status.into(),
+					None => ClientError::SerdeJson {
+						err,
+						text: String::from_utf8_lossy(&body).to_string(),
+					},
 				}),
 		};
 
@@ -119,6 +125,77 @@ impl JsonRpcClient for Provider {
 	}
 }
 
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl BatchClient for Provider {
+	/// Sends the `calls` in a single HTTP request, returning their results in the order of the
+	/// calls whatever the order of the responses.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, ClientError>>, ClientError> {
+		// A response of the batch, tolerating the `null` id of calls the node couldn't parse
+		#[derive(Deserialize)]
+		struct BatchResponse {
+			id: Option<u64>,
+			#[serde(default)]
+			result: Value,
+			error: Option<JsonRpcError>,
+		}
+
+		if calls.is_empty() {
+			return Ok(Vec::new())
+		}
+		let first_id = self.id.fetch_add(calls.len() as u64, Ordering::SeqCst);
+		let payload: Vec<_> = calls
+			.iter()
+			.zip(first_id..)
+			.map(|((method, params), id)| Request::new(id, method.as_str(), params))
+			.collect();
+
+		let res = self.client.post(self.url.as_ref()).json(&payload).send().await?;
+		let status = res.error_for_status_ref().err();
+		let body = res.bytes().await?;
+
+		let responses: Vec<BatchResponse> = match serde_json::from_slice(&body) {
+			Ok(responses) => responses,
+			// A batch the node rejects is answered with a single error
+			Err(err) => match (serde_json::from_slice(&body), status) {
+				(Ok(Response::Error { error, .. }), _) => return Err(error.into()),
+				(_, Some(status)) => return Err(status.into()),
+				_ =>
+					return Err(ClientError::SerdeJson {
+						err,
+						text: String::from_utf8_lossy(&body).to_string(),
+					}),
+			},
+		};
+
+		let mut results: Vec<Option<Result<Value, ClientError>>> =
+			calls.iter().map(|_| None).collect();
+		for response in responses {
+			let index = response.id.and_then(|id| id.checked_sub(first_id));
+			if let Some(result) = index.and_then(|index| results.get_mut(index as usize)) {
+				*result = Some(match response.error {
+					Some(error) => Err(error.into()),
+					None => Ok(response.result),
+				});
+			}
+		}
+		Ok(results
+			.into_iter()
+			.map(|result| {
+				result.unwrap_or_else(|| {
+					Err(ClientError::SerdeJson {
+						err: serde::de::Error::custom("no response to the call in the batch"),
+						text: String::new(),
+					})
+				})
+			})
+			.collect())
+	}
+}
+
 impl Provider {
 	/// Initializes a new HTTP Client
 	///
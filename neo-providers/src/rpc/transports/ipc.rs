@@ -5,7 +5,6 @@ use futures_channel::mpsc;
 use futures_util::stream::StreamExt;
 use hashers::fx_hash::FxHasher64;
 
-use primitive_types::U256;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{value::RawValue, Deserializer};
 use std::{
@@ -220,8 +219,8 @@ pub struct Ipc {
 #[derive(Debug)]
 enum TransportMessage {
 	Request { id: u64, request: Box<[u8]>, sender: Pending },
-	Subscribe { id: U256, sink: Subscription },
-	Unsubscribe { id: U256 },
+	Subscribe { id: String, sink: Subscription },
+	Unsubscribe { id: String },
 }
 
 impl Ipc {
@@ -283,13 +282,13 @@ impl JsonRpcClient for Ipc {
 impl PubsubClient for Ipc {
 	type NotificationStream = mpsc::UnboundedReceiver<Box<RawValue>>;
 
-	fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, IpcError> {
+	fn subscribe<T: Into<String>>(&self, id: T) -> Result<Self::NotificationStream, IpcError> {
 		let (sink, stream) = mpsc::unbounded();
 		self.send(TransportMessage::Subscribe { id: id.into(), sink })?;
 		Ok(stream)
 	}
 
-	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), IpcError> {
+	fn unsubscribe<T: Into<String>>(&self, id: T) -> Result<(), IpcError> {
 		self.send(TransportMessage::Unsubscribe { id: id.into() })
 	}
 }
@@ -339,7 +338,7 @@ async fn run_ipc_server(mut stream: Stream, request_rx: mpsc::UnboundedReceiver<
 
 struct Shared {
 	pending: RefCell<FxHashMap<u64, Pending>>,
-	subs: RefCell<FxHashMap<U256, Subscription>>,
+	subs: RefCell<FxHashMap<String, Subscription>>,
 }
 
 impl Shared {
@@ -383,7 +382,7 @@ impl Shared {
 					}
 				},
 				Subscribe { id, sink } =>
-					if self.subs.borrow_mut().insert(id, sink).is_some() {
+					if self.subs.borrow_mut().insert(id.clone(), sink).is_some() {
 						tracing::warn!(
 							%id,
 							"replaced already-registered subscription"
@@ -512,6 +511,7 @@ impl crate::RpcError for IpcError {
 mod tests {
 	use super::*;
 	use neo_types::{block::Block, TxHash};
+	use primitive_types::U256;
 	use std::time::Duration;
 	use tempfile::NamedTempFile;
 
@@ -545,7 +545,7 @@ mod tests {
 
 		// Subscribing requires sending the sub request and then subscribing to
 		// the returned sub_id
-		let sub_id: U256 = ipc.fetch("neo_subscribe", ["newHeads"]).await.unwrap();
+		let sub_id: String = ipc.fetch("neo_subscribe", ["newHeads"]).await.unwrap();
 		let stream = ipc.subscribe(sub_id).unwrap();
 
 		let blocks: Vec<u64> = stream
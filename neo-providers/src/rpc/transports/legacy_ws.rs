@@ -12,7 +12,6 @@ use futures_util::{
 };
 
 use log::{debug, error};
-use primitive_types::U256;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::value::RawValue;
 use std::{
@@ -91,9 +90,9 @@ enum Instruction {
 	/// JSON-RPC request
 	Request { id: u64, request: String, sender: Pending },
 	/// Create a new subscription
-	Subscribe { id: U256, sink: Subscription },
+	Subscribe { id: String, sink: Subscription },
 	/// Cancel an existing subscription
-	Unsubscribe { id: U256 },
+	Unsubscribe { id: String },
 }
 
 /// A JSON-RPC Client over Websockets.
@@ -208,13 +207,13 @@ impl JsonRpcClient for Ws {
 impl PubsubClient for Ws {
 	type NotificationStream = mpsc::UnboundedReceiver<Box<RawValue>>;
 
-	fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, ClientError> {
+	fn subscribe<T: Into<String>>(&self, id: T) -> Result<Self::NotificationStream, ClientError> {
 		let (sink, stream) = mpsc::unbounded();
 		self.send(Instruction::Subscribe { id: id.into(), sink })?;
 		Ok(stream)
 	}
 
-	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), ClientError> {
+	fn unsubscribe<T: Into<String>>(&self, id: T) -> Result<(), ClientError> {
 		self.send(Instruction::Unsubscribe { id: id.into() })
 	}
 }
@@ -224,7 +223,7 @@ struct WsServer<S> {
 	instructions: Fuse<mpsc::UnboundedReceiver<Instruction>>,
 
 	pending: BTreeMap<u64, Pending>,
-	subscriptions: BTreeMap<U256, Subscription>,
+	subscriptions: BTreeMap<String, Subscription>,
 }
 
 impl<S> WsServer<S>
@@ -306,15 +305,19 @@ where
 	}
 
 	/// Dispatch a subscription request
-	async fn service_subscribe(&mut self, id: U256, sink: Subscription) -> Result<(), ClientError> {
-		if self.subscriptions.insert(id, sink).is_some() {
+	async fn service_subscribe(
+		&mut self,
+		id: String,
+		sink: Subscription,
+	) -> Result<(), ClientError> {
+		if self.subscriptions.insert(id.clone(), sink).is_some() {
 			warn!("Replacing already-registered subscription with id {:?}", id);
 		}
 		Ok(())
 	}
 
 	/// Dispatch a unsubscribe request
-	async fn service_unsubscribe(&mut self, id: U256) -> Result<(), ClientError> {
+	async fn service_unsubscribe(&mut self, id: String) -> Result<(), ClientError> {
 		if self.subscriptions.remove(&id).is_none() {
 			warn!("Unsubscribing from non-existent subscription with id {:?}", id);
 		}
@@ -526,6 +529,7 @@ impl From<ClientError> for ProviderError {
 mod tests {
 	use super::*;
 	use neo_types::block::Block;
+	use primitive_types::U256;
 
 	#[tokio::test]
 	async fn request() {
@@ -547,7 +551,7 @@ mod tests {
 
 		// Subscribing requires sending the sub request and then subscribing to
 		// the returned sub_id
-		let sub_id: U256 = ws.request("neo_subscribe", ["newHeads"]).await.unwrap();
+		let sub_id: String = ws.request("neo_subscribe", ["newHeads"]).await.unwrap();
 		let stream = ws.subscribe(sub_id).unwrap();
 
 		let blocks: Vec<u64> = stream
@@ -1,5 +1,5 @@
 pub(crate) mod common;
-pub use common::{Authorization, JsonRpcError, JwtAuth, JwtKey};
+pub use common::{Authorization, JsonRpcError, JwtAuth, JwtKey, NeoRpcErrorKind};
 
 mod http;
 pub use self::http::{ClientError as HttpClientError, Provider as Http};
@@ -9,8 +9,22 @@ mod ipc;
 #[cfg(all(feature = "ipc", any(unix, windows)))]
 pub use ipc::{Ipc, IpcError};
 
-// mod quorum;
-// pub use quorum::{JsonRpcClientWrapper, Quorum, QuorumError, QuorumProvider, WeightedProvider};
+mod quorum;
+pub use quorum::{JsonRpcClientWrapper, Quorum, QuorumError, QuorumProvider, WeightedProvider};
+
+mod failover;
+pub use failover::{FailoverClient, FailoverClientBuilder, FailoverClientError, NodeHealth};
+
+mod rate_limit;
+pub use rate_limit::{RateLimitStats, RateLimitedClient, RateLimitedClientBuilder};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod recording;
+#[cfg(not(target_arch = "wasm32"))]
+pub use recording::{
+	FixtureError, Interaction, RecordedResponse, RecordingClient, RecordingClientError,
+	ReplayClient, ReplayClientError,
+};
 
 mod rw;
 pub use rw::{RwClient, RwClientError};
@@ -0,0 +1,604 @@
+//! A [JsonRpcClient] implementation that sends every request to several nodes and only returns a
+//! response once a [Quorum] of them agrees on it.
+
+use crate::{errors::ProviderError, JsonRpcClient, JsonRpcError};
+use async_trait::async_trait;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// Methods returning heights of the node, which differ by a block or two between nodes that are
+/// in sync, with the fields holding the heights when the result is an object.
+const HEIGHT_METHODS: &[(&str, &[&str])] = &[
+	("getblockcount", &[]),
+	("getblockheadercount", &[]),
+	("getstateheight", &["localrootindex", "validatedrootindex"]),
+];
+
+/// A provider that bundles multiple providers and only returns a value to the caller once the
+/// quorum has been reached.
+///
+/// Nodes that are in sync can still be a block apart, so responses are compared after removing
+/// the fields that depend on the height of the node, like the `confirmations` of a block. For
+/// `getblockcount`, `getblockheadercount` and the heights of `getstateheight` all nodes are
+/// awaited and the result is the highest height that a quorum of nodes has reached.
+/// `getbestblockhash` returns the hash of the highest block that a quorum has reached, requested
+/// with `getblockhash` once the block count is agreed on.
+///
+/// Other responses are compared exactly. In particular a node returns a state root by
+/// `getstateroot` without its witnesses until it has validated it, so request roots up to the
+/// `validatedrootindex` of `getstateheight` only.
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{Http, Provider, Quorum, QuorumProvider, WeightedProvider};
+/// use std::str::FromStr;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let quorum = QuorumProvider::dyn_rpc()
+/// 	.add_provider(WeightedProvider::new(Box::new(Http::from_str("http://seed1.neo.org:10332")?)))
+/// 	.add_provider(WeightedProvider::new(Box::new(Http::from_str("http://seed2.neo.org:10332")?)))
+/// 	.add_provider(WeightedProvider::with_weight(
+/// 		Box::new(Http::from_str("http://localhost:10332")?),
+/// 		2,
+/// 	))
+/// 	.quorum(Quorum::Majority)
+/// 	.build();
+/// let provider = Provider::quorum(quorum);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuorumProvider<T = Box<dyn JsonRpcClientWrapper>> {
+	/// What kind of quorum is required
+	quorum: Quorum,
+	/// The total weight of all providers
+	total_weight: u64,
+	/// All the internal providers this providers runs
+	providers: Vec<WeightedProvider<T>>,
+}
+
+impl QuorumProvider<Box<dyn JsonRpcClientWrapper>> {
+	/// Create a `QuorumProvider` for different `JsonRpcClient` types
+	pub fn dyn_rpc() -> QuorumProviderBuilder<Box<dyn JsonRpcClientWrapper>> {
+		Self::builder()
+	}
+}
+
+impl<T> QuorumProvider<T> {
+	/// Convenience method for creating a `QuorumProviderBuilder` with same `JsonRpcClient` types
+	pub fn builder() -> QuorumProviderBuilder<T> {
+		QuorumProviderBuilder::default()
+	}
+
+	/// Instantiate a new `QuorumProvider` from a [`Quorum`] and a set of providers
+	pub fn new(quorum: Quorum, providers: impl IntoIterator<Item = WeightedProvider<T>>) -> Self {
+		Self::builder().add_providers(providers).quorum(quorum).build()
+	}
+
+	/// Return a reference to the weighted providers
+	pub fn providers(&self) -> &[WeightedProvider<T>] {
+		&self.providers
+	}
+
+	/// The configured quorum
+	pub fn quorum(&self) -> Quorum {
+		self.quorum
+	}
+
+	/// Adds a provider to the set
+	pub fn add_provider(&mut self, provider: WeightedProvider<T>) {
+		self.total_weight += provider.weight;
+		self.providers.push(provider);
+	}
+
+	/// Whether providers with a total weight of `weight`, `count` of them, reach the quorum
+	fn is_reached(&self, weight: u64, count: usize) -> bool {
+		match self.quorum {
+			Quorum::All => weight >= self.total_weight,
+			Quorum::Majority => weight * 2 > self.total_weight,
+			Quorum::Percentage(percentage) =>
+				weight * 100 >= self.total_weight * percentage.min(100) as u64,
+			Quorum::ProviderCount(providers) => count >= providers,
+			Quorum::Weight(threshold) => weight >= threshold,
+		}
+	}
+
+	/// Returns the response the quorum agrees on, if any
+	fn agreed_response(&self, method: &str, responses: &[Response]) -> Option<Value> {
+		match height_fields(method) {
+			Some([]) => self.agreed_height(responses, |value| value.as_u64()).map(Value::from),
+			Some(fields) => fields
+				.iter()
+				.map(|field| {
+					let height = self.agreed_height(responses, |value| value[*field].as_u64())?;
+					Some((field.to_string(), Value::from(height)))
+				})
+				.collect::<Option<serde_json::Map<_, _>>>()
+				.map(Value::Object),
+			None => responses
+				.iter()
+				.find(|candidate| {
+					let (weight, count) = responses
+						.iter()
+						.filter(|response| response.normalized == candidate.normalized)
+						.fold((0, 0), |(weight, count), response| {
+							(weight + response.weight, count + 1)
+						});
+					self.is_reached(weight, count)
+				})
+				.map(|response| response.value.clone()),
+		}
+	}
+
+	/// Returns the highest of the heights read by `height` that a quorum has reached, a node at
+	/// a greater height having also reached the lower ones
+	fn agreed_height(
+		&self,
+		responses: &[Response],
+		height: impl Fn(&Value) -> Option<u64>,
+	) -> Option<u64> {
+		let heights: Vec<_> = responses
+			.iter()
+			.filter_map(|response| Some((height(&response.value)?, response.weight)))
+			.collect();
+		heights
+			.iter()
+			.map(|(candidate, _)| *candidate)
+			.filter(|candidate| {
+				let (weight, count) = heights
+					.iter()
+					.filter(|(height, _)| height >= candidate)
+					.fold((0, 0), |(weight, count), (_, response_weight)| {
+						(weight + response_weight, count + 1)
+					});
+				self.is_reached(weight, count)
+			})
+			.max()
+	}
+}
+
+/// Builder for a [`QuorumProvider`]
+#[derive(Debug, Clone)]
+pub struct QuorumProviderBuilder<T> {
+	quorum: Quorum,
+	providers: Vec<WeightedProvider<T>>,
+}
+
+impl<T> Default for QuorumProviderBuilder<T> {
+	fn default() -> Self {
+		Self { quorum: Default::default(), providers: Vec::new() }
+	}
+}
+
+impl<T> QuorumProviderBuilder<T> {
+	/// Adds a provider
+	#[must_use]
+	pub fn add_provider(mut self, provider: WeightedProvider<T>) -> Self {
+		self.providers.push(provider);
+		self
+	}
+
+	/// Adds several providers
+	#[must_use]
+	pub fn add_providers(
+		mut self,
+		providers: impl IntoIterator<Item = WeightedProvider<T>>,
+	) -> Self {
+		self.providers.extend(providers);
+		self
+	}
+
+	/// Set the kind of quorum
+	#[must_use]
+	pub fn quorum(mut self, quorum: Quorum) -> Self {
+		self.quorum = quorum;
+		self
+	}
+
+	pub fn build(self) -> QuorumProvider<T> {
+		let total_weight = self.providers.iter().map(|provider| provider.weight).sum();
+		QuorumProvider { quorum: self.quorum, total_weight, providers: self.providers }
+	}
+}
+
+/// Determines when the [`QuorumProvider`] reached a quorum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quorum {
+	/// The quorum is reached when all providers return the exact value
+	All,
+	/// The quorum is reached when the majority of the providers have returned a matching value,
+	/// taking into account their weight.
+	#[default]
+	Majority,
+	/// The quorum is reached when the cumulative weight of a matching return reaches the given
+	/// percentage of the total weight.
+	Percentage(u8),
+	/// The quorum is reached when the given number of providers agree on a response
+	ProviderCount(usize),
+	/// The quorum is reached once the accumulated weight of the matching return reaches this
+	/// weight.
+	Weight(u64),
+}
+
+/// A response of one of the providers
+struct Response {
+	value: Value,
+	normalized: Value,
+	weight: u64,
+}
+
+/// Error thrown when sending a request to a [`QuorumProvider`]
+#[derive(Error, Debug)]
+pub enum QuorumError {
+	#[error("No Quorum reached. (Values: {:?}, Errors: {:?})", values, errors)]
+	/// Returned when no quorum was reached
+	NoQuorumReached {
+		/// The values returned by the providers
+		values: Vec<Value>,
+		/// The errors returned by the providers
+		errors: Vec<ProviderError>,
+	},
+	/// Thrown if the params couldn't be serialized or the response deserialized
+	#[error(transparent)]
+	SerdeJson(#[from] serde_json::Error),
+}
+
+impl crate::RpcError for QuorumError {
+	fn as_error_response(&self) -> Option<&JsonRpcError> {
+		match self {
+			// The nodes refusing the request is the only failure to report as theirs
+			QuorumError::NoQuorumReached { values, errors } if values.is_empty() =>
+				errors.iter().find_map(crate::RpcError::as_error_response),
+			_ => None,
+		}
+	}
+
+	fn as_serde_error(&self) -> Option<&serde_json::Error> {
+		match self {
+			QuorumError::SerdeJson(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<QuorumError> for ProviderError {
+	fn from(src: QuorumError) -> Self {
+		ProviderError::JsonRpcClientError(Box::new(src))
+	}
+}
+
+/// The fields holding the heights of a method returning heights of the node, if it is one
+fn height_fields(method: &str) -> Option<&'static [&'static str]> {
+	HEIGHT_METHODS
+		.iter()
+		.find(|(name, _)| *name == method)
+		.map(|(_, fields)| *fields)
+}
+
+/// Removes the fields of `response` that depend on the height of the node rather than on the
+/// requested data, or that are specific to the node.
+fn normalize_response(method: &str, response: &Value) -> Value {
+	let fields: &[&str] = match method {
+		"getblock" | "getblockheader" | "getrawtransaction" => &["confirmations", "nextblockhash"],
+		// Iterator sessions are kept by the node that executed the script
+		"invokefunction" | "invokescript" | "invokecontractverify" => &["session"],
+		_ => return response.clone(),
+	};
+	let mut response = response.clone();
+	if let Some(object) = response.as_object_mut() {
+		fields.iter().for_each(|field| {
+			object.remove(*field);
+		});
+	}
+	response
+}
+
+/// The params of a request, kept as a zero sized type when they are one so that the inner
+/// transports omit them
+#[derive(Clone, Debug)]
+pub enum QuorumParams {
+	Value(Value),
+	Zst,
+}
+
+impl QuorumParams {
+	pub(crate) fn new<T: Serialize>(params: T) -> Result<Self, serde_json::Error> {
+		if std::mem::size_of::<T>() == 0 {
+			Ok(QuorumParams::Zst)
+		} else {
+			Ok(QuorumParams::Value(serde_json::to_value(params)?))
+		}
+	}
+}
+
+/// Wrapper around a [`JsonRpcClient`] that returns untyped responses, so that transports of
+/// different types can be combined in a [`QuorumProvider`]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JsonRpcClientWrapper: Send + Sync + Debug {
+	/// Make a request, as [`JsonRpcClient::fetch`]
+	async fn fetch(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: JsonRpcClient> JsonRpcClientWrapper for C {
+	async fn fetch(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError> {
+		match params {
+			QuorumParams::Value(params) =>
+				JsonRpcClient::fetch(self, method, params).await.map_err(Into::into),
+			QuorumParams::Zst => JsonRpcClient::fetch(self, method, ()).await.map_err(Into::into),
+		}
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for Box<dyn JsonRpcClientWrapper> {
+	type Error = ProviderError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let params = QuorumParams::new(params)?;
+		let response = JsonRpcClientWrapper::fetch(&**self, method, params).await?;
+		Ok(serde_json::from_value(response)?)
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C> JsonRpcClient for QuorumProvider<C>
+where
+	C: JsonRpcClientWrapper,
+{
+	type Error = QuorumError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		// Nodes a block apart have different best blocks
+		if method == "getbestblockhash" {
+			let count: u64 = JsonRpcClient::fetch(self, "getblockcount", ()).await?;
+			return JsonRpcClient::fetch(self, "getblockhash", [count.saturating_sub(1)]).await
+		}
+
+		let params = QuorumParams::new(params)?;
+		let mut requests = self
+			.providers
+			.iter()
+			.map(|provider| {
+				let params = params.clone();
+				async move {
+					(
+						provider.weight,
+						JsonRpcClientWrapper::fetch(&provider.inner, method, params).await,
+					)
+				}
+			})
+			.collect::<FuturesUnordered<_>>();
+
+		let mut responses = Vec::new();
+		let mut errors = Vec::new();
+		while let Some((weight, result)) = requests.next().await {
+			match result {
+				Ok(value) => {
+					let normalized = normalize_response(method, &value);
+					responses.push(Response { value, normalized, weight });
+					// The highest height agreed on may still rise with the pending responses
+					if height_fields(method).is_some() {
+						continue
+					}
+					if let Some(value) = self.agreed_response(method, &responses) {
+						return Ok(serde_json::from_value(value)?)
+					}
+				},
+				Err(err) => errors.push(err),
+			}
+		}
+		match self.agreed_response(method, &responses) {
+			Some(value) => Ok(serde_json::from_value(value)?),
+			None => Err(QuorumError::NoQuorumReached {
+				values: responses.into_iter().map(|response| response.value).collect(),
+				errors,
+			}),
+		}
+	}
+}
+
+/// A provider with a weight, by which its responses count towards the quorum
+#[derive(Debug, Clone)]
+pub struct WeightedProvider<T> {
+	inner: T,
+	weight: u64,
+}
+
+impl<T> WeightedProvider<T> {
+	/// Create a `WeightedProvider` with weight `1`
+	pub fn new(inner: T) -> Self {
+		Self::with_weight(inner, 1)
+	}
+
+	/// Create a `WeightedProvider` with a custom weight
+	pub fn with_weight(inner: T, weight: u64) -> Self {
+		assert!(weight > 0);
+		Self { inner, weight }
+	}
+
+	/// The weight of the provider
+	pub fn weight(&self) -> u64 {
+		self.weight
+	}
+
+	/// The wrapped provider
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use crate::{MockProvider, MockResponse, RpcError};
+	use serde_json::json;
+
+	/// A provider per response, each answering once with its response.
+	fn quorum(quorum: Quorum, responses: &[(u64, MockResponse)]) -> QuorumProvider<MockProvider> {
+		let providers = responses.iter().map(|(weight, response)| {
+			let mock = MockProvider::new();
+			mock.push_response(response.clone());
+			WeightedProvider::with_weight(mock, *weight)
+		});
+		QuorumProvider::new(quorum, providers)
+	}
+
+	async fn request<T, R>(
+		kind: Quorum,
+		responses: &[(u64, MockResponse)],
+		method: &str,
+		params: T,
+	) -> Result<R, QuorumError>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		JsonRpcClient::fetch(&quorum(kind, responses), method, params).await
+	}
+
+	fn value(value: Value) -> (u64, MockResponse) {
+		(1, MockResponse::Value(value))
+	}
+
+	fn error() -> (u64, MockResponse) {
+		let error = JsonRpcError { code: -100, message: "Unknown block".to_string(), data: None };
+		(1, MockResponse::Error(error))
+	}
+
+	#[tokio::test]
+	async fn test_quorum_kinds() {
+		let responses = [value(json!("0x01")), value(json!("0x01")), value(json!("0x02"))];
+		let hash: Result<String, _> =
+			request(Quorum::Majority, &responses, "getblockhash", [10]).await;
+		assert_eq!(hash.unwrap(), "0x01");
+		let hash: Result<String, _> = request(Quorum::All, &responses, "getblockhash", [10]).await;
+		assert!(hash.is_err());
+		let hash: Result<String, _> =
+			request(Quorum::Percentage(66), &responses, "getblockhash", [10]).await;
+		assert!(hash.is_ok());
+		let hash: Result<String, _> =
+			request(Quorum::Percentage(67), &responses, "getblockhash", [10]).await;
+		assert!(hash.is_err());
+		let hash: Result<String, _> =
+			request(Quorum::ProviderCount(2), &responses, "getblockhash", [10]).await;
+		assert!(hash.is_ok());
+
+		// A heavy provider outweighs the others
+		let weighted = [value(json!("0x01")), value(json!("0x01")), (3, responses[2].1.clone())];
+		let hash: Result<String, _> =
+			request(Quorum::Weight(3), &weighted, "getblockhash", [10]).await;
+		assert_eq!(hash.unwrap(), "0x02");
+		let hash: Result<String, _> =
+			request(Quorum::Majority, &weighted, "getblockhash", [10]).await;
+		assert_eq!(hash.unwrap(), "0x02");
+	}
+
+	#[tokio::test]
+	async fn test_no_quorum() {
+		let responses = [value(json!("0x01")), value(json!("0x02")), value(json!("0x03"))];
+		match request::<_, String>(Quorum::Majority, &responses, "getblockhash", [10]).await {
+			Err(QuorumError::NoQuorumReached { values, errors }) => {
+				assert_eq!(values.len(), 3);
+				assert!(errors.is_empty());
+			},
+			result => panic!("unexpected result: {result:?}"),
+		}
+
+		let responses = [error(), error(), value(json!("0x01"))];
+		let err = request::<_, String>(Quorum::Majority, &responses, "getblockhash", [10])
+			.await
+			.unwrap_err();
+		assert!(matches!(&err, QuorumError::NoQuorumReached { errors, .. } if errors.len() == 2));
+		assert!(err.as_error_response().is_none());
+
+		// When no node answers, their error is reported
+		let responses = [error(), error()];
+		let err = request::<_, String>(Quorum::Majority, &responses, "getblockhash", [10])
+			.await
+			.unwrap_err();
+		assert_eq!(err.as_error_response().unwrap().code, -100);
+	}
+
+	#[tokio::test]
+	async fn test_block_count_is_normalized() {
+		let responses = [value(json!(100)), value(json!(102)), value(json!(101))];
+		let count: Result<u32, _> =
+			request(Quorum::Majority, &responses, "getblockcount", ()).await;
+		assert_eq!(count.unwrap(), 101);
+		let count: Result<u32, _> = request(Quorum::All, &responses, "getblockcount", ()).await;
+		assert_eq!(count.unwrap(), 100);
+
+		let provider = quorum(Quorum::Majority, &responses);
+		let _: u32 = JsonRpcClient::fetch(&provider, "getblockcount", ()).await.unwrap();
+		for provider in provider.providers() {
+			provider.inner().assert_request("getblockcount", ()).unwrap();
+		}
+	}
+
+	#[tokio::test]
+	async fn test_best_block_hash_at_divergent_heights() {
+		// The nodes answer the block count, then the hash of the block at the agreed height
+		let providers = [100, 101, 102].map(|count| {
+			let mock = MockProvider::new();
+			mock.push_response(MockResponse::Value(json!("0x64")));
+			mock.push_response(MockResponse::Value(json!(count)));
+			WeightedProvider::new(mock)
+		});
+		let provider = QuorumProvider::new(Quorum::Majority, providers);
+		let hash: String = JsonRpcClient::fetch(&provider, "getbestblockhash", ()).await.unwrap();
+		assert_eq!(hash, "0x64");
+		for provider in provider.providers() {
+			provider.inner().assert_request("getblockcount", ()).unwrap();
+			provider.inner().assert_request("getblockhash", [100]).unwrap();
+		}
+	}
+
+	#[tokio::test]
+	async fn test_state_height_at_divergent_heights() {
+		let height = |local: u32, validated: u32| {
+			value(json!({ "localrootindex": local, "validatedrootindex": validated }))
+		};
+		let responses = [height(100, 98), height(102, 99), height(101, 99)];
+		let heights: Value =
+			request(Quorum::Majority, &responses, "getstateheight", ()).await.unwrap();
+		assert_eq!(heights, json!({ "localrootindex": 101, "validatedrootindex": 99 }));
+		let heights: Value = request(Quorum::All, &responses, "getstateheight", ()).await.unwrap();
+		assert_eq!(heights, json!({ "localrootindex": 100, "validatedrootindex": 98 }));
+	}
+
+	#[tokio::test]
+	async fn test_block_is_normalized() {
+		let block = |confirmations: u32, next: Option<&str>| {
+			let mut block = json!({ "hash": "0x01", "index": 10, "confirmations": confirmations });
+			if let Some(next) = next {
+				block["nextblockhash"] = json!(next);
+			}
+			value(block)
+		};
+		let responses = [block(1, None), block(2, Some("0x02")), block(3, Some("0x02"))];
+		let block: Result<Value, _> = request(Quorum::All, &responses, "getblock", [10, 1]).await;
+		assert_eq!(block.unwrap()["index"], 10);
+
+		let responses = [value(json!({ "hash": "0x01" })), value(json!({ "hash": "0x02" }))];
+		let block: Result<Value, _> = request(Quorum::All, &responses, "getblock", [10, 1]).await;
+		assert!(block.is_err());
+	}
+}
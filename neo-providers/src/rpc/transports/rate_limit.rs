@@ -0,0 +1,474 @@
+//! A [JsonRpcClient] wrapper that keeps the requests to a node under its rate limits.
+
+use crate::{BatchClient, JsonRpcClient};
+use async_trait::async_trait;
+use futures_util::future::poll_fn;
+use instant::{Duration, Instant};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+	collections::{HashMap, VecDeque},
+	fmt::Debug,
+	sync::Mutex,
+	task::{Context, Poll, Waker},
+};
+
+/// A client sending requests to its inner client no faster than the node allows.
+///
+/// Requests take tokens from a bucket refilled at `requests_per_second`, and holding at most
+/// `burst` tokens. A request costs one token unless its method was given another weight, e.g. for
+/// `invokescript` or `getblock` with transactions, which public nodes throttle harder. At most
+/// `max_in_flight` requests are sent at once.
+///
+/// Requests wait in a single queue and are sent in the order they were made, whatever the task
+/// they were made from, so that a task sending many requests doesn't starve the others. Unlike
+/// [`RetryClient`](crate::RetryClient), which backs off once the node rate limited a request, this
+/// client doesn't exceed the limits in the first place.
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{Http, Provider, RateLimitedClient};
+/// use std::str::FromStr;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let http = Http::from_str("http://seed1.neo.org:10332")?;
+/// let client = RateLimitedClient::builder()
+/// 	.requests_per_second(20)
+/// 	.max_in_flight(4)
+/// 	.method_weight("invokescript", 5)
+/// 	.build(http);
+/// let provider = Provider::new(client);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RateLimitedClient<T> {
+	inner: T,
+	requests_per_second: f64,
+	burst: f64,
+	max_in_flight: usize,
+	weights: HashMap<String, u32>,
+	state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+	tokens: f64,
+	refilled_at: Instant,
+	in_flight: usize,
+	queue: VecDeque<Waiter>,
+	next_ticket: u64,
+	requests: u64,
+	waited: Duration,
+}
+
+#[derive(Debug)]
+struct Waiter {
+	ticket: u64,
+	/// Whether the state changed since the waiter last checked whether it can be sent
+	woken: bool,
+	waker: Option<Waker>,
+}
+
+impl State {
+	fn refill(&mut self, requests_per_second: f64, burst: f64) {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * requests_per_second).min(burst);
+		self.refilled_at = now;
+	}
+
+	fn waiter(&mut self, ticket: u64) -> Option<&mut Waiter> {
+		self.queue.iter_mut().find(|waiter| waiter.ticket == ticket)
+	}
+
+	/// Wakes the request at the front of the queue, which may be sent now
+	fn wake_front(&mut self) {
+		if let Some(waiter) = self.queue.front_mut() {
+			waiter.woken = true;
+			if let Some(waker) = waiter.waker.take() {
+				waker.wake();
+			}
+		}
+	}
+}
+
+/// Removes a request from the queue if it's dropped while waiting.
+struct Ticket<'a> {
+	state: &'a Mutex<State>,
+	ticket: u64,
+}
+
+impl Drop for Ticket<'_> {
+	fn drop(&mut self) {
+		let mut state = self.state.lock().unwrap();
+		if let Some(index) = state.queue.iter().position(|waiter| waiter.ticket == self.ticket) {
+			state.queue.remove(index);
+			if index == 0 {
+				state.wake_front();
+			}
+		}
+	}
+}
+
+/// A request being sent, released when it's dropped.
+struct Permit<'a> {
+	state: &'a Mutex<State>,
+}
+
+impl Drop for Permit<'_> {
+	fn drop(&mut self) {
+		let mut state = self.state.lock().unwrap();
+		state.in_flight -= 1;
+		state.wake_front();
+	}
+}
+
+/// A snapshot of the requests of a [`RateLimitedClient`], e.g. for metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitStats {
+	/// Requests sent to the inner client
+	pub requests: u64,
+	/// Requests being sent
+	pub in_flight: usize,
+	/// Requests waiting to be sent
+	pub queued: usize,
+	/// Tokens left in the bucket
+	pub tokens: f64,
+	/// Total time the sent requests waited in the queue
+	pub waited: Duration,
+}
+
+impl<T> RateLimitedClient<T> {
+	/// Creates a client sending at most `requests_per_second` to `inner`, with the other limits
+	/// of [`RateLimitedClientBuilder`]
+	pub fn new(inner: T, requests_per_second: u32) -> Self {
+		RateLimitedClientBuilder::default()
+			.requests_per_second(requests_per_second)
+			.build(inner)
+	}
+
+	/// Convenience method for creating a `RateLimitedClientBuilder`
+	pub fn builder() -> RateLimitedClientBuilder {
+		RateLimitedClientBuilder::default()
+	}
+
+	/// The client the requests are sent to
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+
+	/// The requests sent and waiting so far
+	pub fn stats(&self) -> RateLimitStats {
+		let mut state = self.state.lock().unwrap();
+		state.refill(self.requests_per_second, self.burst);
+		RateLimitStats {
+			requests: state.requests,
+			in_flight: state.in_flight,
+			queued: state.queue.len(),
+			tokens: state.tokens,
+			waited: state.waited,
+		}
+	}
+
+	/// The tokens a request of `method` takes, capped to the bucket so that it can be sent.
+	fn weight(&self, method: &str) -> f64 {
+		f64::from(self.weights.get(method).copied().unwrap_or(1)).min(self.burst)
+	}
+
+	/// Waits until the request is at the front of the queue, there are enough tokens for it and
+	/// fewer than `max_in_flight` requests are being sent.
+	async fn acquire(&self, weight: f64) -> Permit<'_> {
+		let queued_at = Instant::now();
+		let ticket = {
+			let mut state = self.state.lock().unwrap();
+			let ticket = state.next_ticket;
+			state.next_ticket += 1;
+			state.queue.push_back(Waiter { ticket, woken: false, waker: None });
+			ticket
+		};
+		let _ticket = Ticket { state: &self.state, ticket };
+
+		loop {
+			let wait = {
+				let mut state = self.state.lock().unwrap();
+				state.refill(self.requests_per_second, self.burst);
+				let front = state.queue.front().is_some_and(|waiter| waiter.ticket == ticket);
+				let sendable = front && state.in_flight < self.max_in_flight;
+				if sendable && state.tokens >= weight {
+					state.tokens -= weight;
+					state.in_flight += 1;
+					state.requests += 1;
+					state.waited += queued_at.elapsed();
+					state.queue.pop_front();
+					state.wake_front();
+					return Permit { state: &self.state }
+				}
+				if let Some(waiter) = state.waiter(ticket) {
+					waiter.woken = false;
+				}
+				// Only the front request waits for tokens, the others wait for their turn
+				sendable.then(|| {
+					Duration::from_secs_f64((weight - state.tokens) / self.requests_per_second)
+				})
+			};
+			match wait {
+				Some(wait) => sleep(wait).await,
+				None => poll_fn(|cx| self.poll_woken(ticket, cx)).await,
+			}
+		}
+	}
+
+	fn poll_woken(&self, ticket: u64, cx: &mut Context<'_>) -> Poll<()> {
+		let mut state = self.state.lock().unwrap();
+		match state.waiter(ticket) {
+			Some(waiter) if !waiter.woken => {
+				waiter.waker = Some(cx.waker().clone());
+				Poll::Pending
+			},
+			_ => Poll::Ready(()),
+		}
+	}
+}
+
+async fn sleep(duration: Duration) {
+	#[cfg(target_arch = "wasm32")]
+	futures_timer::Delay::new(duration).await;
+
+	#[cfg(not(target_arch = "wasm32"))]
+	tokio::time::sleep(duration).await;
+}
+
+/// Builder for a [`RateLimitedClient`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RateLimitedClientBuilder {
+	requests_per_second: u32,
+	burst: Option<u32>,
+	max_in_flight: usize,
+	weights: HashMap<String, u32>,
+}
+
+impl Default for RateLimitedClientBuilder {
+	fn default() -> Self {
+		Self { requests_per_second: 10, burst: None, max_in_flight: 10, weights: HashMap::new() }
+	}
+}
+
+impl RateLimitedClientBuilder {
+	/// Sets how many tokens are added to the bucket per second.
+	pub fn requests_per_second(mut self, requests_per_second: u32) -> Self {
+		self.requests_per_second = requests_per_second.max(1);
+		self
+	}
+
+	/// Sets how many tokens the bucket holds, i.e. how many requests can be sent at once after
+	/// the client was idle. Defaults to the requests per second.
+	pub fn burst(mut self, burst: u32) -> Self {
+		self.burst = Some(burst.max(1));
+		self
+	}
+
+	/// Sets how many requests may be sent at once.
+	pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+		self.max_in_flight = max_in_flight.max(1);
+		self
+	}
+
+	/// Sets how many tokens a request of `method` takes, instead of one.
+	pub fn method_weight(mut self, method: impl Into<String>, weight: u32) -> Self {
+		self.weights.insert(method.into(), weight);
+		self
+	}
+
+	/// Creates the client over `inner`, with a full bucket.
+	pub fn build<T>(self, inner: T) -> RateLimitedClient<T> {
+		let burst = f64::from(self.burst.unwrap_or(self.requests_per_second));
+		RateLimitedClient {
+			inner,
+			requests_per_second: f64::from(self.requests_per_second),
+			burst,
+			max_in_flight: self.max_in_flight,
+			weights: self.weights,
+			state: Mutex::new(State {
+				tokens: burst,
+				refilled_at: Instant::now(),
+				in_flight: 0,
+				queue: VecDeque::new(),
+				next_ticket: 0,
+				requests: 0,
+				waited: Duration::ZERO,
+			}),
+		}
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: JsonRpcClient> JsonRpcClient for RateLimitedClient<C> {
+	type Error = C::Error;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let _permit = self.acquire(self.weight(method)).await;
+		self.inner.fetch(method, params).await
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: BatchClient> BatchClient for RateLimitedClient<C> {
+	/// Sends the batch as a single request weighing as much as its calls together, capped to the
+	/// bucket.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+		let weight = calls.iter().map(|(method, _)| self.weight(method)).sum::<f64>();
+		let _permit = self.acquire(weight.min(self.burst)).await;
+		self.inner.fetch_batch(calls).await
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use crate::{MockError, MockProvider, MockResponse};
+	use futures_util::future::join_all;
+	use serde_json::json;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	/// Answers every request with its params after a while, recording how many requests it was
+	/// sent at once and the params in the order the requests arrived.
+	#[derive(Debug, Default)]
+	struct SlowClient {
+		in_flight: AtomicUsize,
+		max_in_flight: AtomicUsize,
+		calls: Mutex<Vec<Value>>,
+	}
+
+	#[async_trait]
+	impl JsonRpcClient for SlowClient {
+		type Error = MockError;
+
+		async fn fetch<T, R>(&self, _method: &str, params: T) -> Result<R, MockError>
+		where
+			T: Debug + Serialize + Send + Sync,
+			R: DeserializeOwned + Send,
+		{
+			let params = serde_json::to_value(params)?;
+			self.calls.lock().unwrap().push(params.clone());
+			let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+			self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			self.in_flight.fetch_sub(1, Ordering::SeqCst);
+			Ok(serde_json::from_value(params)?)
+		}
+	}
+
+	#[async_trait]
+	impl BatchClient for SlowClient {
+		async fn fetch_batch(
+			&self,
+			calls: &[(String, Value)],
+		) -> Result<Vec<Result<Value, MockError>>, MockError> {
+			Ok(calls.iter().map(|(_, params)| Ok(params.clone())).collect())
+		}
+	}
+
+	fn mocked(
+		builder: RateLimitedClientBuilder,
+		responses: usize,
+	) -> RateLimitedClient<MockProvider> {
+		let mock = MockProvider::new();
+		(0..responses).for_each(|_| mock.push_response(MockResponse::Value(json!(100))));
+		builder.build(mock)
+	}
+
+	#[tokio::test]
+	async fn test_requests_per_second() {
+		let client = mocked(RateLimitedClient::builder().requests_per_second(20).burst(2), 5);
+		let start = Instant::now();
+		for _ in 0..5 {
+			let _: u32 = client.fetch("getblockcount", ()).await.unwrap();
+		}
+		// The burst is sent at once, the 3 other requests wait for a token each
+		assert!(start.elapsed() >= Duration::from_millis(140));
+		let stats = client.stats();
+		assert_eq!((stats.requests, stats.in_flight, stats.queued), (5, 0, 0));
+		assert!(stats.waited >= Duration::from_millis(140));
+	}
+
+	#[tokio::test]
+	async fn test_method_weights() {
+		let client = mocked(
+			RateLimitedClient::builder()
+				.requests_per_second(20)
+				.method_weight("invokescript", 4)
+				.method_weight("getblock", 100),
+			3,
+		);
+		let _: u32 = client.fetch("invokescript", ["EMAMCGdldEhlaWdodA=="]).await.unwrap();
+		assert!(client.stats().tokens < 17.0);
+
+		// A weight above the burst takes the whole bucket, instead of never being sent
+		let start = Instant::now();
+		let _: u32 = client.fetch("getblock", [1, 1]).await.unwrap();
+		assert!(start.elapsed() >= Duration::from_millis(150));
+		assert!(client.stats().tokens < 1.0);
+	}
+
+	#[tokio::test]
+	async fn test_batch_weight() {
+		let client = RateLimitedClient::builder()
+			.requests_per_second(20)
+			.method_weight("invokescript", 4)
+			.build(SlowClient::default());
+		let calls = [
+			("invokescript".to_string(), json!(["EMAMCGdldEhlaWdodA=="])),
+			("getblockcount".to_string(), json!([])),
+		];
+		let results = client.fetch_batch(&calls).await.unwrap();
+		assert_eq!(results.len(), 2);
+		// The batch is a single request taking the tokens of all its calls
+		let stats = client.stats();
+		assert_eq!(stats.requests, 1);
+		assert!(stats.tokens < 16.0);
+	}
+
+	#[tokio::test]
+	async fn test_max_in_flight() {
+		let client = RateLimitedClient::builder()
+			.requests_per_second(1000)
+			.max_in_flight(2)
+			.build(SlowClient::default());
+		let results: Vec<Result<Value, _>> =
+			join_all((0..6).map(|index| client.fetch("getblockhash", [index]))).await;
+		assert!(results.iter().all(Result::is_ok));
+		assert_eq!(client.inner().max_in_flight.load(Ordering::SeqCst), 2);
+		assert_eq!(client.stats().in_flight, 0);
+	}
+
+	#[tokio::test]
+	async fn test_requests_are_sent_in_order() {
+		let client = RateLimitedClient::builder()
+			.requests_per_second(50)
+			.burst(1)
+			.max_in_flight(1)
+			.build(SlowClient::default());
+		let _: Vec<Result<Value, _>> =
+			join_all((0..5).map(|index| client.fetch("getblockhash", [index]))).await;
+		let calls = client.inner().calls.lock().unwrap().clone();
+		assert_eq!(calls, (0..5).map(|index| json!([index])).collect::<Vec<_>>());
+
+		// A request dropped while waiting doesn't block the queue
+		let waiting = client.fetch::<_, Value>("getblockhash", [5]);
+		assert!(tokio::time::timeout(Duration::from_millis(5), waiting).await.is_err());
+		let _: Value = client.fetch("getblockhash", [6]).await.unwrap();
+		assert_eq!(client.stats().queued, 0);
+	}
+}
@@ -0,0 +1,515 @@
+//! [JsonRpcClient] implementations recording the responses of a node to a fixture file and
+//! replaying them, so that tests run offline and deterministically.
+
+use super::common::JsonRpcError;
+use crate::{errors::ProviderError, BatchClient, JsonRpcClient, RpcError};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+	fmt::Debug,
+	fs,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+use thiserror::Error;
+
+/// A request and the response of the node to it, as stored in a fixture file.
+///
+/// A fixture file is a JSON array of interactions, like
+/// `{ "method": "getblockcount", "params": [], "result": 2048 }`, or with an `error` object
+/// instead of the `result` for JSON-RPC error responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+	/// The method of the request
+	pub method: String,
+	/// The params of the request, `[]` for requests without params
+	pub params: Value,
+	/// The response of the node
+	#[serde(flatten)]
+	pub response: RecordedResponse,
+}
+
+/// The response to a recorded request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordedResponse {
+	/// The result of a successful request
+	Result(Value),
+	/// The JSON-RPC error returned by the node
+	Error(JsonRpcError),
+}
+
+/// Serializes `params` the way they are recorded, so that requests without params match `[]`.
+fn params_value<T: Serialize>(params: &T) -> serde_json::Result<Value> {
+	Ok(match serde_json::to_value(params)? {
+		Value::Null => Value::Array(Vec::new()),
+		params => params,
+	})
+}
+
+/// A client recording the requests sent to its inner client and the responses to them in a
+/// fixture file, to be replayed by a [`ReplayClient`].
+///
+/// The file is rewritten after every response, so that it's complete even if the recording is
+/// interrupted. Results and JSON-RPC error responses are recorded. Requests that failed to reach
+/// the node aren't, as they can't be replayed. The calls of a batch are recorded as separate
+/// interactions.
+///
+/// The file is written with blocking I/O on the task making the request, which is fine for
+/// recording fixtures but not for serving requests.
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{Http, Middleware, Provider, RecordingClient};
+/// use std::str::FromStr;
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let http = Http::from_str("http://seed1t5.neo.org:20332")?;
+/// let provider = Provider::new(RecordingClient::new(http, "tests/fixtures/block_count.json"));
+/// provider.get_block_count().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecordingClient<T> {
+	inner: T,
+	path: PathBuf,
+	interactions: Mutex<Vec<Interaction>>,
+	// The number of interactions in the file, so that a slower write doesn't replace a more
+	// complete one
+	written: Mutex<usize>,
+}
+
+impl<T> RecordingClient<T> {
+	/// Creates a client recording the requests sent to `inner` in the file at `path`, which is
+	/// replaced by the first response.
+	pub fn new(inner: T, path: impl Into<PathBuf>) -> Self {
+		Self {
+			inner,
+			path: path.into(),
+			interactions: Mutex::new(Vec::new()),
+			written: Mutex::new(0),
+		}
+	}
+
+	/// The client the requests are sent to
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+
+	/// The file the interactions are written to
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// The interactions recorded so far
+	pub fn interactions(&self) -> Vec<Interaction> {
+		self.interactions.lock().unwrap().clone()
+	}
+
+	/// Adds `interactions` and rewrites the file. The file is written without holding the lock
+	/// on the interactions, so that other requests can be recorded meanwhile.
+	fn record(
+		&self,
+		interactions: impl IntoIterator<Item = Interaction>,
+	) -> Result<(), FixtureError> {
+		let (count, json) = {
+			let mut recorded = self.interactions.lock().unwrap();
+			recorded.extend(interactions);
+			(recorded.len(), serde_json::to_vec_pretty(&*recorded)?)
+		};
+		let mut written = self.written.lock().unwrap();
+		if count > *written {
+			fs::write(&self.path, json)?;
+			*written = count;
+		}
+		Ok(())
+	}
+
+	/// The interaction to record for a response, if it came from the node.
+	fn interaction<E: RpcError>(
+		method: &str,
+		params: Value,
+		result: &Result<Value, E>,
+	) -> Option<Interaction> {
+		let response = match result {
+			Ok(value) => RecordedResponse::Result(value.clone()),
+			Err(err) => RecordedResponse::Error(err.as_error_response()?.clone()),
+		};
+		Some(Interaction { method: method.to_string(), params, response })
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: JsonRpcClient + 'static> JsonRpcClient for RecordingClient<C> {
+	type Error = RecordingClientError<C>;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let recorded = params_value(&params).map_err(FixtureError::from)?;
+		let result = self.inner.fetch::<_, Value>(method, params).await;
+		self.record(Self::interaction(method, recorded, &result))?;
+		let value = result.map_err(RecordingClientError::Client)?;
+		Ok(serde_json::from_value(value).map_err(FixtureError::from)?)
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: BatchClient + 'static> BatchClient for RecordingClient<C> {
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+		let results = self.inner.fetch_batch(calls).await.map_err(RecordingClientError::Client)?;
+		let mut interactions = Vec::new();
+		for ((method, params), result) in calls.iter().zip(&results) {
+			let params = params_value(params).map_err(FixtureError::from)?;
+			interactions.extend(Self::interaction(method, params, result));
+		}
+		self.record(interactions)?;
+		Ok(results
+			.into_iter()
+			.map(|result| result.map_err(RecordingClientError::Client))
+			.collect())
+	}
+}
+
+/// A client answering requests with the responses recorded by a [`RecordingClient`].
+///
+/// A request is answered with the first interaction of the fixture with the same method and
+/// params that wasn't replayed yet, so that repeated requests, like polling `getblockcount`,
+/// get the responses in the order they were recorded. A request that wasn't recorded fails with
+/// [`ReplayClientError::UnexpectedCall`].
+///
+/// # Example
+///
+/// ```no_run
+/// use neo_providers::{Middleware, Provider, ReplayClient};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = Provider::new(ReplayClient::load("tests/fixtures/block_count.json")?);
+/// provider.get_block_count().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ReplayClient {
+	interactions: Vec<Interaction>,
+	replayed: Mutex<Vec<bool>>,
+}
+
+impl ReplayClient {
+	/// Creates a client answering with `interactions`
+	pub fn new(interactions: Vec<Interaction>) -> Self {
+		let replayed = Mutex::new(vec![false; interactions.len()]);
+		Self { interactions, replayed }
+	}
+
+	/// Reads the interactions of the fixture file at `path`
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, FixtureError> {
+		let interactions = serde_json::from_slice(&fs::read(path)?)?;
+		Ok(Self::new(interactions))
+	}
+
+	/// Returns the response of the first interaction with `method` and `params` that wasn't
+	/// replayed yet
+	fn replay(&self, method: &str, params: Value) -> Result<&RecordedResponse, ReplayClientError> {
+		let mut replayed = self.replayed.lock().unwrap();
+		let index = self
+			.interactions
+			.iter()
+			.zip(replayed.iter())
+			.position(|(interaction, replayed)| {
+				!replayed && interaction.method == method && interaction.params == params
+			})
+			.ok_or_else(|| ReplayClientError::UnexpectedCall {
+				method: method.to_string(),
+				params,
+			})?;
+		replayed[index] = true;
+		Ok(&self.interactions[index].response)
+	}
+
+	/// The interactions that weren't replayed yet, e.g. to check that a test sent every recorded
+	/// request
+	pub fn remaining(&self) -> Vec<&Interaction> {
+		let replayed = self.replayed.lock().unwrap();
+		self.interactions
+			.iter()
+			.zip(replayed.iter())
+			.filter(|(_, replayed)| !**replayed)
+			.map(|(interaction, _)| interaction)
+			.collect()
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for ReplayClient {
+	type Error = ReplayClientError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		match self.replay(method, params_value(&params)?)? {
+			RecordedResponse::Result(value) => Ok(R::deserialize(value)?),
+			RecordedResponse::Error(err) => Err(ReplayClientError::JsonRpcError(err.clone())),
+		}
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl BatchClient for ReplayClient {
+	/// Answers each call of the batch like a request, so a call that wasn't recorded fails on its
+	/// own.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+		let mut results = Vec::with_capacity(calls.len());
+		for (method, params) in calls {
+			let result = match self.replay(method, params_value(params)?) {
+				Ok(RecordedResponse::Result(value)) => Ok(value.clone()),
+				Ok(RecordedResponse::Error(err)) =>
+					Err(ReplayClientError::JsonRpcError(err.clone())),
+				Err(err) => Err(err),
+			};
+			results.push(result);
+		}
+		Ok(results)
+	}
+}
+
+/// Error thrown when reading or writing a fixture file
+#[derive(Error, Debug)]
+pub enum FixtureError {
+	/// Thrown if the file couldn't be read or written
+	#[error(transparent)]
+	Io(#[from] std::io::Error),
+	/// Thrown if the interactions couldn't be (de)serialized
+	#[error(transparent)]
+	SerdeJson(#[from] serde_json::Error),
+}
+
+/// Error thrown when sending a request to a [`RecordingClient`]
+#[derive(Error, Debug)]
+pub enum RecordingClientError<C>
+where
+	C: JsonRpcClient,
+{
+	/// Thrown if the inner client failed
+	#[error(transparent)]
+	Client(C::Error),
+	/// Thrown if the interaction couldn't be recorded or the response deserialized
+	#[error(transparent)]
+	Fixture(#[from] FixtureError),
+}
+
+impl<C: JsonRpcClient> RpcError for RecordingClientError<C> {
+	fn as_error_response(&self) -> Option<&JsonRpcError> {
+		match self {
+			RecordingClientError::Client(err) => err.as_error_response(),
+			RecordingClientError::Fixture(_) => None,
+		}
+	}
+
+	fn as_serde_error(&self) -> Option<&serde_json::Error> {
+		match self {
+			RecordingClientError::Client(err) => err.as_serde_error(),
+			RecordingClientError::Fixture(FixtureError::SerdeJson(err)) => Some(err),
+			RecordingClientError::Fixture(_) => None,
+		}
+	}
+}
+
+impl<C: JsonRpcClient + 'static> From<RecordingClientError<C>> for ProviderError {
+	fn from(src: RecordingClientError<C>) -> Self {
+		ProviderError::JsonRpcClientError(Box::new(src))
+	}
+}
+
+/// Error thrown when sending a request to a [`ReplayClient`]
+#[derive(Error, Debug)]
+pub enum ReplayClientError {
+	/// Thrown if the fixture has no interaction left for the request
+	#[error("unexpected call to {method} with params {params}")]
+	UnexpectedCall {
+		/// The method of the request
+		method: String,
+		/// The params of the request
+		params: Value,
+	},
+	/// The recorded JSON-RPC error response
+	#[error(transparent)]
+	JsonRpcError(JsonRpcError),
+	/// Thrown if the params couldn't be serialized or the result deserialized
+	#[error(transparent)]
+	SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for ReplayClientError {
+	fn as_error_response(&self) -> Option<&JsonRpcError> {
+		match self {
+			ReplayClientError::JsonRpcError(err) => Some(err),
+			_ => None,
+		}
+	}
+
+	fn as_serde_error(&self) -> Option<&serde_json::Error> {
+		match self {
+			ReplayClientError::SerdeJson(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<ReplayClientError> for ProviderError {
+	fn from(src: ReplayClientError) -> Self {
+		ProviderError::JsonRpcClientError(Box::new(src))
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use crate::{MockError, MockProvider, MockResponse};
+	use serde_json::json;
+
+	fn fixture(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("neo-providers-{}-{name}.json", std::process::id()))
+	}
+
+	#[tokio::test]
+	async fn test_record_and_replay() {
+		let path = fixture("record");
+		let mock = MockProvider::new();
+		let error = JsonRpcError { code: -101, message: "Unknown block".to_string(), data: None };
+		mock.push_response(MockResponse::Error(error));
+		mock.push_response(MockResponse::Value(json!("0x01")));
+		mock.push_response(MockResponse::Value(json!(2048)));
+		let client = RecordingClient::new(mock, &path);
+
+		let count: u32 = client.fetch("getblockcount", ()).await.unwrap();
+		let hash: String = client.fetch("getblockhash", [1]).await.unwrap();
+		let err = client.fetch::<_, Value>("getblockhash", [4096]).await.unwrap_err();
+		assert_eq!(err.as_error_response().unwrap().code, -101);
+		// Failing to reach the node isn't recorded
+		assert!(client.fetch::<_, Value>("getblockcount", ()).await.is_err());
+
+		let recorded: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+		assert_eq!(
+			recorded,
+			json!([
+				{ "method": "getblockcount", "params": [], "result": 2048 },
+				{ "method": "getblockhash", "params": [1], "result": "0x01" },
+				{
+					"method": "getblockhash",
+					"params": [4096],
+					"error": { "code": -101, "message": "Unknown block" }
+				},
+			])
+		);
+
+		let client = ReplayClient::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+		let err = client.fetch::<_, Value>("getblockhash", [4096]).await.unwrap_err();
+		assert_eq!(err.as_error_response().unwrap().code, -101);
+		assert_eq!(client.fetch::<_, String>("getblockhash", [1]).await.unwrap(), hash);
+		assert_eq!(client.fetch::<_, u32>("getblockcount", ()).await.unwrap(), count);
+		assert!(client.remaining().is_empty());
+	}
+
+	/// Answers batches with the responses of the mock, one per call.
+	#[derive(Debug, Default)]
+	struct BatchMock(MockProvider);
+
+	#[async_trait]
+	impl JsonRpcClient for BatchMock {
+		type Error = MockError;
+
+		async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, MockError>
+		where
+			T: Debug + Serialize + Send + Sync,
+			R: DeserializeOwned + Send,
+		{
+			self.0.fetch(method, params).await
+		}
+	}
+
+	#[async_trait]
+	impl BatchClient for BatchMock {
+		async fn fetch_batch(
+			&self,
+			calls: &[(String, Value)],
+		) -> Result<Vec<Result<Value, MockError>>, MockError> {
+			let mut results = Vec::new();
+			for (method, params) in calls {
+				results.push(self.0.fetch(method, params).await);
+			}
+			Ok(results)
+		}
+	}
+
+	#[tokio::test]
+	async fn test_record_and_replay_batch() {
+		let path = fixture("batch");
+		let mock = BatchMock::default();
+		let error = JsonRpcError { code: -101, message: "Unknown block".to_string(), data: None };
+		mock.0.push_response(MockResponse::Error(error));
+		mock.0.push_response(MockResponse::Value(json!(2048)));
+		let client = RecordingClient::new(mock, &path);
+
+		let calls = [
+			("getblockcount".to_string(), Value::Null),
+			("getblockhash".to_string(), json!([4096])),
+		];
+		let results = client.fetch_batch(&calls).await.unwrap();
+		assert_eq!(results[0].as_ref().unwrap(), &json!(2048));
+		assert_eq!(results[1].as_ref().unwrap_err().as_error_response().unwrap().code, -101);
+		assert_eq!(client.interactions().len(), 2);
+
+		let client = ReplayClient::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+		let calls = [calls[1].clone(), calls[0].clone(), calls[0].clone()];
+		let results = client.fetch_batch(&calls).await.unwrap();
+		assert_eq!(results[0].as_ref().unwrap_err().as_error_response().unwrap().code, -101);
+		assert_eq!(results[1].as_ref().unwrap(), &json!(2048));
+		// Each call is replayed once
+		assert!(matches!(results[2], Err(ReplayClientError::UnexpectedCall { .. })));
+		assert!(client.remaining().is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_replay_in_order() {
+		let interactions = serde_json::from_value(json!([
+			{ "method": "getblockcount", "params": [], "result": 1 },
+			{ "method": "getblockcount", "params": [], "result": 2 },
+			{ "method": "getblockhash", "params": [1], "result": "0x01" },
+		]))
+		.unwrap();
+		let client = ReplayClient::new(interactions);
+		assert_eq!(client.fetch::<_, u32>("getblockcount", ()).await.unwrap(), 1);
+		assert_eq!(client.fetch::<_, u32>("getblockcount", ()).await.unwrap(), 2);
+
+		let err = client.fetch::<_, u32>("getblockcount", ()).await.unwrap_err();
+		assert!(
+			matches!(err, ReplayClientError::UnexpectedCall { method, .. } if method == "getblockcount")
+		);
+		let err = client.fetch::<_, String>("getblockhash", [2]).await.unwrap_err();
+		assert!(
+			matches!(err, ReplayClientError::UnexpectedCall { params, .. } if params == json!([2]))
+		);
+		assert_eq!(client.remaining().len(), 1);
+	}
+}
@@ -1,13 +1,18 @@
 //! A [JsonRpcClient] implementation that retries requests filtered by [RetryPolicy]
 //! with an exponential backoff.
 
-use super::{common::JsonRpcError, http::ClientError};
-use crate::{errors::ProviderError, JsonRpcClient};
+use super::{
+	common::{JsonRpcError, NeoRpcErrorKind},
+	http::ClientError,
+};
+use crate::{errors::ProviderError, BatchClient, JsonRpcClient};
 use async_trait::async_trait;
 use reqwest::StatusCode;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::{
 	fmt::Debug,
+	future::Future,
 	sync::atomic::{AtomicU32, Ordering},
 	time::Duration,
 };
@@ -113,6 +118,83 @@ where
 		self.compute_units_per_second = cpus;
 		self
 	}
+
+	/// Sends the request made by `request` until it succeeds, or fails with an error that
+	/// shouldn't be retried or after too many retries.
+	async fn retry<V, F, Fut>(&self, mut request: F) -> Result<V, RetryClientError>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<V, T::Error>>,
+	{
+		let ahead_in_queue = self.requests_enqueued.fetch_add(1, Ordering::SeqCst) as u64;
+
+		let mut rate_limit_retry_number: u32 = 0;
+		let mut timeout_retries: u32 = 0;
+
+		loop {
+			let err;
+
+			// hack to not hold `V` across an await in the sleep future and prevent requiring
+			// V: Send + Sync
+			{
+				match request().await {
+					Ok(ret) => {
+						self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
+						return Ok(ret)
+					},
+					Err(err_) => err = err_,
+				}
+			}
+
+			let should_retry = self.policy.should_retry(&err);
+			if should_retry {
+				rate_limit_retry_number += 1;
+				if rate_limit_retry_number > self.rate_limit_retries {
+					trace!("request timed out after {} retries", self.rate_limit_retries);
+					return Err(RetryClientError::TimeoutError)
+				}
+
+				let current_queued_requests = self.requests_enqueued.load(Ordering::SeqCst) as u64;
+
+				// try to extract the requested backoff from the error or compute the next backoff
+				// based on retry count
+				let mut next_backoff = self.policy.backoff_hint(&err).unwrap_or_else(|| {
+					Duration::from_millis(self.initial_backoff.as_millis() as u64)
+				});
+
+				// the retried method isn't known here, so every queued request is assumed to cost the
+				// same number of compute units. Neo nodes don't weight their methods, and per-method
+				// weights belong in `RateLimitedClient`
+				const AVG_COST: u64 = 17u64;
+				let seconds_to_wait_for_compute_budget = compute_unit_offset_in_secs(
+					AVG_COST,
+					self.compute_units_per_second,
+					current_queued_requests,
+					ahead_in_queue,
+				);
+				next_backoff += Duration::from_secs(seconds_to_wait_for_compute_budget);
+
+				trace!("retrying and backing off for {:?}", next_backoff);
+
+				#[cfg(target_arch = "wasm32")]
+				futures_timer::Delay::new(next_backoff).await;
+
+				#[cfg(not(target_arch = "wasm32"))]
+				tokio::time::sleep(next_backoff).await;
+			} else {
+				let err: ProviderError = err.into();
+				if timeout_retries < self.timeout_retries && maybe_connectivity(&err) {
+					timeout_retries += 1;
+					trace!(err = ?err, "retrying due to spurious network");
+					continue
+				}
+
+				trace!(err = ?err, "should not retry");
+				self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
+				return Err(RetryClientError::ProviderError(err))
+			}
+		}
+	}
 }
 
 /// Builder for a [`RetryClient`]
@@ -276,83 +358,35 @@ where
 			RetryParams::Value(params)
 		};
 
-		let ahead_in_queue = self.requests_enqueued.fetch_add(1, Ordering::SeqCst) as u64;
-
-		let mut rate_limit_retry_number: u32 = 0;
-		let mut timeout_retries: u32 = 0;
-
-		loop {
-			let err;
-
-			// hack to not hold `R` across an await in the sleep future and prevent requiring
-			// R: Send + Sync
-			{
-				let resp = match params {
-					RetryParams::Value(ref params) => self.inner.fetch(method, params).await,
-					RetryParams::Zst(unit) => self.inner.fetch(method, unit).await,
-				};
-				match resp {
-					Ok(ret) => {
-						self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
-						return Ok(ret)
-					},
-					Err(err_) => err = err_,
-				}
+		let params = &params;
+		self.retry(move || async move {
+			match params {
+				RetryParams::Value(params) => self.inner.fetch(method, params).await,
+				RetryParams::Zst(()) => self.inner.fetch(method, ()).await,
 			}
+		})
+		.await
+	}
+}
 
-			let should_retry = self.policy.should_retry(&err);
-			if should_retry {
-				rate_limit_retry_number += 1;
-				if rate_limit_retry_number > self.rate_limit_retries {
-					trace!("request timed out after {} retries", self.rate_limit_retries);
-					return Err(RetryClientError::TimeoutError)
-				}
-
-				let current_queued_requests = self.requests_enqueued.load(Ordering::SeqCst) as u64;
-
-				// try to extract the requested backoff from the error or compute the next backoff
-				// based on retry count
-				let mut next_backoff = self.policy.backoff_hint(&err).unwrap_or_else(|| {
-					Duration::from_millis(self.initial_backoff.as_millis() as u64)
-				});
-
-				// requests are usually weighted and can vary from 10 CU to several 100 CU, cheaper
-				// requests are more common some example alchemy weights:
-				// - `neo_getStorageAt`: 17
-				// - `neo_getBlockByNumber`: 16
-				// - `neo_newFilter`: 20
-				//
-				// (coming from forking mode) assuming here that storage request will be the driver
-				// for Rate limits we choose `17` as the average cost of any request
-				const AVG_COST: u64 = 17u64;
-				let seconds_to_wait_for_compute_budget = compute_unit_offset_in_secs(
-					AVG_COST,
-					self.compute_units_per_second,
-					current_queued_requests,
-					ahead_in_queue,
-				);
-				next_backoff += Duration::from_secs(seconds_to_wait_for_compute_budget);
-
-				trace!("retrying and backing off for {:?}", next_backoff);
-
-				#[cfg(target_arch = "wasm32")]
-				futures_timer::Delay::new(next_backoff).await;
-
-				#[cfg(not(target_arch = "wasm32"))]
-				tokio::time::sleep(next_backoff).await;
-			} else {
-				let err: ProviderError = err.into();
-				if timeout_retries < self.timeout_retries && maybe_connectivity(&err) {
-					timeout_retries += 1;
-					trace!(err = ?err, "retrying due to spurious network");
-					continue
-				}
-
-				trace!(err = ?err, "should not retry");
-				self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
-				return Err(RetryClientError::ProviderError(err))
-			}
-		}
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T> BatchClient for RetryClient<T>
+where
+	T: BatchClient + 'static,
+	T::Error: Sync + Send + 'static,
+{
+	/// Sends the batch, retrying it as a whole like a request. The calls that fail are returned
+	/// as is.
+	async fn fetch_batch(
+		&self,
+		calls: &[(String, Value)],
+	) -> Result<Vec<Result<Value, Self::Error>>, Self::Error> {
+		let results = self.retry(move || self.inner.fetch_batch(calls)).await?;
+		Ok(results
+			.into_iter()
+			.map(|result| result.map_err(|err| RetryClientError::ProviderError(err.into())))
+			.collect())
 	}
 }
 
@@ -431,6 +465,76 @@ impl RetryPolicy<ClientError> for HttpRateLimitRetryPolicy {
 	}
 }
 
+/// Implements [RetryPolicy] for the errors of Neo nodes, both the C# `RpcServer` plugin and neo-go,
+/// which share their error codes.
+///
+/// Transient failures are retried: a block the node doesn't have yet because it's behind the node
+/// that reported the tip, a full memory pool, a busy node, rate limiting (HTTP `429` and `503`)
+/// and connection resets. Requests that can't succeed, like transactions with insufficient funds,
+/// failing the policy or with an invalid signature, are never retried.
+#[derive(Debug, Default)]
+pub struct NeoRetryPolicy;
+
+impl<E: crate::RpcError + 'static> RetryPolicy<E> for NeoRetryPolicy {
+	fn should_retry(&self, error: &E) -> bool {
+		if let Some(err) = error.as_error_response() {
+			return is_transient_neo_error(err)
+		}
+
+		let error: &(dyn std::error::Error + 'static) = error;
+		if let Some(ClientError::ReqwestError(err)) = error.downcast_ref::<ClientError>() {
+			if let Some(status) = err.status() {
+				// Rate limited, or the node behind a proxy is unavailable
+				return matches!(status.as_u16(), 429 | 502 | 503 | 504)
+			}
+		}
+		is_connection_reset(error)
+	}
+
+	fn backoff_hint(&self, _error: &E) -> Option<Duration> {
+		None
+	}
+}
+
+/// Whether a request a Neo node answered with `err` may succeed if it is sent again.
+fn is_transient_neo_error(err: &JsonRpcError) -> bool {
+	match err.kind() {
+		// Nodes behind the tip don't have it yet
+		NeoRpcErrorKind::UnknownBlock | NeoRpcErrorKind::UnknownHeight => true,
+		NeoRpcErrorKind::MempoolFull => true,
+		// Busy nodes and rate limiting proxies have no dedicated code
+		NeoRpcErrorKind::VerificationFailed
+		| NeoRpcErrorKind::InternalError
+		| NeoRpcErrorKind::Other => {
+			let message = err.message.to_lowercase();
+			err.code == 429
+				|| ["busy", "timeout", "try again"]
+					.iter()
+					.any(|transient| message.contains(transient))
+		},
+		_ => false,
+	}
+}
+
+/// Whether the `error` was caused by the connection to the node being reset or timing out
+fn is_connection_reset(error: &(dyn std::error::Error + 'static)) -> bool {
+	let mut source = Some(error);
+	while let Some(err) = source {
+		if let Some(err) = err.downcast_ref::<std::io::Error>() {
+			return matches!(
+				err.kind(),
+				std::io::ErrorKind::ConnectionReset
+					| std::io::ErrorKind::ConnectionAborted
+					| std::io::ErrorKind::BrokenPipe
+					| std::io::ErrorKind::TimedOut
+					| std::io::ErrorKind::UnexpectedEof
+			)
+		}
+		source = err.source();
+	}
+	false
+}
+
 /// Calculates an offset in seconds by taking into account the number of currently queued requests,
 /// number of requests that were ahead in the queue when the request was first issued, the average
 /// cost a weighted request (heuristic), and the number of available compute units per seconds.
@@ -485,6 +589,7 @@ fn maybe_connectivity(err: &ProviderError) -> bool {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::{MockError, MockProvider, MockResponse, RpcError};
 	// assumed average cost of a request
 	const AVG_COST: u64 = 17u64;
 	const COMPUTE_UNITS: u64 = 330u64;
@@ -578,4 +683,85 @@ mod tests {
 		let should_retry = HttpRateLimitRetryPolicy.should_retry(&err);
 		assert!(should_retry);
 	}
+
+	fn neo_error(code: i64, message: &str, data: Option<&str>) -> MockResponse {
+		let data = data.map(|data| serde_json::Value::String(data.to_string()));
+		MockResponse::Error(JsonRpcError { code, message: message.to_string(), data })
+	}
+
+	/// A client retrying with the [NeoRetryPolicy] over a mock answering with `responses` in
+	/// order.
+	fn neo_retrying(responses: Vec<MockResponse>) -> (RetryClient<MockProvider>, MockProvider) {
+		let mock = MockProvider::new();
+		responses.into_iter().rev().for_each(|response| mock.push_response(response));
+		let client = RetryClientBuilder::default()
+			.rate_limit_retries(3)
+			.initial_backoff(Duration::from_millis(1))
+			.build(mock.clone(), Box::new(NeoRetryPolicy));
+		(client, mock)
+	}
+
+	#[test]
+	fn test_neo_error_codes() {
+		let should_retry = |response: MockResponse| {
+			let MockResponse::Error(err) = response else { unreachable!() };
+			NeoRetryPolicy.should_retry(&MockError::JsonRpcError(err))
+		};
+		assert!(should_retry(neo_error(-101, "Unknown block", None)));
+		assert!(should_retry(neo_error(-100, "Unknown block", None)));
+		assert!(should_retry(neo_error(-109, "Unknown height", None)));
+		assert!(should_retry(neo_error(-502, "Memory pool capacity reached", None)));
+		assert!(should_retry(neo_error(-500, "Server is busy", None)));
+		assert!(should_retry(neo_error(
+			-500,
+			"Inventory verification failed",
+			Some("OutOfMemory")
+		)));
+
+		assert!(!should_retry(neo_error(-100, "Unknown transaction", None)));
+		assert!(!should_retry(neo_error(-108, "Unknown iterator", None)));
+		assert!(!should_retry(neo_error(-500, "InsufficientFunds", None)));
+		assert!(!should_retry(neo_error(
+			-500,
+			"Inventory verification failed",
+			Some("PolicyFail")
+		)));
+		assert!(!should_retry(neo_error(-505, "Policy check failed", None)));
+		assert!(!should_retry(neo_error(-508, "Invalid transaction signature", None)));
+		assert!(!should_retry(neo_error(-511, "Insufficient funds for fee", None)));
+		assert!(!should_retry(neo_error(-32602, "Invalid params", None)));
+		assert!(!NeoRetryPolicy.should_retry(&MockError::EmptyResponses));
+	}
+
+	#[tokio::test]
+	#[cfg(not(target_arch = "wasm32"))]
+	async fn test_neo_transient_errors_are_retried() {
+		let (client, mock) = neo_retrying(vec![
+			neo_error(-101, "Unknown block", None),
+			neo_error(-500, "Server is busy", None),
+			MockResponse::Value(serde_json::json!(5)),
+		]);
+		let count: u32 = client.fetch("getblockcount", ()).await.unwrap();
+		assert_eq!(count, 5);
+		for _ in 0..3 {
+			mock.assert_request("getblockcount", ()).unwrap();
+		}
+
+		let (client, _) = neo_retrying(vec![neo_error(-101, "Unknown block", None); 5]);
+		let err = client.fetch::<_, u32>("getblockcount", ()).await.unwrap_err();
+		assert!(matches!(err, RetryClientError::TimeoutError));
+	}
+
+	#[tokio::test]
+	#[cfg(not(target_arch = "wasm32"))]
+	async fn test_neo_permanent_errors_are_not_retried() {
+		let (client, mock) = neo_retrying(vec![
+			neo_error(-500, "Inventory verification failed", Some("InsufficientFunds")),
+			MockResponse::Value(serde_json::json!({ "hash": "0x01" })),
+		]);
+		let err = client.fetch::<_, serde_json::Value>("sendrawtransaction", ["AA=="]).await;
+		assert_eq!(err.unwrap_err().as_error_response().unwrap().code, -500);
+		mock.assert_request("sendrawtransaction", ["AA=="]).unwrap();
+		assert!(mock.assert_request("sendrawtransaction", ["AA=="]).is_err());
+	}
 }
@@ -1,6 +1,5 @@
 use super::WsError;
 use crate::{JsonRpcError, ProviderError};
-use primitive_types::U256;
 use std::{
 	error::Error,
 	fmt::{Display, Formatter},
@@ -34,7 +33,7 @@ pub enum WsClientError {
 
 	/// PubSubClient asked to listen to an unknown subscription id
 	#[error("Attempted to listen to unknown subscription: {0:?}")]
-	UnknownSubscription(U256),
+	UnknownSubscription(String),
 
 	/// Too Many Reconnects
 	#[error("Reconnect limit reached")]
@@ -0,0 +1,196 @@
+//! A local WebSocket server speaking the subscription protocol of neo-go nodes, for tests.
+//!
+//! Only `subscribe` and `unsubscribe` are answered. Like a node, the server sends an event once
+//! to a client if any of its subscriptions to the event match, and forgets the subscriptions of
+//! a connection when it closes.
+//!
+//! The filters are read from the raw params and applied the way neo-go does, independently of
+//! the client's `SubscriptionFilter`, so the tests check what the client sends against the node.
+
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::net::TcpListener;
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+#[derive(Default)]
+struct State {
+	next_id: u64,
+	// Server id, event and filter of the subscriptions of the current connection, the filter
+	// being `null` if there is none
+	subscriptions: Vec<(String, String, Value)>,
+	// Messages to the current connection
+	connection: Option<mpsc::UnboundedSender<Message>>,
+	connections: usize,
+}
+
+pub(crate) struct FakeServer {
+	pub url: String,
+	state: Arc<Mutex<State>>,
+}
+
+impl FakeServer {
+	pub async fn spawn() -> Self {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let state = Arc::new(Mutex::new(State::default()));
+
+		let shared = state.clone();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				let Ok(ws) = accept_async(stream).await else { continue };
+				let (mut sink, mut stream) = ws.split();
+				let (tx, mut rx) = mpsc::unbounded();
+				{
+					let mut state = shared.lock().unwrap();
+					state.subscriptions.clear();
+					state.connection = Some(tx.clone());
+					state.connections += 1;
+				}
+
+				tokio::spawn(async move {
+					while let Some(message) = rx.next().await {
+						if sink.send(message).await.is_err() {
+							return
+						}
+					}
+					let _ = sink.close().await;
+				});
+
+				let state = shared.clone();
+				tokio::spawn(async move {
+					while let Some(Ok(Message::Text(text))) = stream.next().await {
+						let request: Value = serde_json::from_str(&text).unwrap();
+						let response = Self::respond(&state, &request);
+						let _ = tx.unbounded_send(Message::Text(response.to_string()));
+					}
+				});
+			}
+		});
+
+		Self { url, state }
+	}
+
+	fn respond(state: &Mutex<State>, request: &Value) -> Value {
+		let mut state = state.lock().unwrap();
+		let id = &request["id"];
+		let params = &request["params"];
+		match request["method"].as_str() {
+			Some("subscribe") => match Self::subscription(params) {
+				Some((event, filter)) => {
+					state.next_id += 1;
+					let server_id = state.next_id.to_string();
+					state.subscriptions.push((server_id.clone(), event, filter));
+					json!({ "jsonrpc": "2.0", "id": id, "result": server_id })
+				},
+				None => Self::error(id, "invalid subscription"),
+			},
+			Some("unsubscribe") => {
+				let server_id = params[0].as_str().unwrap_or_default();
+				state.subscriptions.retain(|(id, ..)| id != server_id);
+				json!({ "jsonrpc": "2.0", "id": id, "result": true })
+			},
+			_ => Self::error(id, "method not found"),
+		}
+	}
+
+	/// Reads the event and filter of `subscribe` params. Like neo-go, unknown events and filter
+	/// fields are rejected.
+	fn subscription(params: &Value) -> Option<(String, Value)> {
+		let params = params.as_array().filter(|params| (1..=2).contains(&params.len()))?;
+		let event = params[0].as_str()?;
+		let fields: &[&str] = match event {
+			"block_added" => &["primary", "since", "till"],
+			"transaction_added" => &["sender", "signer"],
+			"notification_from_execution" => &["contract", "name"],
+			"transaction_executed" => &["state", "container"],
+			_ => return None,
+		};
+		let filter = params.get(1).cloned().unwrap_or_default();
+		let known = filter.as_object().map_or(filter.is_null(), |filter| {
+			filter.keys().all(|field| fields.contains(&field.as_str()))
+		});
+		known.then(|| (event.to_string(), filter))
+	}
+
+	/// Whether neo-go sends `item` to a subscription with `filter`. Every field set in the filter
+	/// has to match.
+	fn matches(filter: &Value, item: &Value) -> bool {
+		let same_hash = |value: &Value, hash: &Value| match (value.as_str(), hash.as_str()) {
+			(Some(value), Some(hash)) => value
+				.trim_start_matches("0x")
+				.eq_ignore_ascii_case(hash.trim_start_matches("0x")),
+			_ => false,
+		};
+		let Some(filter) = filter.as_object() else { return true };
+		filter
+			.iter()
+			.filter(|(_, expected)| !expected.is_null())
+			.all(|(field, expected)| {
+				match field.as_str() {
+					"primary" => item["primary"] == *expected,
+					"since" => item["index"].as_u64() >= expected.as_u64(),
+					"till" => item["index"].as_u64() <= expected.as_u64(),
+					// The sender of a transaction is its first signer
+					"sender" => same_hash(&item["signers"][0]["account"], expected),
+					"signer" => item["signers"]
+						.as_array()
+						.into_iter()
+						.flatten()
+						.any(|signer| same_hash(&signer["account"], expected)),
+					"contract" => same_hash(&item["contract"], expected),
+					"name" => item["eventname"] == *expected,
+					"state" => item["vmstate"] == *expected,
+					"container" => same_hash(&item["container"], expected),
+					_ => false,
+				}
+			})
+	}
+
+	fn error(id: &Value, message: &str) -> Value {
+		json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": message } })
+	}
+
+	/// Sends `item` for `event` if a subscription of the current connection matches it.
+	pub fn send_event(&self, event: &str, item: Value) -> bool {
+		let state = self.state.lock().unwrap();
+		let matched = state
+			.subscriptions
+			.iter()
+			.any(|(_, subscribed, filter)| subscribed == event && Self::matches(filter, &item));
+		if let (true, Some(connection)) = (matched, &state.connection) {
+			let notification = json!({ "jsonrpc": "2.0", "method": event, "params": [item] });
+			let _ = connection.unbounded_send(Message::Text(notification.to_string()));
+		}
+		matched
+	}
+
+	/// Closes the current connection, so that the client has to reconnect.
+	pub fn disconnect(&self) {
+		let mut state = self.state.lock().unwrap();
+		state.subscriptions.clear();
+		if let Some(connection) = state.connection.take() {
+			connection.close_channel();
+		}
+	}
+
+	/// The number of connections accepted so far.
+	pub fn connections(&self) -> usize {
+		self.state.lock().unwrap().connections
+	}
+
+	/// Waits until the current connection has `count` subscriptions.
+	pub async fn wait_for_subscriptions(&self, count: usize) {
+		tokio::time::timeout(Duration::from_secs(5), async {
+			while self.state.lock().unwrap().subscriptions.len() != count {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+			}
+		})
+		.await
+		.expect("timed out waiting for subscriptions");
+	}
+}
@@ -2,13 +2,12 @@
 use super::WebSocketConfig;
 use super::{
 	backend::{BackendDriver, WsBackend},
-	ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, Response, SubId,
+	ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, Response,
 	WsClient, WsClientError,
 };
-use crate::JsonRpcError;
+use crate::{rpc::subscription::SubscriptionFilter, JsonRpcError};
 use futures_channel::{mpsc, oneshot};
 use futures_util::{select_biased, StreamExt};
-use primitive_types::U256;
 use serde_json::value::{to_raw_value, RawValue};
 use std::{
 	collections::{BTreeMap, HashMap},
@@ -18,64 +17,47 @@ use std::{
 	},
 };
 
-pub type SharedChannelMap = Arc<Mutex<HashMap<U256, mpsc::UnboundedReceiver<Box<RawValue>>>>>;
+pub type SharedChannelMap = Arc<Mutex<HashMap<String, mpsc::UnboundedReceiver<Box<RawValue>>>>>;
 
 pub const DEFAULT_RECONNECTS: usize = 5;
 
-/// This struct manages the relationship between the u64 request ID, and U256
-/// server-side subscription ID. It does this by aliasing the server ID to the
-/// request ID, and returning the Request ID to the caller (hiding the server
-/// ID in the SubscriptionManager internals.) Giving the caller a "fake"
-/// subscription id allows the subscription to behave consistently across
-/// reconnections
+/// This struct manages the relationship between the u64 request ID and the
+/// server-side subscription ID. The request ID is returned to the caller as
+/// the subscription id, hiding the server ID in the SubscriptionManager
+/// internals. Giving the caller a "fake" subscription id allows the
+/// subscription to behave consistently across reconnections.
+///
+/// Notifications only carry the name of their event, so each one is
+/// forwarded to every subscription to that event whose filter it matches.
 pub struct SubscriptionManager {
 	// Active subs indexed by request id
 	subs: BTreeMap<u64, ActiveSub>,
-	// Maps active server-side IDs to local subscription IDs
-	aliases: HashMap<U256, u64>,
 	// Used to share notification channels with the WsClient(s)
 	channel_map: SharedChannelMap,
 }
 
 impl SubscriptionManager {
 	fn new(channel_map: SharedChannelMap) -> Self {
-		Self { subs: Default::default(), aliases: Default::default(), channel_map }
+		Self { subs: Default::default(), channel_map }
 	}
 
 	fn count(&self) -> usize {
 		self.subs.len()
 	}
 
-	fn add_alias(&mut self, sub: U256, id: u64) {
-		if let Some(entry) = self.subs.get_mut(&id) {
-			entry.current_server_id = Some(sub);
-		}
-		self.aliases.insert(sub, id);
-	}
-
-	fn remove_alias(&mut self, server_id: U256) {
-		if let Some(id) = self.aliases.get(&server_id) {
-			if let Some(sub) = self.subs.get_mut(id) {
-				sub.current_server_id = None;
-			}
-		}
-		self.aliases.remove(&server_id);
-	}
-
 	#[tracing::instrument(skip(self))]
 	fn end_subscription(&mut self, id: u64) -> Option<Box<RawValue>> {
 		if let Some(sub) = self.subs.remove(&id) {
 			if let Some(server_id) = sub.current_server_id {
-				tracing::debug!(server_id = format!("0x{server_id:x}"), "Ending subscription");
-				self.remove_alias(server_id);
+				tracing::debug!(%server_id, "Ending subscription");
 				// drop the receiver as we don't need the result
 				let (channel, _) = oneshot::channel();
 				// Serialization errors are ignored, and result in the request
 				// not being dispatched. This is fine, as worst case it will
 				// result in the server sending us notifications we ignore
 				let unsub_request = InFlight {
-					method: "neo_unsubscribe".to_string(),
-					params: SubId(server_id).serialize_raw().ok()?,
+					method: "unsubscribe".to_string(),
+					params: to_raw_value(&[server_id]).ok()?,
 					channel,
 				};
 				// reuse the RPC ID. this is somewhat dirty.
@@ -87,58 +69,72 @@ impl SubscriptionManager {
 		None
 	}
 
-	#[tracing::instrument(skip_all, fields(server_id = ?notification.subscription))]
+	#[tracing::instrument(skip_all, fields(event = %notification.event))]
 	fn handle_notification(&mut self, notification: Notification) {
-		let server_id = notification.subscription;
-
-		// If no alias, just return
-		let id_opt = self.aliases.get(&server_id).copied();
-		if id_opt.is_none() {
-			tracing::debug!(
-				server_id = format!("0x{server_id:x}"),
-				"No aliased subscription found"
-			);
+		if notification.event == "event_missed" {
+			tracing::warn!("Node dropped notifications for this client");
 			return
 		}
-		let id = id_opt.unwrap();
+		let item = match serde_json::from_str::<(Box<RawValue>,)>(notification.params.get()) {
+			Ok((item,)) => item,
+			Err(err) => {
+				tracing::debug!(%err, "Malformed notification");
+				return
+			},
+		};
+		let value: Option<serde_json::Value> = serde_json::from_str(item.get()).ok();
 
-		// alias exists, or should be dropped from alias table
-		let sub_opt = self.subs.get(&id);
-		if sub_opt.is_none() {
-			tracing::trace!(id, "Aliased subscription found, but not active");
-			self.aliases.remove(&server_id);
+		let mut dropped = vec![];
+		for (id, active) in self.subs.iter() {
+			if active.event != notification.event || active.current_server_id.is_none() {
+				continue
+			}
+			if let Some(filter) = &active.filter {
+				if !value.as_ref().is_some_and(|value| filter.matches(value)) {
+					continue
+				}
+			}
+			tracing::debug!(id, "Forwarding notification to listener");
+			// receiver has dropped, so we drop the sub
+			if active.channel.unbounded_send(item.clone()).is_err() {
+				dropped.push(*id);
+			}
 		}
-		let active = sub_opt.unwrap();
-
-		tracing::debug!(id, "Forwarding notification to listener");
-		// send the notification over the channel
-		let send_res = active.channel.unbounded_send(notification.result);
-
-		// receiver has dropped, so we drop the sub
-		if send_res.is_err() {
-			tracing::debug!(id, "Listener dropped. Dropping alias and subs");
-			// TODO: end subcription here?
-			self.aliases.remove(&server_id);
+		for id in dropped {
+			tracing::debug!(id, "Listener dropped. Dropping sub");
 			self.subs.remove(&id);
 		}
 	}
 
 	fn req_success(&mut self, id: u64, result: Box<RawValue>) -> Box<RawValue> {
-		if let Ok(server_id) = serde_json::from_str::<SubId>(result.get()) {
-			tracing::debug!(id, server_id = %server_id.0, "Registering new sub alias");
-			self.add_alias(server_id.0, id);
-			let result = U256::from(id);
-			to_raw_value(&format!("0x{result:x}")).expect("valid json")
+		if let Ok(server_id) = serde_json::from_str::<String>(result.get()) {
+			tracing::debug!(id, %server_id, "Registering new sub");
+			if let Some(entry) = self.subs.get_mut(&id) {
+				entry.current_server_id = Some(server_id);
+			}
+			to_raw_value(&id.to_string()).expect("valid json")
 		} else {
 			result
 		}
 	}
 
+	fn req_fail(&mut self, id: u64) {
+		// The node refused the subscription, so no notifications will come
+		// for it
+		if self.subs.remove(&id).is_some() {
+			tracing::debug!(id, "Subscription failed");
+			self.channel_map.lock().unwrap().remove(&id.to_string());
+		}
+	}
+
 	fn has(&self, id: u64) -> bool {
 		self.subs.contains_key(&id)
 	}
 
-	fn to_reissue(&self) -> impl Iterator<Item = (&u64, &ActiveSub)> {
+	fn to_reissue(&mut self) -> impl Iterator<Item = (&u64, &ActiveSub)> {
+		// Subscriptions are bound to the connection, so the old server ids
+		// are meaningless on the new one
+		self.subs.values_mut().for_each(|sub| sub.current_server_id = None);
 		self.subs.iter()
 	}
 
@@ -148,15 +144,18 @@ impl SubscriptionManager {
 		params: Box<RawValue>,
 	) -> Result<Box<RawValue>, WsClientError> {
 		let (tx, rx) = mpsc::unbounded();
+		// Malformed params are still sent, so that the node answers with an
+		// error. Such a sub never receives notifications
+		let (event, filter) = SubscriptionFilter::from_params(params.get()).unwrap_or_default();
 
-		let active_sub = ActiveSub { params, channel: tx, current_server_id: None };
+		let active_sub = ActiveSub { params, event, filter, channel: tx, current_server_id: None };
 		let req = active_sub.serialize_raw(id)?;
 
 		// Explicit scope for the lock
 		// This insertion should be made BEFORE the request returns.
 		// So we make it before the request is even dispatched :)
 		{
-			self.channel_map.lock().unwrap().insert(id.into(), rx);
+			self.channel_map.lock().unwrap().insert(id.to_string(), rx);
 		}
 		self.subs.insert(id, active_sub);
 
@@ -357,7 +356,7 @@ impl RequestManager {
 		for (id, sub) in self.subs.to_reissue() {
 			let (tx, _rx) = oneshot::channel();
 			let in_flight = InFlight {
-				method: "neo_subscribe".to_string(),
+				method: "subscribe".to_string(),
 				params: sub.params.clone(),
 				channel: tx,
 			};
@@ -394,6 +393,7 @@ impl RequestManager {
 	}
 
 	fn req_fail(&mut self, id: u64, error: JsonRpcError) {
+		self.subs.req_fail(id);
 		// pending fut is missing, this is fine
 		if let Some(req) = self.reqs.remove(&id) {
 			// pending fut has been dropped, this is fine
@@ -422,7 +422,7 @@ impl RequestManager {
 
 		// Ordering matters here. We want this block above the unbounded send,
 		// and after the serialization
-		if in_flight.method == "neo_subscribe" {
+		if in_flight.method == "subscribe" {
 			self.subs.service_subscription_request(id, in_flight.params.clone())?;
 		}
 
@@ -443,14 +443,13 @@ impl RequestManager {
 				let id = self.next_id();
 				self.service_request(id, method, params, sender)?;
 			},
-			Instruction::Unsubscribe { id } => {
-				if let Some(req) = self.subs.end_subscription(id.low_u64()) {
+			Instruction::Unsubscribe { id } =>
+				if let Some(req) = self.subs.end_subscription(id) {
 					self.backend
 						.dispatcher
 						.unbounded_send(req)
 						.map_err(|_| WsClientError::DeadChannel)?;
-				}
-			},
+				},
 		}
 		Ok(())
 	}
@@ -5,7 +5,6 @@ mod backend;
 mod manager;
 
 use manager::{RequestManager, SharedChannelMap};
-use primitive_types::U256;
 use std::{
 	fmt,
 	fmt::{Debug, Formatter},
@@ -20,6 +19,9 @@ use types::*;
 mod error;
 pub use error::*;
 
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod fake_server;
+
 use crate::{JsonRpcClient, ProviderError, PubsubClient};
 use async_trait::async_trait;
 use futures_channel::{mpsc, oneshot};
@@ -131,7 +133,7 @@ impl JsonRpcClient for WsClient {
 impl PubsubClient for WsClient {
 	type NotificationStream = mpsc::UnboundedReceiver<Box<RawValue>>;
 
-	fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, WsClientError> {
+	fn subscribe<T: Into<String>>(&self, id: T) -> Result<Self::NotificationStream, WsClientError> {
 		// due to the behavior of the request manager, we know this map has
 		// been populated by the time the `request()` call returns
 		let id = id.into();
@@ -142,9 +144,11 @@ impl PubsubClient for WsClient {
 			.ok_or(WsClientError::UnknownSubscription(id))
 	}
 
-	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), WsClientError> {
+	fn unsubscribe<T: Into<String>>(&self, id: T) -> Result<(), WsClientError> {
+		let id = id.into();
+		let id = id.parse().map_err(|_| WsClientError::UnknownSubscription(id))?;
 		self.instructions
-			.unbounded_send(Instruction::Unsubscribe { id: id.into() })
+			.unbounded_send(Instruction::Unsubscribe { id })
 			.map_err(|_| WsClientError::UnexpectedClose)
 	}
 }
@@ -215,3 +219,142 @@ impl crate::Provider<WsClient> {
 		Ok(Self::new(ws))
 	}
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+	use super::{fake_server::FakeServer, *};
+	use crate::{BlockFilter, Provider};
+	use futures_util::{FutureExt, Stream, StreamExt};
+	use neo_types::{util::parse_address, vm_state::VMState};
+	use primitive_types::H256;
+	use serde_json::{json, Value};
+	use std::time::Duration;
+
+	const CONTRACT: &str = "0xd2a4cff31913016155e38e474a2c06d08be276cf";
+
+	fn block(index: u32, primary: u8) -> Value {
+		json!({
+			"hash": format!("0x{}", "11".repeat(32)),
+			"size": 697,
+			"version": 0,
+			"previousblockhash": format!("0x{}", "22".repeat(32)),
+			"merkleroot": format!("0x{}", "33".repeat(32)),
+			"time": 1_700_000_000_000u64,
+			"nonce": "0000000000000000",
+			"index": index,
+			"primary": primary,
+			"nextconsensus": "NVg7LjGcUSrgxgjX3zEgqaksfMaiS8Z6e1",
+			"witnesses": [],
+			"tx": [],
+		})
+	}
+
+	fn notification(name: &str) -> Value {
+		json!({
+			"container": format!("0x{}", "aa".repeat(32)),
+			"contract": CONTRACT,
+			"eventname": name,
+			"state": { "type": "Any" },
+		})
+	}
+
+	fn execution(state: &str) -> Value {
+		json!({
+			"container": format!("0x{}", "bb".repeat(32)),
+			"trigger": "Application",
+			"vmstate": state,
+			"exception": null,
+			"gasconsumed": "997775",
+			"stack": [],
+			"notifications": [],
+		})
+	}
+
+	async fn next<S: Stream + Unpin>(stream: &mut S) -> S::Item {
+		tokio::time::timeout(Duration::from_secs(5), stream.next())
+			.await
+			.expect("timed out waiting for a notification")
+			.expect("subscription ended")
+	}
+
+	#[tokio::test]
+	async fn test_typed_subscriptions() {
+		let server = FakeServer::spawn().await;
+		let provider = Provider::<WsClient>::connect(server.url.as_str()).await.unwrap();
+		let contract = parse_address(CONTRACT);
+		let mut blocks = provider.subscribe_blocks(None).await.unwrap();
+		let mut transfers = provider
+			.subscribe_notifications(Some(contract), Some("Transfer"))
+			.await
+			.unwrap();
+		let mut faults = provider.subscribe_executions(Some(VMState::Fault)).await.unwrap();
+		server.wait_for_subscriptions(3).await;
+
+		assert!(server.send_event("block_added", block(7, 1)));
+		let block = next(&mut blocks).await;
+		assert_eq!(block.index, 7);
+		assert_eq!(block.primary, Some(1));
+
+		// The filters are applied by the node
+		assert!(!server.send_event("notification_from_execution", notification("Approval")));
+		assert!(server.send_event("notification_from_execution", notification("Transfer")));
+		let event = next(&mut transfers).await;
+		assert_eq!(event.container, H256::repeat_byte(0xaa));
+		assert_eq!(event.notification.contract, contract);
+		assert_eq!(event.notification.event_name, "Transfer");
+
+		assert!(!server.send_event("transaction_executed", execution("HALT")));
+		assert!(server.send_event("transaction_executed", execution("FAULT")));
+		let event = next(&mut faults).await;
+		assert_eq!(event.container, H256::repeat_byte(0xbb));
+		assert_eq!(event.execution.state, VMState::Fault);
+	}
+
+	#[tokio::test]
+	async fn test_notifications_are_routed_by_filter() {
+		let server = FakeServer::spawn().await;
+		let provider = Provider::<WsClient>::connect(server.url.as_str()).await.unwrap();
+		let filter = |primary| Some(BlockFilter { primary: Some(primary), ..Default::default() });
+		let mut first = provider.subscribe_blocks(filter(1)).await.unwrap();
+		let mut second = provider.subscribe_blocks(filter(2)).await.unwrap();
+		assert!(first.id.parse::<u64>().is_ok());
+		assert_ne!(first.id, second.id);
+		assert!(matches!(
+			provider.as_ref().unsubscribe("0x1"),
+			Err(WsClientError::UnknownSubscription(_))
+		));
+
+		// The node sends each block once, whichever subscription it matches
+		assert!(server.send_event("block_added", block(1, 1)));
+		assert!(server.send_event("block_added", block(2, 2)));
+		assert!(server.send_event("block_added", block(3, 1)));
+		assert_eq!(next(&mut first).await.index, 1);
+		assert_eq!(next(&mut first).await.index, 3);
+		assert_eq!(next(&mut second).await.index, 2);
+		assert!(second.next().now_or_never().is_none());
+
+		// Dropping a stream unsubscribes
+		drop(first);
+		server.wait_for_subscriptions(1).await;
+		assert!(!server.send_event("block_added", block(4, 1)));
+	}
+
+	#[tokio::test]
+	async fn test_subscriptions_resume_after_reconnect() {
+		let server = FakeServer::spawn().await;
+		let provider = Provider::<WsClient>::connect(server.url.as_str()).await.unwrap();
+		let filter = BlockFilter { since: Some(5), ..Default::default() };
+		let mut blocks = provider.subscribe_blocks(Some(filter)).await.unwrap();
+		assert!(server.send_event("block_added", block(5, 0)));
+		assert_eq!(next(&mut blocks).await.index, 5);
+
+		server.disconnect();
+		server.wait_for_subscriptions(1).await;
+		assert_eq!(server.connections(), 2);
+
+		// The subscription is made again with the same filter
+		assert!(!server.send_event("block_added", block(4, 0)));
+		assert!(server.send_event("block_added", block(6, 0)));
+		assert_eq!(next(&mut blocks).await.index, 6);
+	}
+}
@@ -1,6 +1,5 @@
-use crate::{common::Request, JsonRpcError};
+use crate::{common::Request, rpc::subscription::SubscriptionFilter, JsonRpcError};
 use futures_channel::{mpsc, oneshot};
-use primitive_types::U256;
 use serde::{de, Deserialize};
 use serde_json::value::{to_raw_value, RawValue};
 use std::fmt;
@@ -8,19 +7,12 @@ use std::fmt;
 // Normal JSON-RPC response
 pub type Response = Result<Box<RawValue>, JsonRpcError>;
 
-#[derive(serde::Deserialize, serde::Serialize)]
-pub struct SubId(pub U256);
-
-impl SubId {
-	pub(super) fn serialize_raw(&self) -> Result<Box<RawValue>, serde_json::Error> {
-		to_raw_value(&self)
-	}
-}
-
-#[derive(Deserialize, Debug, Clone)]
+/// A notification for every subscription to `event`. neo-go nodes don't send the id of the
+/// subscription, so the notification is routed by event name and filter.
+#[derive(Debug, Clone)]
 pub struct Notification {
-	pub subscription: U256,
-	pub result: Box<RawValue>,
+	pub event: String,
+	pub params: Box<RawValue>,
 }
 
 #[derive(Debug, Clone)]
@@ -115,7 +107,7 @@ impl<'de> Deserialize<'de> for PubSubItem {
 								return Err(de::Error::duplicate_field("params"))
 							}
 
-							let value: Notification = map.next_value()?;
+							let value: Box<RawValue> = map.next_value()?;
 							params = Some(value);
 						},
 						key =>
@@ -138,8 +130,8 @@ impl<'de> Deserialize<'de> for PubSubItem {
 						Ok(PubSubItem::Error { id, error }),
 					(Some(id), Some(_), Some(error), None, None) =>
 						Ok(PubSubItem::Error { id, error }),
-					(None, None, None, Some(_), Some(params)) =>
-						Ok(PubSubItem::Notification { params }),
+					(None, None, None, Some(event), Some(params)) =>
+						Ok(PubSubItem::Notification { params: Notification { event, params } }),
 					_ => Err(de::Error::custom(
 						"response must be either a success/error or notification object",
 					)),
@@ -157,7 +149,7 @@ impl std::fmt::Display for PubSubItem {
 			PubSubItem::Success { id, .. } => write!(f, "Req success. ID: {id}"),
 			PubSubItem::Error { id, .. } => write!(f, "Req error. ID: {id}"),
 			PubSubItem::Notification { params } => {
-				write!(f, "Notification for sub: {:?}", params.subscription)
+				write!(f, "Notification for event: {}", params.event)
 			},
 		}
 	}
@@ -215,13 +207,15 @@ impl InFlight {
 #[derive(Debug)]
 pub(super) struct ActiveSub {
 	pub params: Box<RawValue>,
+	pub event: String,
+	pub filter: Option<SubscriptionFilter>,
 	pub channel: mpsc::UnboundedSender<Box<RawValue>>,
-	pub current_server_id: Option<U256>,
+	pub current_server_id: Option<String>,
 }
 
 impl ActiveSub {
 	pub(super) fn to_request(&self, id: u64) -> Request<'static, Box<RawValue>> {
-		Request::new(id, "neo_subscribe", self.params.clone())
+		Request::new(id, "subscribe", self.params.clone())
 	}
 
 	pub(super) fn serialize_raw(&self, id: u64) -> Result<Box<RawValue>, serde_json::Error> {
@@ -234,7 +228,7 @@ pub enum Instruction {
 	/// JSON-RPC request
 	Request { method: String, params: Box<RawValue>, sender: oneshot::Sender<Response> },
 	/// Cancel an existing subscription
-	Unsubscribe { id: U256 },
+	Unsubscribe { id: u64 },
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -297,5 +291,14 @@ mod test {
 	fn it_desers_pubsub_items() {
 		let a = "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"0xcd0c3e8af590364c09d0fa6a1210faf5\"}";
 		serde_json::from_str::<PubSubItem>(a).unwrap();
+
+		let b = r#"{"jsonrpc":"2.0","method":"block_added","params":[{"index":1}]}"#;
+		match serde_json::from_str::<PubSubItem>(b).unwrap() {
+			PubSubItem::Notification { params } => {
+				assert_eq!(params.event, "block_added");
+				assert_eq!(params.params.get(), r#"[{"index":1}]"#);
+			},
+			item => panic!("unexpected item: {item}"),
+		}
 	}
 }
@@ -4,5 +4,8 @@ pub use pending_transaction::PendingTransaction;
 mod pending_escalator;
 pub use pending_escalator::EscalatingPending;
 
+mod watcher;
+pub use watcher::WatchStream;
+
 pub mod call_raw;
 pub use call_raw::*;
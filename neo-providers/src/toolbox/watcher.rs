@@ -0,0 +1,360 @@
+use crate::{
+	core::responses::{neo_application_log::ApplicationLog, neo_block::NeoBlock},
+	JsonRpcClient, Middleware, NeoRetryPolicy, NotificationEvent, NotificationFilter, Provider,
+	ProviderError, RetryPolicy,
+};
+use futures_core::stream::Stream;
+use futures_timer::Delay;
+use futures_util::stream;
+use instant::Duration;
+use neo_types::vm_state::VMState;
+use primitive_types::H256;
+use std::{collections::VecDeque, pin::Pin};
+
+/// A stream returned by the `watch_*` methods of [`Provider`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type WatchStream<'a, T> = Pin<Box<dyn Stream<Item = Result<T, ProviderError>> + Send + 'a>>;
+/// A stream returned by the `watch_*` methods of [`Provider`].
+#[cfg(target_arch = "wasm32")]
+pub type WatchStream<'a, T> = Pin<Box<dyn Stream<Item = Result<T, ProviderError>> + 'a>>;
+
+/// Polls the node for the blocks following the last one it returned.
+///
+/// A block is final once it's in the chain, so every block is returned exactly once. Requests
+/// that fail transiently, e.g. because the node behind a load balancer lags behind the one that
+/// reported the block count, are retried after the polling interval. Other errors are returned,
+/// and the next call resumes where the failed one stopped.
+struct BlockPoller<'a, P> {
+	provider: &'a Provider<P>,
+	// The index of the next block to return, the current height if `None`
+	next: Option<u32>,
+	// The last block count reported by the node
+	count: u32,
+	interval: Duration,
+}
+
+impl<'a, P: JsonRpcClient> BlockPoller<'a, P> {
+	fn new(provider: &'a Provider<P>, start: Option<u32>) -> Self {
+		Self { provider, next: start, count: 0, interval: provider.get_interval() }
+	}
+
+	async fn next_block(&mut self) -> Result<NeoBlock, ProviderError> {
+		loop {
+			match self.next {
+				Some(next) if next < self.count =>
+					match self.provider.get_block_by_index(next, true).await {
+						Ok(block) => {
+							self.next = Some(next + 1);
+							return Ok(block)
+						},
+						Err(err) if is_transient(&err) =>
+							tracing::debug!(next, %err, "Failed to get block"),
+						Err(err) => return Err(err),
+					},
+				_ => match self.provider.get_block_count().await {
+					Ok(count) => {
+						let next = *self.next.get_or_insert(count);
+						if count > next {
+							self.count = count;
+							continue
+						}
+					},
+					Err(err) if is_transient(&err) =>
+						tracing::debug!(%err, "Failed to get block count"),
+					Err(err) => return Err(err),
+				},
+			}
+			Delay::new(self.interval).await;
+		}
+	}
+
+	async fn application_log(&self, tx_hash: H256) -> Result<ApplicationLog, ProviderError> {
+		loop {
+			match self.provider.get_application_log(tx_hash).await {
+				Ok(log) => return Ok(log),
+				Err(err) if is_transient(&err) =>
+					tracing::debug!(?tx_hash, %err, "Failed to get application log"),
+				Err(err) => return Err(err),
+			}
+			Delay::new(self.interval).await;
+		}
+	}
+}
+
+/// Whether a request that failed with `err` may succeed if it is sent again, see
+/// [`NeoRetryPolicy`].
+fn is_transient(err: &ProviderError) -> bool {
+	RetryPolicy::<ProviderError>::should_retry(&NeoRetryPolicy, err)
+}
+
+/// Polls the node for the notifications emitted by the transactions of each new block.
+struct NotificationPoller<'a, P> {
+	blocks: BlockPoller<'a, P>,
+	filter: NotificationFilter,
+	// Transactions of the last block whose application log wasn't fetched yet, with the index
+	// of the block
+	transactions: VecDeque<(u32, H256)>,
+	// Notifications of the last block, not yet returned
+	pending: VecDeque<(u32, NotificationEvent)>,
+}
+
+impl<'a, P: JsonRpcClient> NotificationPoller<'a, P> {
+	async fn next_notification(&mut self) -> Result<(u32, NotificationEvent), ProviderError> {
+		loop {
+			if let Some(notification) = self.pending.pop_front() {
+				return Ok(notification)
+			}
+			let Some(&(index, tx_hash)) = self.transactions.front() else {
+				let block = self.blocks.next_block().await?;
+				let index = block.index as u32;
+				let transactions = block.transactions.unwrap_or_default();
+				self.transactions.extend(transactions.into_iter().map(|tx| (index, tx.hash)));
+				continue
+			};
+			let log = self.blocks.application_log(tx_hash).await?;
+			self.transactions.pop_front();
+			// Like a node, only notifications of successful executions are sent
+			let notifications = log
+				.executions
+				.into_iter()
+				.filter(|execution| execution.state == VMState::Halt)
+				.flat_map(|execution| execution.notifications)
+				.filter(|notification| self.filter.matches(notification))
+				.map(|notification| {
+					(index, NotificationEvent { container: tx_hash, notification })
+				});
+			self.pending.extend(notifications);
+		}
+	}
+}
+
+impl<P: JsonRpcClient> Provider<P> {
+	/// Streams the blocks added to the chain from now on, with their transactions. Unlike
+	/// [`Provider::subscribe_blocks`], this works with any node as it polls `getblockcount` at
+	/// the interval of [`Provider::get_interval`].
+	///
+	/// Transient errors are retried, others are returned by the stream, which goes on with the
+	/// same block when polled again.
+	pub fn watch_blocks(&self) -> WatchStream<'_, NeoBlock> {
+		self.watch_blocks_since(None)
+	}
+
+	/// Streams the blocks from `index` on, e.g. to resume after the last block seen.
+	pub fn watch_blocks_from(&self, index: u32) -> WatchStream<'_, NeoBlock> {
+		self.watch_blocks_since(Some(index))
+	}
+
+	fn watch_blocks_since(&self, start: Option<u32>) -> WatchStream<'_, NeoBlock> {
+		Box::pin(stream::unfold(BlockPoller::new(self, start), |mut poller| async move {
+			let block = poller.next_block().await;
+			Some((block, poller))
+		}))
+	}
+
+	/// Streams the notifications matching `filter` emitted by the transactions of the blocks
+	/// added from now on, paired with the index of their block. Unlike
+	/// [`Provider::subscribe_notifications`], this works with any node as it fetches the
+	/// application log of every transaction. Notifications of block triggers aren't included.
+	///
+	/// Errors are handled like in [`Provider::watch_blocks`].
+	pub fn watch_notifications(
+		&self,
+		filter: NotificationFilter,
+	) -> WatchStream<'_, (u32, NotificationEvent)> {
+		self.watch_notifications_since(filter, None)
+	}
+
+	/// Streams the notifications matching `filter` from the block at `index` on, e.g. to resume
+	/// after the block of the last notification seen.
+	pub fn watch_notifications_from(
+		&self,
+		filter: NotificationFilter,
+		index: u32,
+	) -> WatchStream<'_, (u32, NotificationEvent)> {
+		self.watch_notifications_since(filter, Some(index))
+	}
+
+	fn watch_notifications_since(
+		&self,
+		filter: NotificationFilter,
+		start: Option<u32>,
+	) -> WatchStream<'_, (u32, NotificationEvent)> {
+		let poller = NotificationPoller {
+			blocks: BlockPoller::new(self, start),
+			filter,
+			transactions: VecDeque::new(),
+			pending: VecDeque::new(),
+		};
+		Box::pin(stream::unfold(poller, |mut poller| async move {
+			let notification = poller.next_notification().await;
+			Some((notification, poller))
+		}))
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use crate::{MockProvider, MockResponse, RpcError};
+	use futures_util::StreamExt;
+	use neo_config::DEFAULT_BLOCK_TIME;
+	use neo_types::util::parse_address;
+	use serde_json::{json, Value};
+
+	const CONTRACT: &str = "0xd2a4cff31913016155e38e474a2c06d08be276cf";
+
+	fn block(index: u32, txs: &[u8]) -> Value {
+		let txs: Vec<Value> = txs.iter().map(|tx| transaction(*tx)).collect();
+		json!({
+			"hash": format!("0x{}", "11".repeat(32)),
+			"size": 697,
+			"version": 0,
+			"previousblockhash": format!("0x{}", "22".repeat(32)),
+			"merkleroot": format!("0x{}", "33".repeat(32)),
+			"time": 1_700_000_000_000u64,
+			"index": index,
+			"primary": 0,
+			"nextconsensus": "NVg7LjGcUSrgxgjX3zEgqaksfMaiS8Z6e1",
+			"witnesses": [],
+			"tx": txs,
+		})
+	}
+
+	fn transaction(hash: u8) -> Value {
+		json!({
+			"hash": format!("0x{}", format!("{hash:02x}").repeat(32)),
+			"size": 250,
+			"version": 0,
+			"nonce": 1,
+			"sender": "NVg7LjGcUSrgxgjX3zEgqaksfMaiS8Z6e1",
+			"sysfee": "997775",
+			"netfee": "1234520",
+			"validuntilblock": 5000,
+			"signers": [],
+			"attributes": [],
+			"script": "",
+			"witnesses": [],
+		})
+	}
+
+	fn application_log(hash: u8, state: &str, events: &[&str]) -> Value {
+		let notifications: Vec<Value> = events
+			.iter()
+			.map(
+				|name| json!({ "contract": CONTRACT, "eventname": name, "state": { "type": "Any" } }),
+			)
+			.collect();
+		json!({
+			"txid": format!("0x{}", format!("{hash:02x}").repeat(32)),
+			"executions": [{
+				"trigger": "Application",
+				"vmstate": state,
+				"exception": null,
+				"gasconsumed": "997775",
+				"stack": [],
+				"notifications": notifications,
+			}],
+		})
+	}
+
+	/// A provider answering with `responses` in order.
+	fn mocked(responses: Vec<MockResponse>) -> Provider<MockProvider> {
+		let (provider, mock) = Provider::mocked();
+		// The mock answers with the last response pushed
+		responses.into_iter().rev().for_each(|response| mock.push_response(response));
+		provider.interval(Duration::from_millis(1))
+	}
+
+	fn error(code: i64, message: &str) -> MockResponse {
+		MockResponse::Error(crate::JsonRpcError { code, message: message.to_string(), data: None })
+	}
+
+	#[tokio::test]
+	async fn test_watch_blocks() {
+		let provider = mocked(vec![
+			// The stream starts at the current height
+			MockResponse::Value(json!(10)),
+			MockResponse::Value(json!(10)),
+			MockResponse::Value(json!(12)),
+			MockResponse::Value(block(10, &[])),
+			// A lagging node doesn't have the block yet
+			error(-100, "Unknown block"),
+			MockResponse::Value(block(11, &[])),
+		]);
+		let blocks: Vec<_> = provider.watch_blocks().take(2).collect().await;
+		let indexes: Vec<_> = blocks.into_iter().map(|block| block.unwrap().index).collect();
+		assert_eq!(indexes, vec![10, 11]);
+
+		let provider =
+			mocked(vec![MockResponse::Value(json!(12)), MockResponse::Value(block(5, &[]))]);
+		let blocks: Vec<_> = provider.watch_blocks_from(5).take(1).collect().await;
+		assert_eq!(blocks[0].as_ref().unwrap().index, 5);
+	}
+
+	#[tokio::test]
+	async fn test_watch_blocks_returns_errors() {
+		let provider = mocked(vec![
+			MockResponse::Value(json!(12)),
+			error(-32601, "Method not found"),
+			// The stream goes on with the block that failed
+			MockResponse::Value(block(5, &[])),
+		]);
+		let mut blocks = provider.watch_blocks_from(5);
+		let err = blocks.next().await.unwrap().unwrap_err();
+		assert_eq!(err.as_error_response().unwrap().code, -32601);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 5);
+	}
+
+	#[tokio::test]
+	async fn test_watch_notifications() {
+		let provider = mocked(vec![
+			MockResponse::Value(json!(8)),
+			MockResponse::Value(block(7, &[0xaa, 0xbb, 0xcc])),
+			MockResponse::Value(application_log(0xaa, "HALT", &["Transfer", "Approval"])),
+			MockResponse::Value(application_log(0xbb, "FAULT", &["Transfer"])),
+			MockResponse::Value(application_log(0xcc, "HALT", &["Transfer"])),
+		]);
+		let filter = NotificationFilter {
+			contract: Some(parse_address(CONTRACT)),
+			name: Some("Transfer".to_string()),
+		};
+		let notifications: Vec<_> =
+			provider.watch_notifications_from(filter, 7).take(2).collect().await;
+		let containers: Vec<_> = notifications
+			.iter()
+			.map(Result::as_ref)
+			.map(Result::unwrap)
+			.map(|(index, event)| (*index, event.container, event.notification.event_name.as_str()))
+			.collect();
+		assert_eq!(
+			containers,
+			vec![
+				(7, H256::repeat_byte(0xaa), "Transfer"),
+				(7, H256::repeat_byte(0xcc), "Transfer")
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_watch_notifications_returns_errors() {
+		let provider = mocked(vec![
+			MockResponse::Value(json!(8)),
+			MockResponse::Value(block(7, &[0xaa])),
+			error(-32603, "Internal error"),
+			// The application log that failed is fetched again
+			MockResponse::Value(application_log(0xaa, "HALT", &["Transfer"])),
+		]);
+		let mut notifications = provider.watch_notifications_from(NotificationFilter::default(), 7);
+		assert!(notifications.next().await.unwrap().is_err());
+		let (index, event) = notifications.next().await.unwrap().unwrap();
+		assert_eq!((index, event.container), (7, H256::repeat_byte(0xaa)));
+	}
+
+	#[tokio::test]
+	async fn test_default_config() {
+		let (provider, _) = Provider::mocked();
+		assert_eq!(provider.get_interval(), Duration::from_millis(DEFAULT_BLOCK_TIME));
+		assert_eq!(provider.config().polling_interval as u64, DEFAULT_BLOCK_TIME);
+	}
+}
@@ -86,7 +86,7 @@ pub fn private_key_to_address(private_key: &Secp256r1PrivateKey) -> String {
 /// Convert a script hash to an address.
 pub fn script_hash_to_address(script_hash: &ScriptHash) -> String {
 	let mut data = vec![DEFAULT_ADDRESS_VERSION];
-	data.extend_from_slice(&script_hash.0);
+	data.extend_from_slice(&script_hash.to_le_vec());
 	let mut sha = &data.hash256().hash256();
 	data.extend_from_slice(&sha[..4]);
 	bs58::encode(data).into_string()
@@ -135,3 +135,16 @@ pub fn hex_to_address(hex: &str) -> Result<String, ProviderError> {
 	let script_hash = H160::from_str(hex).map_err(|_| ProviderError::InvalidAddress)?;
 	Ok(script_hash.to_address())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_script_hash_to_address() {
+		let script_hash = H160::from_str("d6c712eb53b1a130f59fd4e5864bdac27458a509").unwrap();
+		let address = script_hash_to_address(&script_hash);
+		assert_eq!(address, "NLnyLtep7jwyq1qhNPkwXbJpurC4jUT8ke");
+		assert_eq!(address_to_script_hash(&address).unwrap(), script_hash);
+	}
+}
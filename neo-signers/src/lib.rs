@@ -5,6 +5,12 @@
 mod wallet;
 pub use wallet::*;
 
+mod remote;
+pub use remote::*;
+
+mod message;
+pub use message::*;
+
 /// Re-export the BIP-32 crate so that wordlists can be accessed conveniently.
 pub use coins_bip39;
 
@@ -0,0 +1,243 @@
+//! # Wallet message signing
+//!
+//! Helpers to sign and verify arbitrary messages in the format used by the `signMessage` APIs of
+//! NeoLine, O3 and Neon wallets, so that a signature produced by a browser wallet can be checked
+//! by a backend and vice versa.
+//!
+//! ## Format
+//!
+//! The signed payload is not the message itself but an envelope that mimics a transaction, which
+//! stops the signature from being replayed as a transaction witness:
+//!
+//! ```text
+//! 010001f0 || varint(len(salt + message)) || salt + message || 0000
+//! ```
+//!
+//! The salt is a random 16 byte value in hex, concatenated as text in front of the message. The
+//! "without salt" variant simply leaves it out. The envelope is signed with ECDSA over secp256r1
+//! and SHA-256, and the result is exchanged as JSON:
+//!
+//! ```json
+//! { "publicKey": "03...", "data": "<hex r || s>", "salt": "<hex>", "message": "Hello World" }
+//! ```
+//!
+//! ## Examples
+//!
+//! ```
+//! use neo_crypto::key_pair::KeyPair;
+//! use neo_signers::SignedMessage;
+//!
+//! let key_pair = KeyPair::new_random();
+//! let signed = SignedMessage::sign("Hello World", &key_pair).unwrap();
+//! assert!(signed.verify().is_ok());
+//!
+//! // the JSON can be handed to a dApp or read back from one
+//! let json = serde_json::to_string(&signed).unwrap();
+//! let parsed: SignedMessage = serde_json::from_str(&json).unwrap();
+//! assert!(parsed.verify().is_ok());
+//! ```
+
+use neo_codec::Encoder;
+use neo_crypto::{
+	error::CryptoError,
+	key_pair::KeyPair,
+	keys::{Secp256r1PublicKey, Secp256r1Signature},
+};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the message envelope
+const MESSAGE_PREFIX: [u8; 4] = [0x01, 0x00, 0x01, 0xf0];
+/// Suffix of the message envelope
+const MESSAGE_SUFFIX: [u8; 2] = [0x00, 0x00];
+/// Number of random bytes in a salt
+const SALT_LENGTH: usize = 16;
+
+/// A message signed in the NeoLine / O3 / Neon `signMessage` format.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedMessage {
+	/// Hex encoded compressed public key of the signer
+	pub public_key: String,
+	/// Hex encoded 64 byte `r || s` signature over the envelope
+	pub data: String,
+	/// Hex salt prepended to the message, `None` for the no-salt variant
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub salt: Option<String>,
+	/// The signed message
+	pub message: String,
+}
+
+impl SignedMessage {
+	/// Signs `message` with a freshly generated random salt.
+	pub fn sign(message: &str, key_pair: &KeyPair) -> Result<Self, CryptoError> {
+		let salt = hex::encode(rand::random::<[u8; SALT_LENGTH]>());
+		Self::sign_with_salt(message, &salt, key_pair)
+	}
+
+	/// Signs `message` with the given salt.
+	pub fn sign_with_salt(
+		message: &str,
+		salt: &str,
+		key_pair: &KeyPair,
+	) -> Result<Self, CryptoError> {
+		Self::create(message, Some(salt.to_string()), key_pair)
+	}
+
+	/// Signs `message` without a salt, like `signMessageWithoutSalt`.
+	pub fn sign_without_salt(message: &str, key_pair: &KeyPair) -> Result<Self, CryptoError> {
+		Self::create(message, None, key_pair)
+	}
+
+	fn create(
+		message: &str,
+		salt: Option<String>,
+		key_pair: &KeyPair,
+	) -> Result<Self, CryptoError> {
+		let envelope = message_envelope(message, salt.as_deref());
+		let signature = key_pair.private_key.sign_tx(&envelope)?;
+
+		Ok(Self {
+			public_key: key_pair.public_key.get_encoded_compressed_hex(),
+			data: hex::encode(signature.to_bytes()),
+			salt,
+			message: message.to_string(),
+		})
+	}
+
+	/// Returns the envelope that was signed.
+	pub fn envelope(&self) -> Vec<u8> {
+		message_envelope(&self.message, self.salt.as_deref())
+	}
+
+	/// Returns the public key of the signer.
+	pub fn signer(&self) -> Result<Secp256r1PublicKey, CryptoError> {
+		Secp256r1PublicKey::from_bytes(&hex::decode(&self.public_key)?)
+	}
+
+	/// Verifies the signature against the public key, salt and message.
+	pub fn verify(&self) -> Result<(), CryptoError> {
+		let signature = Secp256r1Signature::from_bytes(&hex::decode(&self.data)?)?;
+		self.signer()?.verify(&self.envelope(), &signature)
+	}
+}
+
+/// Builds the `010001f0 || varint || salt + message || 0000` envelope that wallets sign.
+pub fn message_envelope(message: &str, salt: Option<&str>) -> Vec<u8> {
+	let mut encoder = Encoder::new();
+	encoder.write_bytes(&MESSAGE_PREFIX);
+	encoder.write_var_string(&format!("{}{}", salt.unwrap_or_default(), message));
+	encoder.write_bytes(&MESSAGE_SUFFIX);
+	encoder.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_config::TestConstants;
+	use neo_crypto::keys::Secp256r1PrivateKey;
+
+	const SALT: &str = "f1bbe9a2e3c0c9f1f4b2c52a1df1b5a4";
+
+	// Signatures of `DEFAULT_ACCOUNT_PRIVATE_KEY` produced outside this crate: the envelope is
+	// built by hand following the format above, signed with OpenSSL's ECDSA over SHA-256 and
+	// normalized to a low `s`.
+	const SALTED_SIGNATURE: &str = "0efc69f42dbccee45739af740a685a4e8730361ba25bcd3cda6bb1926c2e11393202ac110acbf27dba7bce51c280b7b130b1bb5486062500015fb4b63f43f39a";
+	const UNSALTED_SIGNATURE: &str = "ef1b62d4ebd9a545c17aaa8c2b8cd0f04a2e9a2bbfb6623721cecd8d2b70fc7c757f33b2121c534a5eaa6e6d8cbd775b352facb737d688c138cfafdabdecece5";
+	const LONG_MESSAGE_SIGNATURE: &str = "d0a7175b5703e4edad584d36f25ca998fbfea947f529be452f3a4382a085e6200bcb9e5812de628ea77a750801a1f775a959b5285374a6b166220b0e6d3265ce";
+
+	fn key_pair() -> KeyPair {
+		KeyPair::from_secret_key(
+			&Secp256r1PrivateKey::from_bytes(
+				&hex::decode(TestConstants::DEFAULT_ACCOUNT_PRIVATE_KEY).unwrap(),
+			)
+			.unwrap(),
+		)
+	}
+
+	#[test]
+	fn test_message_envelope() {
+		assert_eq!(
+			hex::encode(message_envelope("Hello World", Some(SALT))),
+			"010001f02b663162626539613265336330633966316634623263353261316466316235613448656c6c6f20576f726c640000"
+		);
+		assert_eq!(
+			hex::encode(message_envelope("Hello World", None)),
+			"010001f00b48656c6c6f20576f726c640000"
+		);
+	}
+
+	#[test]
+	fn test_message_envelope_long_message() {
+		let message = "a".repeat(300);
+		let envelope = message_envelope(&message, None);
+		assert_eq!(&envelope[..7], &[0x01, 0x00, 0x01, 0xf0, 0xfd, 0x2c, 0x01]);
+		assert_eq!(envelope.len(), 4 + 3 + 300 + 2);
+	}
+
+	#[test]
+	fn test_verify_external_signature() {
+		let signed: SignedMessage = serde_json::from_str(&format!(
+			r#"{{
+				"publicKey": "{}",
+				"data": "{}",
+				"salt": "{}",
+				"message": "Hello World"
+			}}"#,
+			TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY,
+			SALTED_SIGNATURE,
+			SALT
+		))
+		.unwrap();
+		assert!(signed.verify().is_ok());
+	}
+
+	#[test]
+	fn test_verify_external_signature_without_salt() {
+		let signed = SignedMessage {
+			public_key: TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY.to_string(),
+			data: UNSALTED_SIGNATURE.to_string(),
+			salt: None,
+			message: "Hello World".to_string(),
+		};
+		assert!(signed.verify().is_ok());
+
+		let signed = SignedMessage {
+			data: LONG_MESSAGE_SIGNATURE.to_string(),
+			message: "a".repeat(300),
+			..signed
+		};
+		assert!(signed.verify().is_ok());
+	}
+
+	#[test]
+	fn test_sign_and_verify() {
+		let signed = SignedMessage::sign("Hello World", &key_pair()).unwrap();
+		assert_eq!(signed.public_key, TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY);
+		assert_eq!(signed.salt.as_ref().unwrap().len(), 2 * SALT_LENGTH);
+		assert!(signed.verify().is_ok());
+
+		let signed = SignedMessage::sign_without_salt("Hello World", &key_pair()).unwrap();
+		assert!(signed.salt.is_none());
+		assert!(signed.verify().is_ok());
+	}
+
+	#[test]
+	fn test_verify_tampered_message() {
+		let mut signed = SignedMessage::sign_with_salt("Hello World", SALT, &key_pair()).unwrap();
+		signed.message = "Hello World!".to_string();
+		assert!(signed.verify().is_err());
+
+		let mut signed = SignedMessage::sign_with_salt("Hello World", SALT, &key_pair()).unwrap();
+		signed.salt = None;
+		assert!(signed.verify().is_err());
+	}
+
+	#[test]
+	fn test_serialize() {
+		let signed = SignedMessage::sign_without_salt("Hello World", &key_pair()).unwrap();
+		let json = serde_json::to_value(&signed).unwrap();
+		assert_eq!(json["publicKey"], TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY);
+		assert_eq!(json["message"], "Hello World");
+		assert!(json.get("salt").is_none());
+	}
+}
@@ -0,0 +1,495 @@
+//! # Remote signer
+//!
+//! [`RemoteSigner`] implements [`Signer`] by forwarding every signing request to an external
+//! signing service over HTTP, so that private keys never have to live in the application process.
+//!
+//! ## Protocol
+//!
+//! Every operation is a single `POST` of a JSON object to the configured URL:
+//!
+//! ```json
+//! {
+//!   "type": "transaction",
+//!   "data": "4e454f33...",
+//!   "network": 860833102,
+//!   "scriptHash": "69ecca587293047be4c59159bf8bc399985c160d"
+//! }
+//! ```
+//!
+//! - `type` is either `"message"` or `"transaction"`.
+//! - `data` is the hex encoded payload to sign. For transactions this is
+//!   [`Transaction::get_hash_data`], for messages it is [`hash_message`] of the message.
+//! - `network` is the network magic the payload is bound to.
+//! - `scriptHash` is the hex encoded script hash of the account that should sign.
+//!
+//! The service signs `data` with ECDSA over secp256r1 and SHA-256 (the same operation as
+//! [`Secp256r1PrivateKey::sign_tx`](neo_crypto::keys::Secp256r1PrivateKey::sign_tx)) and replies
+//! with one of:
+//!
+//! ```json
+//! { "signature": "<hex r || s>", "publicKey": "<hex compressed key>" }
+//! { "witness": { "invocation": "<base64>", "verification": "<base64>" } }
+//! { "error": "reason the request was refused" }
+//! ```
+//!
+//! `publicKey` is optional for message signing. When it is present the signature is verified
+//! locally before it is returned. A witness can only be built from a bare signature if the public
+//! key is known.
+//!
+//! The service is not trusted to pick the account: the verification script of a returned witness,
+//! or the one built from `publicKey`, must hash to the requested `scriptHash`. The invocation
+//! script of a returned witness can not be checked without running it and is taken as is.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use neo_providers::Authorization;
+//! use neo_signers::{RemoteSigner, Signer};
+//! use primitive_types::H160;
+//! use url::Url;
+//!
+//! # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+//! let url = Url::parse("https://signer.internal/sign")?;
+//! let signer = RemoteSigner::new_with_auth(url, H160::zero(), Authorization::bearer("token"))?
+//! 	.with_network_magic(860833102u32);
+//!
+//! let signature = signer.sign_message("hello").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::Signer;
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine};
+use neo_config::NeoNetwork;
+use neo_crypto::{
+	error::CryptoError,
+	keys::{Secp256r1PublicKey, Secp256r1Signature},
+};
+use neo_providers::{
+	core::transaction::{
+		invocation_script::InvocationScript, transaction::Transaction,
+		transaction_error::TransactionError, verification_script::VerificationScript,
+		witness::Witness,
+	},
+	Authorization,
+};
+use neo_types::{address::Address, hash_message, script_hash::ScriptHashExtension};
+use primitive_types::H160;
+use reqwest::{header, header::HeaderValue, Client};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// The kind of payload a [`RemoteSignRequest`] carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteSignKind {
+	/// An arbitrary message, hashed with [`hash_message`]
+	Message,
+	/// The hash data of a transaction
+	Transaction,
+}
+
+/// Request body sent to the remote signing service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSignRequest {
+	/// What is being signed
+	#[serde(rename = "type")]
+	pub kind: RemoteSignKind,
+	/// Hex encoded payload to sign
+	pub data: String,
+	/// Network magic the payload is bound to
+	pub network: u32,
+	/// Hex encoded script hash of the signing account
+	pub script_hash: String,
+}
+
+/// A witness returned by the remote signing service, with base64 encoded scripts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteWitness {
+	/// Base64 encoded invocation script
+	pub invocation: String,
+	/// Base64 encoded verification script
+	pub verification: String,
+}
+
+/// Response body returned by the remote signing service
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSignResponse {
+	/// Hex encoded 64 byte `r || s` signature
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub signature: Option<String>,
+	/// Hex encoded public key that produced `signature`
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub public_key: Option<String>,
+	/// A complete witness, for services that build the scripts themselves
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub witness: Option<RemoteWitness>,
+	/// Reason the service refused to sign
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// Error thrown by the [`RemoteSigner`]
+#[derive(Debug, Error)]
+pub enum RemoteSignerError {
+	/// Thrown if the HTTP request failed
+	#[error(transparent)]
+	ReqwestError(#[from] reqwest::Error),
+	/// Thrown if the authorization header could not be built
+	#[error(transparent)]
+	InvalidHeader(#[from] header::InvalidHeaderValue),
+	/// Thrown if the service refused to sign
+	#[error("Remote signer rejected the request: {0}")]
+	Rejected(String),
+	/// Thrown if the service answered with neither a signature nor a witness
+	#[error("Remote signer returned an empty response")]
+	EmptyResponse,
+	/// Thrown if a witness is requested but the service did not return a public key
+	#[error("Remote signer did not return a public key")]
+	MissingPublicKey,
+	/// Thrown if the returned signature does not verify against the returned public key
+	#[error("Remote signer returned an invalid signature")]
+	InvalidSignature,
+	/// Thrown if the returned verification script is not the one of the requested account
+	#[error("Remote signer returned a witness for {actual:?} instead of {expected:?}")]
+	ScriptHashMismatch {
+		/// Script hash of the account the signer was created for
+		expected: H160,
+		/// Script hash of the returned verification script
+		actual: H160,
+	},
+	/// Error propagated from the hex crate.
+	#[error(transparent)]
+	HexError(#[from] hex::FromHexError),
+	/// Error propagated from the base64 crate.
+	#[error(transparent)]
+	Base64Error(#[from] base64::DecodeError),
+	/// Thrown if the returned signature or public key is malformed
+	#[error(transparent)]
+	CryptoError(#[from] CryptoError),
+	/// Thrown if the hash data of the transaction could not be built
+	#[error(transparent)]
+	TransactionError(#[from] TransactionError),
+}
+
+/// A [`Signer`] that delegates signing to a remote service.
+///
+/// See the [module documentation](self) for the wire protocol.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+	client: Client,
+	url: Url,
+	script_hash: H160,
+	network_magic: u32,
+}
+
+impl RemoteSigner {
+	/// Creates a signer for the account `script_hash` that posts to `url`
+	pub fn new(url: impl Into<Url>, script_hash: H160) -> Self {
+		Self::new_with_client(url, Client::new(), script_hash)
+	}
+
+	/// Creates a signer that authenticates against the service with `auth`
+	pub fn new_with_auth(
+		url: impl Into<Url>,
+		script_hash: H160,
+		auth: Authorization,
+	) -> Result<Self, RemoteSignerError> {
+		let mut auth_value = HeaderValue::from_str(&auth.to_string())?;
+		auth_value.set_sensitive(true);
+
+		let mut headers = header::HeaderMap::new();
+		headers.insert(header::AUTHORIZATION, auth_value);
+
+		let client = Client::builder().default_headers(headers).build()?;
+
+		Ok(Self::new_with_client(url, client, script_hash))
+	}
+
+	/// Creates a signer that uses the provided http client
+	pub fn new_with_client(url: impl Into<Url>, client: Client, script_hash: H160) -> Self {
+		Self { client, url: url.into(), script_hash, network_magic: NeoNetwork::MainNet.to_magic() }
+	}
+
+	/// The Url of the signing service
+	pub fn url(&self) -> &Url {
+		&self.url
+	}
+
+	/// The script hash of the account this signer signs for
+	pub fn script_hash(&self) -> H160 {
+		self.script_hash
+	}
+
+	async fn request(
+		&self,
+		kind: RemoteSignKind,
+		data: &[u8],
+		network: u32,
+	) -> Result<RemoteSignResponse, RemoteSignerError> {
+		let request = RemoteSignRequest {
+			kind,
+			data: hex::encode(data),
+			network,
+			script_hash: hex::encode(self.script_hash.as_bytes()),
+		};
+
+		let response: RemoteSignResponse = self
+			.client
+			.post(self.url.as_ref())
+			.json(&request)
+			.send()
+			.await?
+			.error_for_status()?
+			.json()
+			.await?;
+
+		match response.error {
+			Some(reason) => Err(RemoteSignerError::Rejected(reason)),
+			None => Ok(response),
+		}
+	}
+
+	/// Parses the signature of `response` and verifies it over `data` if a public key was returned
+	fn verified_signature(
+		response: &RemoteSignResponse,
+		data: &[u8],
+	) -> Result<(Secp256r1Signature, Option<Secp256r1PublicKey>), RemoteSignerError> {
+		let signature = response.signature.as_ref().ok_or(RemoteSignerError::EmptyResponse)?;
+		let signature = Secp256r1Signature::from_bytes(&hex::decode(signature)?)?;
+
+		let public_key = match &response.public_key {
+			Some(key) => {
+				let key = Secp256r1PublicKey::from_bytes(&hex::decode(key)?)?;
+				key.verify(data, &signature).map_err(|_| RemoteSignerError::InvalidSignature)?;
+				Some(key)
+			},
+			None => None,
+		};
+
+		Ok((signature, public_key))
+	}
+
+	/// Checks that `verification` is the verification script of the signing account
+	fn check_verification_script(&self, verification: &[u8]) -> Result<(), RemoteSignerError> {
+		let actual = H160::from_script(verification);
+		if actual != self.script_hash {
+			return Err(RemoteSignerError::ScriptHashMismatch { expected: self.script_hash, actual })
+		}
+		Ok(())
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Signer for RemoteSigner {
+	type Error = RemoteSignerError;
+
+	async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+		&self,
+		message: S,
+	) -> Result<Secp256r1Signature, Self::Error> {
+		let message_hash = hash_message(message.as_ref());
+		let data = message_hash.as_bytes();
+		let response = self.request(RemoteSignKind::Message, data, self.network_magic).await?;
+		let (signature, _) = Self::verified_signature(&response, data)?;
+		Ok(signature)
+	}
+
+	async fn get_witness(&self, tx: &Transaction) -> Result<Witness, Self::Error> {
+		let mut tx_with_chain = tx.clone();
+		if tx_with_chain.network_magic().is_none() {
+			// in the case we don't have a network_magic, let's use the signer network magic instead
+			tx_with_chain.set_network_magic(self.network_magic);
+		}
+		let network = tx_with_chain.network_magic().unwrap_or(self.network_magic);
+		let data = tx_with_chain.get_hash_data()?;
+
+		let response = self.request(RemoteSignKind::Transaction, &data, network).await?;
+
+		if let Some(witness) = &response.witness {
+			let verification = general_purpose::STANDARD.decode(&witness.verification)?;
+			self.check_verification_script(&verification)?;
+			return Ok(Witness::from_scripts(
+				general_purpose::STANDARD.decode(&witness.invocation)?,
+				verification,
+			))
+		}
+
+		let (signature, public_key) = Self::verified_signature(&response, &data)?;
+		let public_key = public_key.ok_or(RemoteSignerError::MissingPublicKey)?;
+		let verification = VerificationScript::from_public_key(&public_key);
+		self.check_verification_script(verification.script())?;
+
+		Ok(Witness::from_scripts_obj(InvocationScript::from_signature(&signature), verification))
+	}
+
+	fn address(&self) -> Address {
+		self.script_hash.to_address()
+	}
+
+	fn network_magic(&self) -> u32 {
+		self.network_magic
+	}
+
+	fn with_network_magic<T: Into<u32>>(mut self, network_magic: T) -> Self {
+		self.network_magic = network_magic.into();
+		self
+	}
+}
+
+#[cfg(test)]
+#[cfg(not(target_arch = "wasm32"))]
+mod tests {
+	use super::*;
+	use neo_config::TestConstants;
+	use neo_crypto::{key_pair::KeyPair, keys::Secp256r1PrivateKey};
+	use wiremock::{
+		matchers::{header, method, path},
+		Mock, MockServer, Request, Respond, ResponseTemplate,
+	};
+
+	/// A minimal signing service which signs every request with a fixed key
+	struct SigningService {
+		key_pair: KeyPair,
+	}
+
+	impl Respond for SigningService {
+		fn respond(&self, request: &Request) -> ResponseTemplate {
+			let request: RemoteSignRequest = serde_json::from_slice(&request.body).unwrap();
+			let data = hex::decode(&request.data).unwrap();
+			let signature = self.key_pair.private_key.sign_tx(&data).unwrap();
+
+			ResponseTemplate::new(200).set_body_json(RemoteSignResponse {
+				signature: Some(hex::encode(signature.to_bytes())),
+				public_key: Some(hex::encode(self.key_pair.public_key.get_encoded(true))),
+				..Default::default()
+			})
+		}
+	}
+
+	fn key_pair() -> KeyPair {
+		KeyPair::from_secret_key(
+			&Secp256r1PrivateKey::from_bytes(
+				&hex::decode(TestConstants::DEFAULT_ACCOUNT_PRIVATE_KEY).unwrap(),
+			)
+			.unwrap(),
+		)
+	}
+
+	async fn signing_server(key_pair: KeyPair) -> MockServer {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.and(path("/sign"))
+			.and(header("authorization", "Bearer secret-token"))
+			.respond_with(SigningService { key_pair })
+			.mount(&server)
+			.await;
+		server
+	}
+
+	fn remote_signer(server: &MockServer, key_pair: &KeyPair) -> RemoteSigner {
+		let url = Url::parse(&format!("{}/sign", server.uri())).unwrap();
+		let script_hash =
+			H160::from_script(VerificationScript::from_public_key(&key_pair.public_key).script());
+		RemoteSigner::new_with_auth(url, script_hash, Authorization::bearer("secret-token"))
+			.unwrap()
+			.with_network_magic(768u32)
+	}
+
+	#[tokio::test]
+	async fn test_sign_message() {
+		let key_pair = key_pair();
+		let server = signing_server(key_pair.clone()).await;
+		let signer = remote_signer(&server, &key_pair);
+
+		let message = "Some data";
+		let signature = signer.sign_message(message).await.unwrap();
+
+		let message_hash = hash_message(message.as_bytes());
+		assert!(key_pair.public_key.verify(message_hash.as_bytes(), &signature).is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_get_witness() {
+		let key_pair = key_pair();
+		let server = signing_server(key_pair.clone()).await;
+		let signer = remote_signer(&server, &key_pair);
+
+		let tx = Transaction::new();
+		let witness = signer.get_witness(&tx).await.unwrap();
+
+		assert_eq!(witness.verification, VerificationScript::from_public_key(&key_pair.public_key));
+
+		let requests = server.received_requests().await.unwrap();
+		let request: RemoteSignRequest = serde_json::from_slice(&requests[0].body).unwrap();
+		assert_eq!(request.kind, RemoteSignKind::Transaction);
+		assert_eq!(request.network, 768);
+		assert_eq!(request.script_hash, hex::encode(signer.script_hash().as_bytes()));
+	}
+
+	#[tokio::test]
+	async fn test_rejected_request() {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(ResponseTemplate::new(200).set_body_json(RemoteSignResponse {
+				error: Some("policy violation".to_string()),
+				..Default::default()
+			}))
+			.mount(&server)
+			.await;
+
+		let signer = RemoteSigner::new(Url::parse(&server.uri()).unwrap(), H160::zero());
+		let err = signer.sign_message("Some data").await.unwrap_err();
+		assert!(matches!(err, RemoteSignerError::Rejected(reason) if reason == "policy violation"));
+	}
+
+	#[tokio::test]
+	async fn test_witness_of_other_account() {
+		let key_pair = key_pair();
+		let verification = VerificationScript::from_public_key(&key_pair.public_key);
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(ResponseTemplate::new(200).set_body_json(RemoteSignResponse {
+				witness: Some(RemoteWitness {
+					invocation: general_purpose::STANDARD.encode([0x0c, 0x40]),
+					verification: general_purpose::STANDARD.encode(verification.script()),
+				}),
+				..Default::default()
+			}))
+			.mount(&server)
+			.await;
+
+		let signer = RemoteSigner::new(Url::parse(&server.uri()).unwrap(), H160::zero());
+		let err = signer.get_witness(&Transaction::new()).await.unwrap_err();
+		assert!(matches!(
+			err,
+			RemoteSignerError::ScriptHashMismatch { expected, actual }
+				if expected == H160::zero() && actual == H160::from_script(verification.script())
+		));
+
+		// A bare signature by another key is refused as well
+		let server = signing_server(key_pair.clone()).await;
+		let url = Url::parse(&format!("{}/sign", server.uri())).unwrap();
+		let signer =
+			RemoteSigner::new_with_auth(url, H160::zero(), Authorization::bearer("secret-token"))
+				.unwrap();
+		let err = signer.get_witness(&Transaction::new()).await.unwrap_err();
+		assert!(matches!(err, RemoteSignerError::ScriptHashMismatch { .. }));
+	}
+
+	#[tokio::test]
+	async fn test_unauthorized_request() {
+		let key_pair = key_pair();
+		let server = signing_server(key_pair.clone()).await;
+		let url = Url::parse(&format!("{}/sign", server.uri())).unwrap();
+
+		let signer = RemoteSigner::new(url, H160::zero());
+		let err = signer.sign_message("Some data").await.unwrap_err();
+		assert!(matches!(err, RemoteSignerError::ReqwestError(_)));
+	}
+}
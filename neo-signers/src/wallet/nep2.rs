@@ -59,6 +59,8 @@ use aes::{
 use crypto::scrypt::{scrypt, ScryptParams};
 use neo_crypto::{key_pair::KeyPair, keys::PrivateKeyExtension};
 use neo_providers::public_key_to_script_hash;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
 
 // const DKLEN: usize = 64;
 // const NEP2_PRIVATE_KEY_LENGTH: usize = 39;
@@ -91,13 +93,23 @@ impl NEP2 {
 		}
 		let address_hash = &nep2_data[3..7];
 		let encrypted = &nep2_data[7..39];
-		let derived_key =
-			Self::generate_derived_scrypt_key(password.as_bytes(), address_hash, params)?;
-		let decrypted_bytes = Self::perform_cipher(encrypted, &derived_key[32..], false)?;
-		let plain_private_key = xor(&derived_key[..32], &decrypted_bytes);
-		let key_pair = KeyPair::from_private_key(&plain_private_key.into())?;
+		let derived_key = Zeroizing::new(Self::generate_derived_scrypt_key(
+			password.as_bytes(),
+			address_hash,
+			params,
+		)?);
+		let decrypted_bytes =
+			Zeroizing::new(Self::perform_cipher(encrypted, &derived_key[32..], false)?);
+		let mut plain_private_key = Zeroizing::new([0u8; 32]);
+		for (byte, (x, y)) in plain_private_key
+			.iter_mut()
+			.zip(derived_key[..32].iter().zip(decrypted_bytes.iter()))
+		{
+			*byte = x ^ y;
+		}
+		let key_pair = KeyPair::from_private_key(&plain_private_key)?;
 		let new_address_hash = key_pair.get_address_hash()?;
-		if new_address_hash != address_hash {
+		if !bool::from(new_address_hash[..].ct_eq(address_hash)) {
 			return Err(CryptoError::InvalidPassphrase(
 				"Calculated address hash does not match the one in the provided encrypted address."
 					.to_string(),
@@ -113,18 +125,29 @@ impl NEP2 {
 		params: ScryptParams,
 	) -> Result<String, WalletError> {
 		let address_hash = public_key_to_script_hash(&key_pair.public_key);
-		let private_key = key_pair.private_key().to_vec();
-		let derived_key =
-			Self::generate_derived_scrypt_key(password.as_bytes(), &address_hash, params)?;
+		let private_key = key_pair.private_key.secret_bytes();
+		let derived_key = Zeroizing::new(Self::generate_derived_scrypt_key(
+			password.as_bytes(),
+			&address_hash,
+			params,
+		)?);
 		let derived_half1 = &derived_key[..32];
 		let derived_half2 = &derived_key[32..];
 		let encrypted_half1 = Self::perform_cipher(
-			&Self::xor_private_key_and_derived_half(private_key, derived_half1, 0..16),
+			&Zeroizing::new(Self::xor_private_key_and_derived_half(
+				private_key.as_slice(),
+				derived_half1,
+				0..16,
+			)),
 			derived_half2,
 			true,
 		)?;
 		let encrypted_half2 = Self::perform_cipher(
-			&Self::xor_private_key_and_derived_half(private_key, derived_half1, 16..32),
+			&Zeroizing::new(Self::xor_private_key_and_derived_half(
+				private_key.as_slice(),
+				derived_half1,
+				16..32,
+			)),
 			derived_half2,
 			true,
 		)?;
@@ -3,13 +3,13 @@ use crate::{
 	NEP6Account, NEP6Contract, NEP6Parameter, Signer,
 };
 use async_trait::async_trait;
-use neo_crypto::keys::Secp256r1Signature;
+use neo_crypto::keys::{Secp256r1PublicKey, Secp256r1Signature};
 use neo_providers::{
 	core::{
 		account::{Account, AccountTrait},
 		transaction::{
-			transaction::Transaction, verification_script::VerificationScript, witness,
-			witness::Witness,
+			invocation_script::InvocationScript, transaction::Transaction,
+			verification_script::VerificationScript, witness, witness::Witness,
 		},
 		wallet::WalletTrait,
 	},
@@ -19,6 +19,7 @@ use neo_types::{
 	address::{Address, AddressExtension},
 	address_or_scripthash::AddressOrScriptHash,
 	contract_parameter_type::ContractParameterType,
+	string::StringExt,
 	ScryptParamsDef, *,
 };
 use primitive_types::H160;
@@ -114,8 +115,15 @@ impl Wallet {
 	// Serialization methods
 
 	pub fn to_nep6(&self) -> Result<NEP6Wallet, WalletError> {
-		let accounts =
-			self.accounts.values().filter_map(|a| Wallet::from_account(a).ok()).collect();
+		let accounts = self
+			.accounts
+			.values()
+			.filter_map(|a| Wallet::from_account(a).ok())
+			.map(|mut a| {
+				a.is_default = a.address.to_script_hash().ok() == Some(self.default_account);
+				a
+			})
+			.collect();
 
 		Ok(NEP6Wallet {
 			name: self.name.clone(),
@@ -155,7 +163,11 @@ impl Wallet {
 			match nep6_account.contract {
 				Some(ref contract) if contract.script.is_some() => {
 					let script = contract.script.clone().unwrap();
-					let verification_script = VerificationScript::from(script.as_bytes().to_vec());
+					let verification_script = VerificationScript::from(
+						script
+							.base64_decoded()
+							.map_err(|e| WalletError::AccountState(e.to_string()))?,
+					);
 					let signing_threshold = if verification_script.is_multi_sig() {
 						Some(verification_script.get_signing_threshold().unwrap())
 					} else {
@@ -171,6 +183,9 @@ impl Wallet {
 				_ => (None, None, None),
 			};
 
+		let is_deployed_contract =
+			nep6_account.contract.as_ref().map(|c| c.is_deployed).unwrap_or_default();
+
 		Ok(Account {
 			address_or_scripthash: AddressOrScriptHash::Address(nep6_account.address.clone()),
 			label: nep6_account.label.clone(),
@@ -179,6 +194,7 @@ impl Wallet {
 			encrypted_private_key: nep6_account.key.clone(),
 			signing_threshold: signing_threshold.map(|x| x as u32),
 			nr_of_participants: nr_of_participants.map(|x| x as u32),
+			is_deployed_contract,
 			..Default::default()
 		})
 	}
@@ -205,9 +221,9 @@ impl Wallet {
 		let contract = match &account.verification_script {
 			Some(script) => {
 				let parameters = if script.is_multi_sig() {
+					// one signature parameter per required signer, like neo-cli
 					let threshold = script.get_signing_threshold().unwrap();
-					let nr_accounts = script.get_nr_of_accounts().unwrap();
-					(0..nr_accounts)
+					(0..threshold)
 						.map(|i| NEP6Parameter {
 							param_name: format!("signature{}", i),
 							param_type: ContractParameterType::Signature,
@@ -228,13 +244,16 @@ impl Wallet {
 					is_deployed: false,
 				})
 			},
+			// the parameters of `verify` are provided per transaction
+			None if account.is_deployed_contract =>
+				Some(NEP6Contract { script: None, nep6_parameters: vec![], is_deployed: true }),
 			None => None,
 		};
 
 		Ok(NEP6Account {
 			address: account.address_or_scripthash.address(),
 			label: account.label.clone(),
-			is_default: false, // TODO
+			is_default: false,
 			lock: account.is_locked,
 			key: account.encrypted_private_key.clone(),
 			contract,
@@ -269,6 +288,83 @@ impl Wallet {
 			account.encrypt_private_key(password);
 		}
 	}
+
+	/// Signs `tx` with every key of the multi-sig account `multi_sig` held (decrypted) in this
+	/// wallet, returning the signatures together with the public key that produced them.
+	///
+	/// Other participants can do the same with their own wallets, and the collected signatures are
+	/// combined into a witness with [`Wallet::sign_multi_sig`].
+	pub fn partial_multi_sig_signatures(
+		&self,
+		tx: &Transaction,
+		multi_sig: &H160,
+	) -> Result<Vec<(Secp256r1PublicKey, Secp256r1Signature)>, WalletError> {
+		let public_keys = self.multi_sig_account(multi_sig)?.multi_sig_public_keys()?;
+		let hash_data = self.hash_data(tx)?;
+
+		let mut signatures = vec![];
+		for key_pair in self.accounts.values().filter_map(|a| a.key_pair.as_ref()) {
+			if public_keys.contains(&key_pair.public_key) {
+				let signature = key_pair.private_key.sign_tx(&hash_data)?;
+				signatures.push((key_pair.public_key.clone(), signature));
+			}
+		}
+		Ok(signatures)
+	}
+
+	/// Builds the witness of the multi-sig account `multi_sig` for `tx`.
+	///
+	/// The transaction is signed with the participating keys held in this wallet, and
+	/// `collected_signatures` adds the signatures of participants whose keys are held elsewhere.
+	/// Signatures are ordered like the keys in the verification script and the first
+	/// `threshold` of them are used.
+	pub fn sign_multi_sig(
+		&self,
+		tx: &Transaction,
+		multi_sig: &H160,
+		collected_signatures: &[(Secp256r1PublicKey, Secp256r1Signature)],
+	) -> Result<Witness, WalletError> {
+		let account = self.multi_sig_account(multi_sig)?;
+		let public_keys = account.multi_sig_public_keys()?;
+		let threshold = account.get_signing_threshold()? as usize;
+
+		let mut signatures = self.partial_multi_sig_signatures(tx, multi_sig)?;
+		signatures.extend_from_slice(collected_signatures);
+
+		let ordered = public_keys
+			.iter()
+			.filter_map(|key| signatures.iter().find(|(k, _)| k == key).map(|(_, s)| s.clone()))
+			.take(threshold)
+			.collect::<Vec<_>>();
+		if ordered.len() < threshold {
+			return Err(WalletError::AccountState(format!(
+				"Multi-sig account needs {} signatures, only {} available",
+				threshold,
+				ordered.len()
+			)))
+		}
+
+		Ok(Witness::from_scripts_obj(
+			InvocationScript::from_signatures(&ordered),
+			account.verification_script.clone().unwrap(),
+		))
+	}
+
+	fn multi_sig_account(&self, multi_sig: &H160) -> Result<&Account, WalletError> {
+		self.accounts
+			.get(multi_sig)
+			.filter(|a| a.is_multi_sig())
+			.ok_or_else(|| WalletError::AccountState("Account is not MultiSig".to_string()))
+	}
+
+	fn hash_data(&self, tx: &Transaction) -> Result<Vec<u8>, WalletError> {
+		if tx.network_magic().is_none() {
+			return Err(WalletError::AccountState(
+				"Transaction network magic is not set".to_string(),
+			))
+		}
+		Ok(tx.get_hash_data()?)
+	}
 }
 
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
@@ -314,3 +410,149 @@ impl Signer for Wallet {
 		todo!()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_config::{NeoNetwork, TestConstants};
+	use neo_crypto::{key_pair::KeyPair, keys::Secp256r1PrivateKey};
+	use neo_types::script_hash::ScriptHashExtension;
+
+	const PRIVATE_KEY_2: &str = "0000000000000000000000000000000000000000000000000000000000001111";
+	const PUBLIC_KEY_3: &str = "038570e95d85825286db92c78317679bdd8ffe3c90d0af84291bf64132b66fcc99";
+
+	fn key_pair(private_key: &str) -> KeyPair {
+		KeyPair::from_secret_key(
+			&Secp256r1PrivateKey::from_bytes(&hex::decode(private_key).unwrap()).unwrap(),
+		)
+	}
+
+	fn multi_sig_wallet() -> (Wallet, Vec<KeyPair>, Account) {
+		let key_pairs =
+			vec![key_pair(TestConstants::DEFAULT_ACCOUNT_PRIVATE_KEY), key_pair(PRIVATE_KEY_2)];
+		let public_keys = vec![
+			key_pairs[0].public_key.clone(),
+			key_pairs[1].public_key.clone(),
+			Secp256r1PublicKey::from_encoded(PUBLIC_KEY_3).unwrap(),
+		];
+		let multi_sig = Account::multi_sig(&public_keys, 2).unwrap();
+
+		let mut wallet = Wallet::new();
+		wallet.add_account(multi_sig.clone());
+		wallet.set_default_account(multi_sig.get_script_hash());
+		(wallet, key_pairs, multi_sig)
+	}
+
+	fn transaction() -> Transaction {
+		let mut tx = Transaction::new();
+		tx.set_network_magic(NeoNetwork::TestNet.to_magic());
+		tx
+	}
+
+	#[test]
+	fn test_nep6_contract_script() {
+		// NEP-6 files hold the verification script in base64
+		let nep6_account = NEP6Account {
+			address: TestConstants::DEFAULT_ACCOUNT_ADDRESS.to_string(),
+			label: None,
+			is_default: true,
+			lock: false,
+			key: None,
+			contract: Some(NEP6Contract {
+				script: Some(
+					"DCEDOk0FGwS3/AIw0rGq7f1ahL4nmlNhpzWNtmWteFd4fxtBVuezJw==".to_string(),
+				),
+				nep6_parameters: vec![],
+				is_deployed: false,
+			}),
+			extra: None,
+		};
+
+		let account = Wallet::to_account(&nep6_account).unwrap();
+		let script = account.verification_script.unwrap();
+		assert_eq!(
+			script.script(),
+			&hex::decode(TestConstants::DEFAULT_ACCOUNT_VERIFICATION_SCRIPT).unwrap()
+		);
+		assert!(script.is_single_sig());
+	}
+
+	#[test]
+	fn test_nep6_multi_sig_round_trip() {
+		let (wallet, _, multi_sig) = multi_sig_wallet();
+
+		let nep6 = wallet.to_nep6().unwrap();
+		let json = serde_json::to_string(&nep6).unwrap();
+		let nep6: NEP6Wallet = serde_json::from_str(&json).unwrap();
+
+		let account = &nep6.accounts()[0];
+		assert!(account.is_default);
+		let contract = account.contract.as_ref().unwrap();
+		assert_eq!(contract.nep6_parameters.len(), 2);
+		assert_eq!(contract.nep6_parameters[1].param_name, "signature1");
+
+		let wallet = Wallet::from_nep6(nep6).unwrap();
+		let account = wallet.get_account(&multi_sig.get_script_hash()).unwrap();
+		assert_eq!(account.verification_script, multi_sig.verification_script);
+		assert_eq!(account.get_signing_threshold().unwrap(), 2);
+		assert_eq!(account.get_nr_of_participants().unwrap(), 3);
+	}
+
+	#[test]
+	fn test_nep6_contract_account_round_trip() {
+		let hash = H160::from_hex(TestConstants::COMMITTEE_ACCOUNT_SCRIPT_HASH).unwrap();
+		let mut wallet = Wallet::new();
+		wallet.add_account(Account::from_contract(&hash));
+		wallet.set_default_account(hash);
+
+		let nep6 = wallet.to_nep6().unwrap();
+		let contract = nep6.accounts()[0].contract.as_ref().unwrap();
+		assert!(contract.is_deployed);
+		assert!(contract.script.is_none());
+
+		let wallet = Wallet::from_nep6(nep6).unwrap();
+		let account = wallet.get_account(&hash).unwrap();
+		assert!(account.is_deployed_contract);
+		assert!(account.verification_script.is_none());
+	}
+
+	#[test]
+	fn test_sign_multi_sig() {
+		let (mut wallet, key_pairs, multi_sig) = multi_sig_wallet();
+		let tx = transaction();
+
+		// one key is not enough for a 2-of-3 account
+		wallet.add_account(Account::from_key_pair(key_pairs[0].clone(), None, None).unwrap());
+		assert_eq!(
+			wallet
+				.partial_multi_sig_signatures(&tx, &multi_sig.get_script_hash())
+				.unwrap()
+				.len(),
+			1
+		);
+		assert!(wallet.sign_multi_sig(&tx, &multi_sig.get_script_hash(), &[]).is_err());
+
+		// the second signature is collected from another wallet
+		let signature = key_pairs[1].private_key.sign_tx(&tx.get_hash_data().unwrap()).unwrap();
+		let witness = wallet
+			.sign_multi_sig(
+				&tx,
+				&multi_sig.get_script_hash(),
+				&[(key_pairs[1].public_key.clone(), signature)],
+			)
+			.unwrap();
+
+		assert_eq!(&witness.verification, multi_sig.verification_script.as_ref().unwrap());
+		// signatures follow the key order of the verification script
+		let invocation: &[u8] = &witness.invocation;
+		assert_eq!(invocation.len(), 2 * 66);
+		let hash_data = tx.get_hash_data().unwrap();
+		for (i, public_key) in
+			[&key_pairs[1].public_key, &key_pairs[0].public_key].iter().enumerate()
+		{
+			let signature =
+				Secp256r1Signature::from_bytes(&invocation[i * 66 + 2..(i + 1) * 66]).unwrap();
+			assert!(public_key.verify(&hash_data, &signature).is_ok());
+		}
+	}
+}
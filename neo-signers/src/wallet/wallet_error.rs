@@ -33,4 +33,6 @@ pub enum WalletError {
 	CryptoError(#[from] neo_crypto::error::CryptoError),
 	#[error(transparent)]
 	TransactionError(#[from] TransactionError),
+	#[error(transparent)]
+	ProviderError(#[from] neo_providers::ProviderError),
 }
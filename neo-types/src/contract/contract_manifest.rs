@@ -1,9 +1,13 @@
 use crate::{
 	contract_parameter::ContractParameter, contract_parameter_type::ContractParameterType,
+	error::TypeError, script_hash::ScriptHashExtension,
 };
+use base64::{engine::general_purpose, Engine};
+use neo_crypto::keys::{Secp256r1PublicKey, Secp256r1Signature};
+use primitive_types::H160;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	hash::{Hash, Hasher},
 };
 
@@ -15,7 +19,7 @@ pub struct ContractManifest {
 	pub name: Option<String>,
 	#[serde(default)]
 	pub groups: Vec<ContractGroup>,
-	#[serde(skip_serializing)]
+	#[serde(serialize_with = "serialize_features")]
 	pub features: Option<HashMap<String, serde_json::Value>>,
 	#[serde(default)]
 	#[serde(serialize_with = "serialize_wildcard")]
@@ -26,7 +30,7 @@ pub struct ContractManifest {
 	pub abi: Option<ContractABI>,
 	#[serde(default)]
 	pub permissions: Vec<ContractPermission>,
-	#[serde(skip_serializing)]
+	#[serde(default)]
 	#[serde(serialize_with = "serialize_wildcard")]
 	#[serde(deserialize_with = "deserialize_wildcard")]
 	pub trusts: Vec<String>,
@@ -63,6 +67,7 @@ impl Hash for ContractManifest {
 
 #[derive(Serialize, Deserialize, Eq, PartialEq, Hash, Debug, Clone)]
 pub struct ContractGroup {
+	#[serde(rename = "pubkey")]
 	pub pub_key: String,
 	pub signature: String,
 }
@@ -79,6 +84,7 @@ pub struct ContractMethod {
 	pub name: String,
 	pub parameters: Vec<ContractParameter>,
 	pub offset: usize,
+	#[serde(rename = "returntype")]
 	pub return_type: ContractParameterType,
 	pub safe: bool,
 }
@@ -96,3 +102,618 @@ pub struct ContractPermission {
 	#[serde(deserialize_with = "deserialize_wildcard")]
 	pub methods: Vec<String>,
 }
+
+impl ContractManifest {
+	/// Maximum size of the serialized manifest, the node's `MaxItemSize`.
+	pub const MAX_SIZE: usize = u16::MAX as usize;
+	const WILDCARD: &'static str = "*";
+
+	/// Checks the manifest against the rules the node enforces on deploy, so a bad manifest is
+	/// rejected before paying the deployment fees.
+	///
+	/// The methods the node and the standards call by name have a fixed shape: `_deploy(data,
+	/// update)` and `_initialize()` return nothing, `verify` returns a boolean and the
+	/// `onNEP17Payment`/`onNEP11Payment` callbacks take the payment and return nothing. `_deploy`
+	/// runs on deploy and update, so it can't be safe. Parameters the node passes as `Any` may be
+	/// declared with any type. Only the shape is checked, not that the code at `offset` matches.
+	///
+	/// Group signatures depend on the contract hash and are checked by
+	/// [`ContractManifest::verify_groups`].
+	pub fn validate(&self) -> Result<(), TypeError> {
+		let json = serde_json::to_value(self).map_err(|e| invalid(e.to_string()))?;
+		let size = json.to_string().len();
+		if size > Self::MAX_SIZE {
+			return Err(invalid(format!("size {} exceeds {} bytes", size, Self::MAX_SIZE)))
+		}
+		if self.name.as_deref().map_or(true, str::is_empty) {
+			return Err(invalid("name is empty".to_string()))
+		}
+		// The node rejects a manifest without `features` as well as one with any feature.
+		if json.get("features") != Some(&serde_json::json!({})) {
+			return Err(invalid("features must be an empty object".to_string()))
+		}
+
+		let mut keys = HashSet::new();
+		for group in &self.groups {
+			let key = hex::decode(&group.pub_key)
+				.ok()
+				.and_then(|key| Secp256r1PublicKey::from_bytes(&key).ok())
+				.ok_or_else(|| invalid(format!("invalid group public key {}", group.pub_key)))?;
+			if !keys.insert(key.get_encoded(true)) {
+				return Err(invalid(format!("duplicate group {}", group.pub_key)))
+			}
+		}
+
+		let mut standards = HashSet::new();
+		for standard in &self.supported_standards {
+			if standard.is_empty() || !standards.insert(standard) {
+				return Err(invalid(format!("invalid supported standard '{}'", standard)))
+			}
+		}
+
+		self.abi
+			.as_ref()
+			.ok_or_else(|| invalid("abi is missing".to_string()))?
+			.validate()?;
+
+		let mut contracts = HashSet::new();
+		for permission in &self.permissions {
+			Self::validate_descriptor(&permission.contract, true)?;
+			if !contracts.insert(&permission.contract) {
+				return Err(invalid(format!("duplicate permission for {}", permission.contract)))
+			}
+			if permission.methods != [Self::WILDCARD] {
+				let mut methods = HashSet::new();
+				for method in &permission.methods {
+					if method.is_empty() || method == Self::WILDCARD || !methods.insert(method) {
+						return Err(invalid(format!("invalid permitted method '{}'", method)))
+					}
+				}
+			}
+		}
+
+		if self.trusts != [Self::WILDCARD] {
+			let mut trusts = HashSet::new();
+			for trust in &self.trusts {
+				Self::validate_descriptor(trust, false)?;
+				if !trusts.insert(trust) {
+					return Err(invalid(format!("duplicate trust {}", trust)))
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Verifies that every group signed `contract_hash`, the hash the contract will be deployed
+	/// at, which is derived from the sender, the NEF checksum and the manifest name.
+	pub fn verify_groups(&self, contract_hash: &H160) -> Result<(), TypeError> {
+		let message = contract_hash.to_le_vec();
+		for group in &self.groups {
+			let key = hex::decode(&group.pub_key)
+				.ok()
+				.and_then(|key| Secp256r1PublicKey::from_bytes(&key).ok())
+				.ok_or_else(|| invalid(format!("invalid group public key {}", group.pub_key)))?;
+			let signature = general_purpose::STANDARD
+				.decode(&group.signature)
+				.ok()
+				.and_then(|signature| Secp256r1Signature::from_bytes(&signature).ok())
+				.ok_or_else(|| invalid(format!("invalid signature of group {}", group.pub_key)))?;
+			key.verify(&message, &signature).map_err(|_| {
+				invalid(format!("group {} didn't sign the contract", group.pub_key))
+			})?;
+		}
+		Ok(())
+	}
+
+	/// Checks a permission contract or trust, which is a contract hash, a group public key or,
+	/// for permissions only, the wildcard.
+	fn validate_descriptor(descriptor: &str, allow_wildcard: bool) -> Result<(), TypeError> {
+		let valid = match descriptor.len() {
+			1 => allow_wildcard && descriptor == Self::WILDCARD,
+			42 => descriptor.starts_with("0x") && H160::from_hex(descriptor).is_ok(),
+			66 => hex::decode(descriptor)
+				.map_or(false, |key| Secp256r1PublicKey::from_bytes(&key).is_ok()),
+			_ => false,
+		};
+		match valid {
+			true => Ok(()),
+			false => Err(invalid(format!("invalid contract descriptor '{}'", descriptor))),
+		}
+	}
+}
+
+impl ContractABI {
+	fn validate(&self) -> Result<(), TypeError> {
+		if self.methods.is_empty() {
+			return Err(invalid("abi has no methods".to_string()))
+		}
+
+		let mut methods = HashSet::new();
+		for method in &self.methods {
+			if method.name.is_empty() || !methods.insert((&method.name, method.parameters.len())) {
+				return Err(invalid(format!("invalid or duplicate method '{}'", method.name)))
+			}
+			validate_parameters(&method.name, &method.parameters)?;
+			method.validate_reserved()?;
+		}
+
+		let mut events = HashSet::new();
+		for event in self.events.iter().flatten() {
+			if event.name.is_empty() || !events.insert(&event.name) {
+				return Err(invalid(format!("invalid or duplicate event '{}'", event.name)))
+			}
+			validate_parameters(&event.name, &event.parameters)?;
+		}
+		Ok(())
+	}
+}
+
+impl ContractMethod {
+	/// The methods called by name, with the types of their parameters, if fixed, and of their
+	/// result.
+	const RESERVED: &'static [(
+		&'static str,
+		Option<&'static [ContractParameterType]>,
+		ContractParameterType,
+	)] = &[
+		(
+			"_deploy",
+			Some(&[ContractParameterType::Any, ContractParameterType::Boolean]),
+			ContractParameterType::Void,
+		),
+		("_initialize", Some(&[]), ContractParameterType::Void),
+		("verify", None, ContractParameterType::Boolean),
+		(
+			"onNEP17Payment",
+			Some(&[
+				ContractParameterType::H160,
+				ContractParameterType::Integer,
+				ContractParameterType::Any,
+			]),
+			ContractParameterType::Void,
+		),
+		(
+			"onNEP11Payment",
+			Some(&[
+				ContractParameterType::H160,
+				ContractParameterType::Integer,
+				ContractParameterType::ByteArray,
+				ContractParameterType::Any,
+			]),
+			ContractParameterType::Void,
+		),
+	];
+
+	/// Checks that a method called by name has the shape it's called with.
+	fn validate_reserved(&self) -> Result<(), TypeError> {
+		let Some((_, parameters, return_type)) =
+			Self::RESERVED.iter().find(|(name, ..)| *name == self.name)
+		else {
+			return Ok(())
+		};
+		if let Some(parameters) = parameters {
+			if self.parameters.len() != parameters.len() {
+				return Err(invalid(format!(
+					"'{}' must have {} parameters",
+					self.name,
+					parameters.len()
+				)))
+			}
+			for (parameter, expected) in self.parameters.iter().zip(parameters.iter()) {
+				if *expected != ContractParameterType::Any && parameter.get_type() != *expected {
+					let name = parameter.get_name().unwrap_or_default();
+					return Err(invalid(format!(
+						"parameter '{}' of '{}' must be {}",
+						name, self.name, expected
+					)))
+				}
+			}
+		}
+		if self.return_type != *return_type {
+			return Err(invalid(format!("'{}' must return {}", self.name, return_type)))
+		}
+		if self.name == "_deploy" && self.safe {
+			return Err(invalid("'_deploy' can't be safe".to_string()))
+		}
+		Ok(())
+	}
+}
+
+/// Parameters need distinct names and a type that can hold a value.
+fn validate_parameters(owner: &str, parameters: &[ContractParameter]) -> Result<(), TypeError> {
+	let mut names = HashSet::new();
+	for parameter in parameters {
+		let name = parameter.get_name().unwrap_or_default();
+		if name.is_empty() || !names.insert(name) {
+			return Err(invalid(format!("invalid or duplicate parameter '{}' of '{}'", name, owner)))
+		}
+		if parameter.get_type() == ContractParameterType::Void {
+			return Err(invalid(format!("parameter '{}' of '{}' can't be Void", name, owner)))
+		}
+	}
+	Ok(())
+}
+
+fn invalid(message: String) -> TypeError {
+	TypeError::InvalidManifest(message)
+}
+
+/// Serializes missing features as the empty object the node expects.
+fn serialize_features<S>(
+	features: &Option<HashMap<String, serde_json::Value>>,
+	serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+	S: Serializer,
+{
+	match features {
+		Some(features) => features.serialize(serializer),
+		None => HashMap::<String, serde_json::Value>::new().serialize(serializer),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use neo_crypto::key_pair::KeyPair;
+
+	fn manifest() -> ContractManifest {
+		ContractManifest {
+			name: Some("Token".to_string()),
+			groups: vec![],
+			features: None,
+			supported_standards: vec!["NEP-17".to_string()],
+			abi: Some(ContractABI {
+				methods: vec![
+					ContractMethod {
+						name: "_deploy".to_string(),
+						parameters: vec![
+							ContractParameter::with_name("data", ContractParameterType::Any),
+							ContractParameter::with_name("update", ContractParameterType::Boolean),
+						],
+						offset: 0,
+						return_type: ContractParameterType::Void,
+						safe: false,
+					},
+					ContractMethod {
+						name: "balanceOf".to_string(),
+						parameters: vec![ContractParameter::with_name(
+							"account",
+							ContractParameterType::H160,
+						)],
+						offset: 10,
+						return_type: ContractParameterType::Integer,
+						safe: true,
+					},
+				],
+				events: Some(vec![ContractEvent {
+					name: "Transfer".to_string(),
+					parameters: vec![
+						ContractParameter::with_name("from", ContractParameterType::H160),
+						ContractParameter::with_name("to", ContractParameterType::H160),
+						ContractParameter::with_name("amount", ContractParameterType::Integer),
+					],
+				}]),
+			}),
+			permissions: vec![ContractPermission {
+				contract: "*".to_string(),
+				methods: vec!["onNEP17Payment".to_string()],
+			}],
+			trusts: vec![],
+			extra: None,
+		}
+	}
+
+	fn methods(manifest: &mut ContractManifest) -> &mut Vec<ContractMethod> {
+		&mut manifest.abi.as_mut().unwrap().methods
+	}
+
+	fn method(
+		name: &str,
+		parameters: &[(&str, ContractParameterType)],
+		return_type: ContractParameterType,
+	) -> ContractMethod {
+		ContractMethod {
+			name: name.to_string(),
+			parameters: parameters
+				.iter()
+				.map(|(name, typ)| ContractParameter::with_name(name, typ.clone()))
+				.collect(),
+			offset: 20,
+			return_type,
+			safe: false,
+		}
+	}
+
+	#[test]
+	fn test_validate() {
+		manifest().validate().unwrap();
+
+		let mut manifest = manifest();
+		manifest.trusts = vec![
+			"0x23ba2703c53263e8d6e522dc32203339dcd8eee9".to_string(),
+			"033a4d051b04b7fc0230d2b1aaedfd5a84be279a5361a7358db665ad7857787f1b".to_string(),
+		];
+		manifest.permissions[0].methods = vec!["*".to_string()];
+		manifest.validate().unwrap();
+	}
+
+	#[test]
+	fn test_validate_rejects_invalid_manifest() {
+		let invalid: Vec<(fn(&mut ContractManifest), &str)> = vec![
+			(|m| m.name = Some(String::new()), "name is empty"),
+			(
+				|m| m.features = Some(HashMap::from([("storage".to_string(), true.into())])),
+				"features must be an empty object",
+			),
+			(
+				|m| m.supported_standards.push("NEP-17".to_string()),
+				"invalid supported standard 'NEP-17'",
+			),
+			(|m| m.abi = None, "abi is missing"),
+			(|m| methods(m).clear(), "abi has no methods"),
+			(
+				|m| {
+					let method = methods(m)[1].clone();
+					methods(m).push(method)
+				},
+				"invalid or duplicate method 'balanceOf'",
+			),
+			(
+				|m| {
+					methods(m)[1].parameters.push(ContractParameter::with_name(
+						"account",
+						ContractParameterType::Integer,
+					))
+				},
+				"invalid or duplicate parameter 'account' of 'balanceOf'",
+			),
+			(
+				|m| {
+					methods(m)[1].parameters[0] =
+						ContractParameter::new(ContractParameterType::H160)
+				},
+				"invalid or duplicate parameter '' of 'balanceOf'",
+			),
+			(
+				|m| {
+					methods(m)[1].parameters[0] =
+						ContractParameter::with_name("account", ContractParameterType::Void)
+				},
+				"parameter 'account' of 'balanceOf' can't be Void",
+			),
+			(
+				|m| {
+					let events = m.abi.as_mut().unwrap().events.as_mut().unwrap();
+					events.push(events[0].clone())
+				},
+				"invalid or duplicate event 'Transfer'",
+			),
+			(|m| m.permissions.push(m.permissions[0].clone()), "duplicate permission for *"),
+			(
+				|m| m.permissions[0].contract = "0x1234".to_string(),
+				"invalid contract descriptor '0x1234'",
+			),
+			(
+				|m| m.permissions[0].methods = vec!["*".to_string(), "transfer".to_string()],
+				"invalid permitted method '*'",
+			),
+			(
+				|m| m.trusts = vec!["0x23ba2703c53263e8d6e522dc32203339dcd8eee9".to_string(); 2],
+				"duplicate trust 0x23ba2703c53263e8d6e522dc32203339dcd8eee9",
+			),
+			(
+				|m| m.trusts = vec!["*".to_string(), "*".to_string()],
+				"invalid contract descriptor '*'",
+			),
+			(
+				|m| m.extra = Some(HashMap::from([("data".to_string(), "x".repeat(70000).into())])),
+				"exceeds 65535 bytes",
+			),
+			(
+				|m| {
+					m.groups
+						.push(ContractGroup { pub_key: "00".repeat(33), signature: String::new() })
+				},
+				"invalid group public key",
+			),
+			(|m| methods(m)[0].safe = true, "'_deploy' can't be safe"),
+			(
+				|m| {
+					methods(m)[0].parameters[1] =
+						ContractParameter::with_name("update", ContractParameterType::Integer)
+				},
+				"parameter 'update' of '_deploy' must be Boolean",
+			),
+			(
+				|m| methods(m)[0].return_type = ContractParameterType::Boolean,
+				"'_deploy' must return Void",
+			),
+			(
+				|m| {
+					methods(m).push(method(
+						"_initialize",
+						&[("data", ContractParameterType::Any)],
+						ContractParameterType::Void,
+					))
+				},
+				"'_initialize' must have 0 parameters",
+			),
+			(
+				|m| methods(m).push(method("verify", &[], ContractParameterType::Void)),
+				"'verify' must return Boolean",
+			),
+			(
+				|m| {
+					methods(m).push(method(
+						"onNEP17Payment",
+						&[
+							("from", ContractParameterType::ByteArray),
+							("amount", ContractParameterType::Integer),
+							("data", ContractParameterType::Any),
+						],
+						ContractParameterType::Void,
+					))
+				},
+				"parameter 'from' of 'onNEP17Payment' must be H160",
+			),
+			(
+				|m| {
+					methods(m).push(method(
+						"onNEP11Payment",
+						&[
+							("from", ContractParameterType::H160),
+							("amount", ContractParameterType::Integer),
+							("data", ContractParameterType::Any),
+						],
+						ContractParameterType::Void,
+					))
+				},
+				"'onNEP11Payment' must have 4 parameters",
+			),
+		];
+		for (i, (modify, expected)) in invalid.into_iter().enumerate() {
+			let mut manifest = manifest();
+			modify(&mut manifest);
+			match manifest.validate() {
+				Err(TypeError::InvalidManifest(message)) => assert!(
+					message.contains(expected),
+					"case {}: expected '{}', got '{}'",
+					i,
+					expected,
+					message
+				),
+				result => panic!("case {} should be invalid, got {:?}", i, result),
+			}
+		}
+	}
+
+	#[test]
+	fn test_validate_reserved_methods() {
+		let mut manifest = manifest();
+		methods(&mut manifest).extend([
+			method("_initialize", &[], ContractParameterType::Void),
+			method(
+				"verify",
+				&[("signer", ContractParameterType::H160)],
+				ContractParameterType::Boolean,
+			),
+			// The node passes `data` as Any, so any declared type is accepted
+			method(
+				"onNEP17Payment",
+				&[
+					("from", ContractParameterType::H160),
+					("amount", ContractParameterType::Integer),
+					("data", ContractParameterType::String),
+				],
+				ContractParameterType::Void,
+			),
+			method(
+				"onNEP11Payment",
+				&[
+					("from", ContractParameterType::H160),
+					("amount", ContractParameterType::Integer),
+					("tokenId", ContractParameterType::ByteArray),
+					("data", ContractParameterType::Any),
+				],
+				ContractParameterType::Void,
+			),
+		]);
+		manifest.validate().unwrap();
+	}
+
+	#[test]
+	fn test_serialized_features() {
+		let json = serde_json::to_value(manifest()).unwrap();
+		assert_eq!(json["features"], serde_json::json!({}));
+
+		let mut manifest = manifest();
+		manifest.features = Some(HashMap::new());
+		manifest.validate().unwrap();
+	}
+
+	#[test]
+	fn test_wildcards_round_trip() {
+		let mut manifest = manifest();
+		manifest.abi = None;
+		manifest
+			.permissions
+			.push(ContractPermission { contract: "*".to_string(), methods: vec!["*".to_string()] });
+		manifest.trusts = vec!["0x23ba2703c53263e8d6e522dc32203339dcd8eee9".to_string()];
+		let json = serde_json::to_value(&manifest).unwrap();
+		assert_eq!(json["supportedstandards"], serde_json::json!(["NEP-17"]));
+		assert_eq!(json["permissions"][1]["methods"], "*");
+		assert_eq!(
+			json["trusts"],
+			serde_json::json!(["0x23ba2703c53263e8d6e522dc32203339dcd8eee9"])
+		);
+
+		let decoded: ContractManifest = serde_json::from_value(json).unwrap();
+		assert_eq!(decoded.supported_standards, manifest.supported_standards);
+		assert_eq!(decoded.permissions, manifest.permissions);
+		assert_eq!(decoded.trusts, manifest.trusts);
+
+		manifest.trusts = vec!["*".to_string()];
+		assert_eq!(serde_json::to_value(&manifest).unwrap()["trusts"], "*");
+	}
+
+	#[test]
+	fn test_node_manifest() {
+		// The manifest of GasToken, as returned by `getcontractstate` on MainNet
+		let json = r#"{
+			"name": "GasToken",
+			"groups": [],
+			"features": {},
+			"supportedstandards": ["NEP-17"],
+			"abi": {
+				"methods": [
+					{"name": "balanceOf", "parameters": [{"name": "account", "type": "Hash160"}], "returntype": "Integer", "offset": 0, "safe": true},
+					{"name": "decimals", "parameters": [], "returntype": "Integer", "offset": 7, "safe": true},
+					{"name": "symbol", "parameters": [], "returntype": "String", "offset": 14, "safe": true},
+					{"name": "totalSupply", "parameters": [], "returntype": "Integer", "offset": 21, "safe": true},
+					{"name": "transfer", "parameters": [{"name": "from", "type": "Hash160"}, {"name": "to", "type": "Hash160"}, {"name": "amount", "type": "Integer"}, {"name": "data", "type": "Any"}], "returntype": "Boolean", "offset": 28, "safe": false}
+				],
+				"events": [
+					{"name": "Transfer", "parameters": [{"name": "from", "type": "Hash160"}, {"name": "to", "type": "Hash160"}, {"name": "amount", "type": "Integer"}]}
+				]
+			},
+			"permissions": [{"contract": "*", "methods": "*"}],
+			"trusts": [],
+			"extra": null
+		}"#;
+		let manifest: ContractManifest = serde_json::from_str(json).unwrap();
+		manifest.validate().unwrap();
+		let abi = manifest.abi.as_ref().unwrap();
+		assert_eq!(abi.methods[4].return_type, ContractParameterType::Boolean);
+		assert_eq!(abi.methods[4].parameters[0].get_type(), ContractParameterType::H160);
+
+		// Serialized with the names of the node
+		let serialized = serde_json::to_value(&manifest).unwrap();
+		assert_eq!(serialized["abi"]["methods"][0]["returntype"], "Integer");
+		assert_eq!(serialized["abi"]["methods"][0]["parameters"][0]["type"], "Hash160");
+		assert_eq!(serialized["trusts"], serde_json::json!([]));
+		let group = ContractGroup { pub_key: "02".repeat(33), signature: String::new() };
+		assert!(serde_json::to_value(group).unwrap().get("pubkey").is_some());
+	}
+
+	#[test]
+	fn test_verify_groups() {
+		let contract_hash = H160::repeat_byte(0x42);
+		let key_pair = KeyPair::new_random();
+		let signature =
+			key_pair.private_key().sign_tx(&contract_hash.to_le_vec()).unwrap().to_bytes();
+
+		let mut manifest = manifest();
+		manifest.groups.push(ContractGroup {
+			pub_key: hex::encode(key_pair.public_key().get_encoded(true)),
+			signature: general_purpose::STANDARD.encode(signature),
+		});
+		manifest.validate().unwrap();
+		manifest.verify_groups(&contract_hash).unwrap();
+		assert!(manifest.verify_groups(&H160::repeat_byte(0x43)).is_err());
+
+		manifest.groups.push(manifest.groups[0].clone());
+		assert!(manifest.validate().is_err());
+	}
+}
@@ -217,6 +217,14 @@ impl ContractParameter {
 		Self { name: None, typ, value: None }
 	}
 
+	pub fn with_name(name: &str, typ: ContractParameterType) -> Self {
+		Self { name: Some(name.to_string()), typ, value: None }
+	}
+
+	pub fn get_name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+
 	pub fn get_type(&self) -> ContractParameterType {
 		self.typ.clone()
 	}
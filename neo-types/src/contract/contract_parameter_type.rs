@@ -27,8 +27,10 @@ pub enum ContractParameterType {
 	#[strum(serialize = "String")]
 	String = 0x13,
 	#[strum(serialize = "H160")]
+	#[serde(rename = "Hash160", alias = "H160")]
 	H160 = 0x14,
 	#[strum(serialize = "H256")]
+	#[serde(rename = "Hash256", alias = "H256")]
 	H256 = 0x15,
 	#[strum(serialize = "PublicKey")]
 	PublicKey = 0x16,
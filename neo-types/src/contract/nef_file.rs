@@ -1,12 +1,10 @@
 use crate::{
 	contract_parameter::ContractParameter, error::TypeError, stack_item::StackItem, Bytes,
 };
-use neo_codec::{encode::NeoSerializable, CodecError, Decoder, Encoder};
+use neo_codec::{encode::NeoSerializable, Decoder};
 use neo_crypto::hash::HashableForVec;
 use primitive_types::H160;
-use serde::{Deserializer, Serializer};
-use std::hash::Hasher;
-use tokio::io::AsyncReadExt;
+use std::path::Path;
 
 /*
 ┌───────────────────────────────────────────────────────────────────────┐
@@ -17,8 +15,8 @@ use tokio::io::AsyncReadExt;
 │ Magic    │ uint32        │ Magic header                               │
 │ Compiler │ byte[64]      │ Compiler name and version                  │
 ├──────────┼───────────────┼────────────────────────────────────────────┤
-│ Source   │ byte[]        │ The url of the source files, max 255 bytes │
-│ Reserve  │ byte[2]       │ Reserved for future extensions. Must be 0. │
+│ Source   │ byte[]        │ The url of the source files, max 256 bytes │
+│ Reserve  │ byte          │ Reserved for future extensions. Must be 0. │
 │ Tokens   │ MethodToken[] │ Method tokens                              │
 │ Reserve  │ byte[2]       │ Reserved for future extensions. Must be 0. │
 │ Script   │ byte[]        │ Var bytes for the payload                  │
@@ -27,12 +25,18 @@ use tokio::io::AsyncReadExt;
 └──────────┴───────────────┴────────────────────────────────────────────┘
  */
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "TypeError", magic = Self::MAGIC, validate = "Self::validate")]
 pub struct NefFile {
+	#[neo(fixed_string = Self::COMPILER_SIZE)]
 	compiler: Option<String>,
+	#[neo(var_string, max = Self::MAX_SOURCE_URL_SIZE)]
 	source_url: String,
+	#[neo(list, max = Self::MAX_METHOD_TOKENS, reserved = 1)]
 	method_tokens: Vec<MethodToken>,
+	#[neo(var_bytes, max = Self::MAX_SCRIPT_LENGTH, reserved = 2)]
 	script: Bytes,
+	#[neo(fixed = Self::CHECKSUM_SIZE)]
 	checksum: Bytes,
 }
 
@@ -48,126 +52,139 @@ impl NefFile {
 	const COMPILER_SIZE: usize = 64;
 	const MAX_SOURCE_URL_SIZE: usize = 256;
 	const MAX_SCRIPT_LENGTH: usize = 512 * 1024;
+	const MAX_METHOD_TOKENS: usize = 128;
 	const CHECKSUM_SIZE: usize = 4;
+	const MAX_FILE_SIZE: usize = 0x100000;
 	pub const HEADER_SIZE: usize = Self::MAGIC_SIZE + Self::COMPILER_SIZE;
 
-	fn get_checksum_as_integer(bytes: &Bytes) -> i32 {
-		let mut bytes = bytes.clone();
-		bytes.reverse();
-		i32::from_be_bytes(bytes.try_into().unwrap())
-	}
-
-	fn compute_checksum(file: &NefFile) -> Bytes {
-		Self::compute_checksum_from_bytes(file.to_array())
-	}
-
-	fn compute_checksum_from_bytes(bytes: Bytes) -> Bytes {
-		let mut file_bytes = bytes.clone();
-		file_bytes.truncate(bytes.len() - Self::CHECKSUM_SIZE);
-		file_bytes.hash256()[..Self::CHECKSUM_SIZE].try_into().unwrap()
-	}
-
-	fn read_from_file(file: &str) -> Result<Self, TypeError> {
-		let file_bytes = std::fs::read(file).unwrap();
-		if file_bytes.len() > 0x100000 {
-			return Err(TypeError::InvalidArgError("NEF file is too large".to_string()))
+	/// Builds a NEF from the output of a compiler and computes its checksum.
+	pub fn new(
+		compiler: &str,
+		source_url: &str,
+		method_tokens: Vec<MethodToken>,
+		script: Bytes,
+	) -> Result<Self, TypeError> {
+		if compiler.len() > Self::COMPILER_SIZE {
+			return Err(TypeError::InvalidArgError(format!(
+				"Compiler name and version can't be longer than {} bytes",
+				Self::COMPILER_SIZE
+			)))
+		}
+		if source_url.len() > Self::MAX_SOURCE_URL_SIZE {
+			return Err(TypeError::InvalidArgError(format!(
+				"Source URL can't be longer than {} bytes",
+				Self::MAX_SOURCE_URL_SIZE
+			)))
+		}
+		if method_tokens.len() > Self::MAX_METHOD_TOKENS {
+			return Err(TypeError::InvalidArgError(format!(
+				"A NEF can't have more than {} method tokens",
+				Self::MAX_METHOD_TOKENS
+			)))
+		}
+		if script.is_empty() || script.len() > Self::MAX_SCRIPT_LENGTH {
+			return Err(TypeError::InvalidArgError("Invalid script length".to_string()))
 		}
 
-		let mut reader = Decoder::new(&file_bytes);
-		let nef = reader.read_serializable().unwrap();
+		let mut nef = Self {
+			compiler: Some(compiler.to_string()),
+			source_url: source_url.to_string(),
+			method_tokens,
+			script,
+			checksum: vec![0; Self::CHECKSUM_SIZE],
+		};
+		nef.checksum = Self::compute_checksum(&nef);
 		Ok(nef)
 	}
 
-	fn read_from_stack_item(item: StackItem) -> Result<Self, TypeError> {
-		if let StackItem::ByteString { value: bytes } = item {
-			let mut reader = Decoder::new(&bytes.as_bytes());
-			let nef = reader.read_serializable().unwrap();
-			Ok(nef)
-		} else {
-			Err(TypeError::UnexpectedReturnType(
-				serde_json::to_string(&item).unwrap() + StackItem::BYTE_STRING_VALUE,
-			))
-		}
+	pub fn compiler(&self) -> Option<&str> {
+		self.compiler.as_deref()
 	}
-}
-
-impl NeoSerializable for NefFile {
-	type Error = TypeError;
 
-	fn size(&self) -> usize {
-		let mut size = Self::HEADER_SIZE;
-		size += self.source_url.len() + 1;
-		size += self.method_tokens.len() + 2;
-		size += self.script.len();
-		size += Self::CHECKSUM_SIZE;
+	pub fn source_url(&self) -> &str {
+		&self.source_url
+	}
 
-		size
+	pub fn method_tokens(&self) -> &[MethodToken] {
+		&self.method_tokens
 	}
 
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_u32(Self::MAGIC);
-		writer
-			.write_fixed_string(&self.compiler, Self::COMPILER_SIZE)
-			.expect("Failed to serialize compiler");
-		writer.write_var_string(&self.source_url);
-		writer.write_u8(0);
-		writer.write_serializable_variable_list(&self.method_tokens);
-		writer.write_u16(0);
-		writer.write_var_bytes(&self.script);
-		writer.write_bytes(&self.checksum);
+	pub fn script(&self) -> &Bytes {
+		&self.script
 	}
 
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let magic = reader.read_u32();
-		if magic != Self::MAGIC {
-			return Err(TypeError::InvalidEncoding("Invalid magic".to_string()))
-		}
+	pub fn checksum(&self) -> &Bytes {
+		&self.checksum
+	}
 
-		let compiler_bytes = reader.read_bytes(Self::COMPILER_SIZE)?;
-		let compiler = String::from_utf8(compiler_bytes.to_vec())
-			.map_err(|_| CodecError::InvalidEncoding("Invalid compiler".to_string()))?;
+	/// The checksum as the little-endian integer the node reports in `getcontractstate`.
+	pub fn checksum_as_integer(&self) -> u32 {
+		u32::from_le_bytes(self.checksum[..].try_into().unwrap())
+	}
 
-		let source_url = reader.read_var_string()?;
-		if source_url.len() > Self::MAX_SOURCE_URL_SIZE {
-			return Err(TypeError::InvalidEncoding("Invalid source url".to_string()))
-		}
+	/// Computes the checksum of `file`, the first 4 bytes of the double SHA-256 of everything
+	/// before the checksum.
+	fn compute_checksum(file: &NefFile) -> Bytes {
+		let mut bytes = file.to_array();
+		bytes.truncate(bytes.len() - Self::CHECKSUM_SIZE);
+		bytes.hash256().hash256()[..Self::CHECKSUM_SIZE].to_vec()
+	}
 
-		if reader.read_u8() != 0 {
-			return Err(TypeError::InvalidEncoding("Invalid reserve bytes".to_string()))
+	fn validate(&self) -> Result<(), TypeError> {
+		if self.script.is_empty() {
+			return Err(TypeError::InvalidEncoding("Invalid script".to_string()))
 		}
-
-		let method_tokens = reader.read_serializable_list()?;
-
-		if reader.read_u16() != 0 {
-			return Err(TypeError::InvalidEncoding("Invalid reserve bytes".to_string()))
+		if self.checksum != Self::compute_checksum(self) {
+			return Err(TypeError::InvalidEncoding("Invalid checksum".to_string()))
 		}
+		Ok(())
+	}
 
-		let script = reader.read_var_bytes()?;
-		if script.is_empty() {
-			return Err(TypeError::InvalidEncoding("Invalid script".to_string()))
+	/// Decodes a NEF, rejecting trailing bytes.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, TypeError> {
+		if bytes.len() > Self::MAX_FILE_SIZE {
+			return Err(TypeError::InvalidArgError("NEF file is too large".to_string()))
 		}
-
-		let file =
-			Self { compiler: Some(compiler), source_url, method_tokens, script, checksum: vec![] };
-
-		let checksum = reader.read_bytes(Self::CHECKSUM_SIZE)?;
-		if checksum != Self::compute_checksum(&file) {
-			return Err(TypeError::InvalidEncoding("Invalid checksum".to_string()))
+		let mut reader = Decoder::new(bytes);
+		let nef = Self::decode(&mut reader)?;
+		if reader.available() > 0 {
+			return Err(TypeError::InvalidEncoding("Trailing bytes after NEF".to_string()))
 		}
+		Ok(nef)
+	}
 
-		Ok(file)
+	/// Reads a `.nef` file, e.g. one written by the compiler.
+	pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, TypeError> {
+		let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+			TypeError::InvalidArgError(format!(
+				"Can't read NEF file {}: {}",
+				path.as_ref().display(),
+				e
+			))
+		})?;
+		Self::from_bytes(&bytes)
 	}
 
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+	pub fn read_from_stack_item(item: StackItem) -> Result<Self, TypeError> {
+		if let StackItem::ByteString { .. } = item {
+			let bytes = item
+				.as_bytes()
+				.ok_or_else(|| TypeError::InvalidEncoding("Invalid byte string".to_string()))?;
+			Self::from_bytes(&bytes)
+		} else {
+			Err(TypeError::UnexpectedReturnType(
+				serde_json::to_string(&item).unwrap() + StackItem::BYTE_STRING_VALUE,
+			))
+		}
 	}
 }
 
-#[derive(Debug, Clone)]
+/// A static call to another contract, resolved when the contract is deployed.
+#[derive(Debug, Clone, PartialEq, Eq, NeoSerializable)]
+#[neo(error = "TypeError", validate = "Self::validate")]
 pub struct MethodToken {
 	hash: H160,
+	#[neo(var_string, max = Self::MAX_METHOD_LENGTH)]
 	method: String,
 	params_count: u16,
 	has_return_value: bool,
@@ -175,48 +192,136 @@ pub struct MethodToken {
 }
 
 impl MethodToken {
-	const PARAMS_COUNT_SIZE: usize = 2;
-	const HAS_RETURN_VALUE_SIZE: usize = 1;
-	const CALL_FLAGS_SIZE: usize = 1;
+	const MAX_METHOD_LENGTH: usize = 32;
+	/// `CallFlags::All`, every valid flag.
+	const ALL_CALL_FLAGS: u8 = 0x0f;
+
+	pub fn new(
+		hash: H160,
+		method: &str,
+		params_count: u16,
+		has_return_value: bool,
+		call_flags: u8,
+	) -> Result<Self, TypeError> {
+		let token =
+			Self { hash, method: method.to_string(), params_count, has_return_value, call_flags };
+		if method.len() > Self::MAX_METHOD_LENGTH {
+			return Err(TypeError::InvalidArgError(format!(
+				"Method name can't be longer than {} bytes",
+				Self::MAX_METHOD_LENGTH
+			)))
+		}
+		token.validate()?;
+		Ok(token)
+	}
+
+	pub fn hash(&self) -> H160 {
+		self.hash
+	}
+
+	pub fn method(&self) -> &str {
+		&self.method
+	}
+
+	pub fn params_count(&self) -> u16 {
+		self.params_count
+	}
+
+	pub fn has_return_value(&self) -> bool {
+		self.has_return_value
+	}
+
+	pub fn call_flags(&self) -> u8 {
+		self.call_flags
+	}
+
+	fn validate(&self) -> Result<(), TypeError> {
+		if self.method.starts_with('_') {
+			return Err(TypeError::InvalidEncoding("Method name can't start with '_'".to_string()))
+		}
+		if self.call_flags & !Self::ALL_CALL_FLAGS != 0 {
+			return Err(TypeError::InvalidEncoding("Invalid call flags".to_string()))
+		}
+		Ok(())
+	}
 }
 
-impl NeoSerializable for MethodToken {
-	type Error = TypeError;
+#[cfg(test)]
+mod tests {
+	use super::*;
 
-	fn size(&self) -> usize {
-		let mut size = H160::len_bytes();
-		size += self.method.len();
-		size += MethodToken::PARAMS_COUNT_SIZE;
-		size += MethodToken::HAS_RETURN_VALUE_SIZE;
-		size += MethodToken::CALL_FLAGS_SIZE;
+	fn token() -> MethodToken {
+		MethodToken::new(H160::repeat_byte(0xab), "transfer", 4, true, 0x0f).unwrap()
+	}
 
-		size
+	#[test]
+	fn test_new_nef_file() {
+		let nef = NefFile::new("neon", "", vec![], vec![0x40]).unwrap();
+		assert_eq!(nef.checksum(), &hex::decode("cdc2134c").unwrap());
+		assert_eq!(nef.checksum_as_integer(), 1276363469);
+
+		let bytes = nef.to_array();
+		assert_eq!(bytes.len(), nef.size());
+		assert_eq!(&bytes[..4], b"NEF3");
+		assert_eq!(&bytes[4..8], b"neon");
+		assert_eq!(&bytes[68..], &hex::decode("00000000000140cdc2134c").unwrap()[..]);
 	}
 
-	fn encode(&self, writer: &mut Encoder) {
-		writer.write_serializable_fixed(&self.hash);
-		writer.write_var_string(&self.method);
-		writer.write_u16(self.params_count);
-		writer.write_bool(self.has_return_value);
-		writer.write_u8(self.call_flags);
+	#[test]
+	fn test_nef_file_round_trip() {
+		let nef = NefFile::new("neon", "", vec![token()], vec![0x40]).unwrap();
+		assert_eq!(nef.checksum(), &hex::decode("60b594ca").unwrap());
+
+		let bytes = nef.to_array();
+		assert_eq!(bytes.len(), 112);
+		let decoded = NefFile::from_bytes(&bytes).unwrap();
+		assert_eq!(decoded, nef);
+		assert_eq!(decoded.compiler(), Some("neon"));
+		assert_eq!(decoded.method_tokens(), &[token()]);
+		assert_eq!(decoded.script(), &vec![0x40]);
+
+		let path = std::env::temp_dir().join("neo_types_test_nef_file_round_trip.nef");
+		std::fs::write(&path, &bytes).unwrap();
+		assert_eq!(NefFile::read_from_file(&path).unwrap(), nef);
+		std::fs::remove_file(&path).unwrap();
 	}
 
-	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
-	where
-		Self: Sized,
-	{
-		let hash = reader.read_serializable()?;
-		let method = reader.read_var_string()?;
-		let params_count = reader.read_u16();
-		let has_return_value = reader.read_bool();
-		let call_flags = reader.read_u8();
+	#[test]
+	fn test_nef_file_rejects_invalid_input() {
+		let bytes = NefFile::new("neon", "", vec![token()], vec![0x40]).unwrap().to_array();
+
+		let mut bad_magic = bytes.clone();
+		bad_magic[0] = 0;
+		assert!(NefFile::from_bytes(&bad_magic).is_err());
+
+		let mut bad_checksum = bytes.clone();
+		*bad_checksum.last_mut().unwrap() ^= 1;
+		assert!(NefFile::from_bytes(&bad_checksum).is_err());
+
+		let mut bad_script = bytes.clone();
+		bad_script[bytes.len() - 5] = 0x41;
+		assert!(NefFile::from_bytes(&bad_script).is_err());
 
-		Ok(Self { hash, method, params_count, has_return_value, call_flags })
+		let mut trailing = bytes.clone();
+		trailing.push(0);
+		assert!(NefFile::from_bytes(&trailing).is_err());
+
+		for len in 0..bytes.len() {
+			assert!(NefFile::from_bytes(&bytes[..len]).is_err());
+		}
+
+		assert!(NefFile::read_from_file("/nonexistent/contract.nef").is_err());
 	}
 
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
+	#[test]
+	fn test_new_rejects_invalid_arguments() {
+		assert!(NefFile::new(&"a".repeat(65), "", vec![], vec![0x40]).is_err());
+		assert!(NefFile::new("neon", &"a".repeat(257), vec![], vec![0x40]).is_err());
+		assert!(NefFile::new("neon", "", vec![], vec![]).is_err());
+		assert!(NefFile::new("neon", "", vec![token(); 129], vec![0x40]).is_err());
+
+		assert!(MethodToken::new(H160::zero(), "_deploy", 0, false, 0x0f).is_err());
+		assert!(MethodToken::new(H160::zero(), "transfer", 0, false, 0x10).is_err());
+		assert!(MethodToken::new(H160::zero(), &"a".repeat(33), 0, false, 0x0f).is_err());
 	}
 }
@@ -46,6 +46,8 @@ pub enum TypeError {
 	InvalidArgError(String),
 	#[error("Invalid neo name {0}")]
 	InvalidNeoName(String),
+	#[error("Invalid manifest: {0}")]
+	InvalidManifest(String),
 	#[error("Numeric overflow")]
 	NumericOverflow,
 	#[error("Wif error {0}")]
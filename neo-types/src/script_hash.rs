@@ -1,4 +1,4 @@
-use crate::error::TypeError;
+use crate::{error::TypeError, op_code::OpCode};
 use hex::FromHexError;
 use neo_config::DEFAULT_ADDRESS_VERSION;
 use neo_crypto::hash::HashableForVec;
@@ -46,6 +46,13 @@ where
 
 	/// Creates an instance from a script byte slice.
 	fn from_script(script: &[u8]) -> Self;
+
+	/// Computes the hash of a contract deployed by `sender` with the given NEF checksum and
+	/// manifest name, the same way `ContractManagement` does on the node.
+	///
+	/// The hash is that of the script `ABORT; PUSH sender; PUSH nef_checksum; PUSH name`, so it
+	/// is known before the deployment and stays the same across updates.
+	fn from_contract(sender: &H160, nef_checksum: u32, name: &str) -> Self;
 }
 
 impl ScriptHashExtension for H160 {
@@ -92,7 +99,7 @@ impl ScriptHashExtension for H160 {
 
 	fn to_address(&self) -> String {
 		let mut data = vec![DEFAULT_ADDRESS_VERSION];
-		data.extend_from_slice(&self.0);
+		data.extend_from_slice(&self.to_le_vec());
 		let mut sha = &data.hash256().hash256();
 		data.extend_from_slice(&sha[..4]);
 		bs58::encode(data).into_string()
@@ -115,6 +122,41 @@ impl ScriptHashExtension for H160 {
 		arr.copy_from_slice(&hash);
 		Self(arr)
 	}
+
+	fn from_contract(sender: &H160, nef_checksum: u32, name: &str) -> Self {
+		let mut script = vec![OpCode::Abort as u8];
+		push_data(&mut script, &sender.to_le_vec());
+		push_integer(&mut script, nef_checksum);
+		push_data(&mut script, name.as_bytes());
+		Self::from_script(&script)
+	}
+}
+
+/// Appends the push of `data` a `ScriptBuilder` would emit.
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+	match u8::try_from(data.len()) {
+		Ok(len) => script.extend([OpCode::PushData1 as u8, len]),
+		Err(_) => {
+			script.push(OpCode::PushData2 as u8);
+			script.extend((data.len() as u16).to_le_bytes());
+		},
+	}
+	script.extend_from_slice(data);
+}
+
+/// Appends the shortest push of `value` a `ScriptBuilder` would emit. Integers are signed on the
+/// stack, so values with the high bit set need the next larger size.
+fn push_integer(script: &mut Vec<u8>, value: u32) {
+	let bytes = (value as u64).to_le_bytes();
+	let (op_code, len) = match value {
+		0..=16 => return script.push(OpCode::Push0 as u8 + value as u8),
+		0x11..=0x7f => (OpCode::PushInt8, 1),
+		0x80..=0x7fff => (OpCode::PushInt16, 2),
+		0x8000..=0x7fff_ffff => (OpCode::PushInt32, 4),
+		_ => (OpCode::PushInt64, 8),
+	};
+	script.push(op_code as u8);
+	script.extend_from_slice(&bytes[..len]);
 }
 
 #[cfg(test)]
@@ -185,6 +227,56 @@ mod tests {
 		assert_eq!(hash.to_le_vec(), expected);
 	}
 
+	#[test]
+	fn test_to_address() {
+		let hash = H160::from_hex("d6c712eb53b1a130f59fd4e5864bdac27458a509").unwrap();
+		assert_eq!(hash.to_address(), "NLnyLtep7jwyq1qhNPkwXbJpurC4jUT8ke");
+		assert_eq!(H160::from_address(&hash.to_address()).unwrap(), hash);
+	}
+
+	#[test]
+	fn test_from_contract() {
+		// Native contracts are deployed by the zero address with a zero checksum.
+		for (name, expected) in [
+			("NeoToken", "ef4073a0f2b305a38ec4050e4d3d28bc40ea63f5"),
+			("GasToken", "d2a4cff31913016155e38e474a2c06d08be276cf"),
+			("PolicyContract", "cc5e4edd9f5f8dba8bb65734541df7a1c081c67b"),
+			("ContractManagement", "fffdc93764dbaddd97c48f252a53ea4643faa3fd"),
+			("StdLib", "acce6fd80d44e1796aa0c2c625e9e4e0ce39efc0"),
+		] {
+			assert_eq!(
+				H160::from_contract(&H160::zero(), 0, name),
+				H160::from_hex(expected).unwrap()
+			);
+		}
+
+		let sender = H160::from_hex("23ba2703c53263e8d6e522dc32203339dcd8eee9").unwrap();
+		assert_eq!(
+			H160::from_contract(&sender, 1234, "Test"),
+			H160::from_hex("1d94203a24736bf4dac2e9df9d4b9663cac17b58").unwrap()
+		);
+		assert_eq!(
+			H160::from_contract(&sender, 3341295436, "Test"),
+			H160::from_hex("753104f2576dc4362ba0a75a77fc3886e8bbf75b").unwrap()
+		);
+	}
+
+	#[test]
+	fn test_push_integer() {
+		for (value, expected) in [
+			(0, "10"),
+			(16, "20"),
+			(17, "0011"),
+			(0x80, "018000"),
+			(0x8000, "0200800000"),
+			(0x8000_0000, "030000008000000000"),
+		] {
+			let mut script = vec![];
+			push_integer(&mut script, value);
+			assert_eq!(hex::encode(script), expected);
+		}
+	}
+
 	#[test]
 	// #[should_panic]
 	fn test_from_invalid_address() {
@@ -125,12 +125,18 @@ pub fn deserialize_wildcard<'de, D>(deserializer: D) -> Result<Vec<String>, D::E
 where
 	D: Deserializer<'de>,
 {
-	let s: String = Deserialize::deserialize(deserializer)?;
-	if s == "*" {
-		Ok(vec!["*".to_string()])
-	} else {
-		Ok(vec![s])
-	}
+	// Either the `*` wildcard or a list, like `supportedstandards`
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Wildcard {
+		Wildcard(String),
+		List(Vec<String>),
+	}
+
+	Ok(match Wildcard::deserialize(deserializer)? {
+		Wildcard::Wildcard(wildcard) => vec![wildcard],
+		Wildcard::List(list) => list,
+	})
 }
 
 pub fn serialize_u256<S>(item: &U256, serializer: S) -> Result<S::Ok, S::Error>
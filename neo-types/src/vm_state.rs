@@ -18,11 +18,15 @@ use strum_macros::{Display, EnumString};
 #[repr(u8)]
 pub enum VMState {
 	#[strum(serialize = "NONE")]
+	#[serde(rename = "NONE")]
 	None = 0,
 	#[strum(serialize = "HALT")]
+	#[serde(rename = "HALT")]
 	Halt = 1,
 	#[strum(serialize = "FAULT")]
+	#[serde(rename = "FAULT")]
 	Fault = 2,
 	#[strum(serialize = "BREAK")]
+	#[serde(rename = "BREAK")]
 	Break = 4,
 }